@@ -1,11 +1,20 @@
 use argon2::Error as ArgonError;
 use reqwest::Error as ReqwestError;
 use reqwest_middleware::Error as MiddlewareReqwestError;
+use serde::Serialize;
 use std::num;
 use tracing::{event, instrument, Level};
+use utoipa::ToSchema;
 use warp::{
-    filters::body::BodyDeserializeError, filters::cors::CorsForbidden, http::StatusCode,
-    reject::Reject, reply::Reply, Rejection,
+    filters::body::BodyDeserializeError,
+    filters::cors::CorsForbidden,
+    http::StatusCode,
+    reject::{
+        InvalidHeader, InvalidQuery, LengthRequired, MethodNotAllowed,
+        MissingHeader, PayloadTooLarge, Reject, UnsupportedMediaType,
+    },
+    reply::{Reply, Response},
+    Rejection,
 };
 
 #[derive(Debug)]
@@ -14,7 +23,18 @@ pub enum Error {
     WrongPassword,
     ArgonLibraryError(ArgonError),
     CannotDecryptToken,
+    /// Missing or invalid token
     Unauthorized,
+    /// Valid token, but the account may not act on the resource
+    Forbidden,
+    /// The account lacks the reputation the action requires
+    InsufficientReputation(i32),
+    ModeratorRequired,
+    AdminRequired,
+    QuestionClosed,
+    QuestionLocked,
+    /// The question can't change from the first status to the second
+    InvalidStatusTransition(String, String),
     ParseInt(num::ParseIntError),
     DatabaseQueryError(sqlx::Error),
     MigrationError(sqlx::migrate::MigrateError),
@@ -22,6 +42,13 @@ pub enum Error {
     MiddlewareReqwestAPIError(MiddlewareReqwestError),
     ClientError(APILayerError),
     ServerError(APILayerError),
+    ValidationError(Vec<FieldError>),
+    PreconditionRequired,
+    PreconditionFailed,
+    UnsupportedMediaType(String),
+    InvalidBody(String),
+    /// The blob store of the attachments failed
+    StorageError(String),
 }
 
 #[derive(Debug, Clone)]
@@ -36,21 +63,242 @@ impl std::fmt::Display for APILayerError {
     }
 }
 
+/// A single field of a request payload that failed validation
+#[derive(Serialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for FieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match &*self {
-            Error::ParseInt(err) => write!(f, "Cannot parse parameter: {}", err),
-            Error::MissingParameters(message) => write!(f, "Missing parameters: {}", message),
+        match self {
+            Error::ParseInt(err) => {
+                write!(f, "Cannot parse parameter: {}", err)
+            }
+            Error::MissingParameters(message) => {
+                write!(f, "Missing parameters: {}", message)
+            }
             Error::WrongPassword => write!(f, "Wrong password!"),
-            Error::ArgonLibraryError(_) => write!(f, "Cannot verify password"),
-            Error::CannotDecryptToken => write!(f, "Cannot decrypt token!"),
-            Error::Unauthorized => write!(f, "No permission to change the underlying resource!"),
-            Error::DatabaseQueryError(_) => write!(f, "Cannot update, invalid data!"),
+            Error::ArgonLibraryError(_) => {
+                write!(f, "Cannot verify password")
+            }
+            Error::CannotDecryptToken => {
+                write!(f, "Cannot decrypt token!")
+            }
+            Error::Unauthorized => {
+                write!(f, "Missing or invalid authorization token!")
+            }
+            Error::Forbidden => write!(
+                f,
+                "No permission to change the underlying resource!"
+            ),
+            Error::ModeratorRequired => {
+                write!(f, "Only moderators can perform this action!")
+            }
+            Error::AdminRequired => {
+                write!(f, "Only administrators can perform this action!")
+            }
+            Error::QuestionClosed => {
+                write!(f, "The question is closed to new answers!")
+            }
+            Error::QuestionLocked => {
+                write!(f, "The question is locked and cannot change!")
+            }
+            Error::InvalidStatusTransition(from, to) => write!(
+                f,
+                "The status of the question cannot change from {} to {}!",
+                from, to
+            ),
+            Error::InsufficientReputation(required) => write!(
+                f,
+                "At least {} reputation is required for this action!",
+                required
+            ),
+            Error::DatabaseQueryError(err) => {
+                match database_error_kind(err) {
+                    DatabaseErrorKind::NotFound => {
+                        write!(f, "The requested resource was not found!")
+                    }
+                    DatabaseErrorKind::AlreadyExists => {
+                        write!(f, "The resource already exists!")
+                    }
+                    DatabaseErrorKind::InvalidReference => {
+                        write!(
+                            f,
+                            "The data references a missing resource!"
+                        )
+                    }
+                    DatabaseErrorKind::Other => {
+                        write!(f, "Cannot process the database query!")
+                    }
+                }
+            }
             Error::MigrationError(_) => write!(f, "Cannot migrate data!"),
-            Error::ReqwestAPIError(err) => write!(f, "External API error: {}", err),
-            Error::MiddlewareReqwestAPIError(err) => write!(f, "External API error: {}", err),
-            Error::ClientError(err) => write!(f, "External Client error: {}", err),
-            Error::ServerError(err) => write!(f, "External Server error: {}", err),
+            Error::ReqwestAPIError(err) => {
+                write!(f, "External API error: {}", err)
+            }
+            Error::MiddlewareReqwestAPIError(err) => {
+                write!(f, "External API error: {}", err)
+            }
+            Error::ClientError(err) => {
+                write!(f, "External Client error: {}", err)
+            }
+            Error::ServerError(err) => {
+                write!(f, "External Server error: {}", err)
+            }
+            Error::ValidationError(errors) => {
+                let errors = errors
+                    .iter()
+                    .map(|e| e.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                write!(f, "Invalid request data: {}", errors)
+            }
+            Error::PreconditionRequired => {
+                write!(f, "The If-Match header is required!")
+            }
+            Error::PreconditionFailed => write!(
+                f,
+                "The resource was modified since it was last fetched!"
+            ),
+            Error::UnsupportedMediaType(expected) => {
+                write!(f, "Content-Type must be {}", expected)
+            }
+            Error::InvalidBody(message) => {
+                write!(f, "Request body deserialize error: {}", message)
+            }
+            Error::StorageError(message) => {
+                write!(f, "Storage error: {}", message)
+            }
+        }
+    }
+}
+
+impl Error {
+    /// Stable, machine-readable identifier of the error,
+    /// returned as the `code` member of the problem details body
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::MissingParameters(_) => "missing_parameters",
+            Error::WrongPassword => "wrong_password",
+            Error::ArgonLibraryError(_) => "password_verification_failed",
+            Error::CannotDecryptToken => "invalid_token",
+            Error::Unauthorized => "unauthorized",
+            Error::Forbidden => "forbidden",
+            Error::InsufficientReputation(_) => "insufficient_reputation",
+            Error::ModeratorRequired => "moderator_required",
+            Error::AdminRequired => "admin_required",
+            Error::QuestionClosed => "question_closed",
+            Error::QuestionLocked => "question_locked",
+            Error::InvalidStatusTransition(_, _) => {
+                "invalid_status_transition"
+            }
+            Error::ParseInt(_) => "invalid_parameter",
+            Error::DatabaseQueryError(err) => {
+                match database_error_kind(err) {
+                    DatabaseErrorKind::NotFound => "not_found",
+                    DatabaseErrorKind::AlreadyExists => "already_exists",
+                    DatabaseErrorKind::InvalidReference => {
+                        "invalid_reference"
+                    }
+                    DatabaseErrorKind::Other => "database_error",
+                }
+            }
+            Error::MigrationError(_) => "migration_failed",
+            Error::ReqwestAPIError(_) => "upstream_invalid_response",
+            Error::MiddlewareReqwestAPIError(_) => "upstream_unavailable",
+            Error::ClientError(_) => "upstream_client_error",
+            Error::ServerError(_) => "upstream_server_error",
+            Error::ValidationError(_) => "validation_failed",
+            Error::PreconditionRequired => "precondition_required",
+            Error::PreconditionFailed => "precondition_failed",
+            Error::UnsupportedMediaType(_) => "unsupported_media_type",
+            Error::InvalidBody(_) => "invalid_body",
+            Error::StorageError(_) => "storage_error",
+        }
+    }
+
+    /// HTTP status code the error is answered with
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Error::MissingParameters(_) | Error::ParseInt(_) => {
+                StatusCode::BAD_REQUEST
+            }
+            Error::WrongPassword
+            | Error::CannotDecryptToken
+            | Error::Unauthorized => StatusCode::UNAUTHORIZED,
+            Error::Forbidden
+            | Error::InsufficientReputation(_)
+            | Error::ModeratorRequired
+            | Error::AdminRequired => StatusCode::FORBIDDEN,
+            Error::QuestionClosed
+            | Error::QuestionLocked
+            | Error::InvalidStatusTransition(_, _) => StatusCode::CONFLICT,
+            Error::ArgonLibraryError(_)
+            | Error::MigrationError(_)
+            | Error::StorageError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::DatabaseQueryError(err) => {
+                match database_error_kind(err) {
+                    DatabaseErrorKind::NotFound => StatusCode::NOT_FOUND,
+                    DatabaseErrorKind::AlreadyExists => {
+                        StatusCode::CONFLICT
+                    }
+                    DatabaseErrorKind::InvalidReference => {
+                        StatusCode::UNPROCESSABLE_ENTITY
+                    }
+                    DatabaseErrorKind::Other => {
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    }
+                }
+            }
+            Error::ReqwestAPIError(_)
+            | Error::ClientError(_)
+            | Error::ServerError(_) => StatusCode::BAD_GATEWAY,
+            Error::MiddlewareReqwestAPIError(_) => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            Error::ValidationError(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::PreconditionRequired => {
+                StatusCode::PRECONDITION_REQUIRED
+            }
+            Error::PreconditionFailed => StatusCode::PRECONDITION_FAILED,
+            Error::UnsupportedMediaType(_) => {
+                StatusCode::UNSUPPORTED_MEDIA_TYPE
+            }
+            Error::InvalidBody(_) => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    /// Human-readable explanation sent to the client. Errors caused by
+    /// the server or an upstream service don't expose their internals.
+    fn detail(&self) -> String {
+        match self {
+            Error::ReqwestAPIError(_)
+            | Error::ClientError(_)
+            | Error::ServerError(_) => {
+                "The content moderation service returned an invalid response"
+                    .to_string()
+            }
+            Error::MiddlewareReqwestAPIError(_) => {
+                "The content moderation service is unavailable".to_string()
+            }
+            Error::WrongPassword => {
+                "Wrong e-mail/password combination!".to_string()
+            }
+            Error::ValidationError(_) => {
+                "The request data did not pass validation".to_string()
+            }
+            Error::StorageError(_) => {
+                "The file storage is unavailable".to_string()
+            }
+            _ => self.to_string(),
         }
     }
 }
@@ -58,95 +306,283 @@ impl std::fmt::Display for Error {
 impl Reject for Error {}
 impl Reject for APILayerError {}
 
-const DUPLICATED_KEY: u32 = 23505;
+enum DatabaseErrorKind {
+    NotFound,
+    AlreadyExists,
+    InvalidReference,
+    Other,
+}
+
+fn database_error_kind(err: &sqlx::Error) -> DatabaseErrorKind {
+    match err {
+        sqlx::Error::RowNotFound => DatabaseErrorKind::NotFound,
+        sqlx::Error::Database(err) if err.is_unique_violation() => {
+            DatabaseErrorKind::AlreadyExists
+        }
+        sqlx::Error::Database(err) if err.is_foreign_key_violation() => {
+            DatabaseErrorKind::InvalidReference
+        }
+        _ => DatabaseErrorKind::Other,
+    }
+}
+
+tokio::task_local! {
+    /// Id of the request being served, set by the server for every
+    /// request
+    pub static REQUEST_ID: String;
+}
+
+/// Id of the request being served, the one of its trace span. A new id
+/// outside of a request.
+pub fn request_id() -> String {
+    REQUEST_ID
+        .try_with(String::clone)
+        .unwrap_or_else(|_| uuid::Uuid::new_v4().to_string())
+}
+
+/// Problem details body as described in RFC 7807
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    pub code: String,
+    pub request_id: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+impl Problem {
+    pub fn new(status: StatusCode, code: &str, detail: String) -> Self {
+        Problem {
+            problem_type: "about:blank".to_string(),
+            title: status
+                .canonical_reason()
+                .unwrap_or("Unknown Error")
+                .to_string(),
+            status: status.as_u16(),
+            detail,
+            code: code.to_string(),
+            request_id: request_id(),
+            errors: Vec::new(),
+        }
+    }
+}
+
+impl Reply for Problem {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.status)
+            .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let reply = warp::reply::with_header(
+            warp::reply::json(&self),
+            "content-type",
+            PROBLEM_CONTENT_TYPE,
+        );
+        warp::reply::with_status(reply, status).into_response()
+    }
+}
 
 #[instrument]
 pub async fn return_error(r: Rejection) -> Result<impl Reply, Rejection> {
-    if let Some(Error::DatabaseQueryError(e)) = r.find() {
-        event!(Level::ERROR, "Database query error");
-        match e {
-            sqlx::Error::Database(err) => {
-                event!(
-                    Level::ERROR,
-                    "sqlx_error code: {:?}",
-                    err.code().unwrap().parse::<u32>().unwrap()
-                );
-                if err.code().unwrap().parse::<u32>().unwrap() == DUPLICATED_KEY {
-                    Ok(warp::reply::with_status(
-                        "Account already exists!".to_string(),
-                        StatusCode::UNPROCESSABLE_ENTITY,
-                    ))
-                } else {
-                    Ok(warp::reply::with_status(
-                        "Cannot process data".to_string(),
-                        StatusCode::UNPROCESSABLE_ENTITY,
-                    ))
-                }
-            }
-            _ => Ok(warp::reply::with_status(
-                "Cannot process data".to_string(),
-                StatusCode::UNPROCESSABLE_ENTITY,
-            )),
+    let problem = if let Some(error) = r.find::<Error>() {
+        let mut problem =
+            Problem::new(error.status(), error.code(), error.detail());
+        if let Error::ValidationError(errors) = error {
+            problem.errors = errors.clone();
         }
-    } else if let Some(Error::ReqwestAPIError(e)) = r.find() {
-        event!(Level::ERROR, "{}", e);
-        Ok(warp::reply::with_status(
-            "Internal Server Error".to_string(),
-            StatusCode::INTERNAL_SERVER_ERROR,
-        ))
-    } else if let Some(Error::Unauthorized) = r.find() {
-        event!(Level::ERROR, "Not matching account id");
-        Ok(warp::reply::with_status(
-            "No permission to change the underlying resource".to_string(),
-            StatusCode::UNAUTHORIZED,
-        ))
-    } else if let Some(Error::WrongPassword) = r.find() {
-        event!(Level::ERROR, "Entered wrong password!");
-        Ok(warp::reply::with_status(
-            "Wrong e-mail/password combination!".to_string(),
-            StatusCode::UNAUTHORIZED,
-        ))
-    } else if let Some(Error::MiddlewareReqwestAPIError(e)) = r.find() {
-        event!(Level::ERROR, "{}", e);
-        Ok(warp::reply::with_status(
-            "Internal Server Error".to_string(),
-            StatusCode::INTERNAL_SERVER_ERROR,
-        ))
-    } else if let Some(Error::ClientError(e)) = r.find() {
-        event!(Level::ERROR, "{}", e);
-        Ok(warp::reply::with_status(
-            "Internal Server Error".to_string(),
-            StatusCode::INTERNAL_SERVER_ERROR,
-        ))
-    } else if let Some(Error::ServerError(e)) = r.find() {
-        event!(Level::ERROR, "{}", e);
-        Ok(warp::reply::with_status(
-            "Internal Server Error".to_string(),
-            StatusCode::INTERNAL_SERVER_ERROR,
-        ))
+        problem
     } else if let Some(error) = r.find::<CorsForbidden>() {
-        event!(Level::ERROR, "CORS forbidden error: {}", error);
-        Ok(warp::reply::with_status(
-            error.to_string(),
+        Problem::new(
             StatusCode::FORBIDDEN,
-        ))
+            "cors_forbidden",
+            error.to_string(),
+        )
     } else if let Some(error) = r.find::<BodyDeserializeError>() {
-        event!(Level::ERROR, "Cannot deserizalize request body: {}", error);
-        Ok(warp::reply::with_status(
+        Problem::new(
+            StatusCode::BAD_REQUEST,
+            "invalid_body",
             error.to_string(),
-            StatusCode::UNPROCESSABLE_ENTITY,
-        ))
-    } else if let Some(error) = r.find::<Error>() {
-        event!(Level::ERROR, "{}", error);
-        Ok(warp::reply::with_status(
+        )
+    } else if let Some(error) = r.find::<InvalidQuery>() {
+        Problem::new(
+            StatusCode::BAD_REQUEST,
+            "invalid_query",
             error.to_string(),
-            StatusCode::UNPROCESSABLE_ENTITY,
-        ))
-    } else {
-        event!(Level::WARN, "Requested route was not found");
-        Ok(warp::reply::with_status(
-            "Route not found".to_string(),
+        )
+    } else if let Some(error) = r.find::<MissingHeader>() {
+        if error.name() == "Authorization" {
+            Problem::new(
+                StatusCode::UNAUTHORIZED,
+                "missing_token",
+                error.to_string(),
+            )
+        } else {
+            Problem::new(
+                StatusCode::BAD_REQUEST,
+                "missing_header",
+                error.to_string(),
+            )
+        }
+    } else if let Some(error) = r.find::<InvalidHeader>() {
+        Problem::new(
+            StatusCode::BAD_REQUEST,
+            "invalid_header",
+            error.to_string(),
+        )
+    } else if let Some(error) = r.find::<LengthRequired>() {
+        Problem::new(
+            StatusCode::LENGTH_REQUIRED,
+            "length_required",
+            error.to_string(),
+        )
+    } else if let Some(error) = r.find::<PayloadTooLarge>() {
+        Problem::new(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "payload_too_large",
+            error.to_string(),
+        )
+    } else if let Some(error) = r.find::<UnsupportedMediaType>() {
+        Problem::new(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "unsupported_media_type",
+            error.to_string(),
+        )
+    } else if let Some(error) = r.find::<MethodNotAllowed>() {
+        Problem::new(
+            StatusCode::METHOD_NOT_ALLOWED,
+            "method_not_allowed",
+            error.to_string(),
+        )
+    } else if r.is_not_found() {
+        Problem::new(
             StatusCode::NOT_FOUND,
-        ))
+            "route_not_found",
+            "Route not found".to_string(),
+        )
+    } else {
+        Problem::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "internal_error",
+            "Unhandled rejection".to_string(),
+        )
+    };
+
+    if let Some(error) = r.find::<Error>() {
+        event!(
+            Level::ERROR,
+            code = problem.code,
+            request_id = problem.request_id,
+            "{:?}",
+            error
+        );
+    } else if problem.status >= 500 {
+        event!(
+            Level::ERROR,
+            code = problem.code,
+            request_id = problem.request_id,
+            "{:?}",
+            r
+        );
+    } else {
+        event!(
+            Level::WARN,
+            code = problem.code,
+            request_id = problem.request_id,
+            "{}",
+            problem.detail
+        );
+    }
+
+    Ok(problem)
+}
+
+#[cfg(test)]
+mod handle_errors_tests {
+    use super::*;
+
+    #[test]
+    fn row_not_found_maps_to_404() {
+        // Arrange
+        let error = Error::DatabaseQueryError(sqlx::Error::RowNotFound);
+        // Act
+        let (status, code) = (error.status(), error.code());
+        // Assert
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(code, "not_found");
+    }
+
+    #[test]
+    fn parse_error_maps_to_400() {
+        // Arrange
+        let error = Error::ParseInt("x".parse::<i32>().unwrap_err());
+        // Act
+        let (status, code) = (error.status(), error.code());
+        // Assert
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(code, "invalid_parameter");
+    }
+
+    #[tokio::test]
+    async fn error_body_is_problem_json() {
+        // Arrange
+        let rejection = warp::reject::custom(Error::WrongPassword);
+        // Act
+        let res = return_error(rejection).await.unwrap().into_response();
+        let content_type = res.headers()["content-type"].clone();
+        let body =
+            warp::hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let body: serde_json::Value =
+            serde_json::from_slice(&body).unwrap();
+        // Assert
+        assert_eq!(content_type, PROBLEM_CONTENT_TYPE);
+        assert_eq!(body["status"], 401);
+        assert_eq!(body["code"], "wrong_password");
+        assert!(body["request_id"].is_string());
+    }
+
+    #[tokio::test]
+    async fn request_id_is_the_id_of_the_request() {
+        // Arrange
+        let rejection = warp::reject::custom(Error::Forbidden);
+        // Act
+        let res = REQUEST_ID
+            .scope("7b0c".to_string(), return_error(rejection))
+            .await
+            .unwrap()
+            .into_response();
+        let body =
+            warp::hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let body: serde_json::Value =
+            serde_json::from_slice(&body).unwrap();
+        // Assert
+        assert_eq!(body["request_id"], "7b0c");
+    }
+
+    #[tokio::test]
+    async fn validation_errors_are_listed_per_field() {
+        // Arrange
+        let rejection =
+            warp::reject::custom(Error::ValidationError(vec![
+                FieldError {
+                    field: "title".to_string(),
+                    message: "must not be empty".to_string(),
+                },
+            ]));
+        // Act
+        let res = return_error(rejection).await.unwrap().into_response();
+        let status = res.status();
+        let body =
+            warp::hyper::body::to_bytes(res.into_body()).await.unwrap();
+        let body: serde_json::Value =
+            serde_json::from_slice(&body).unwrap();
+        // Assert
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["code"], "validation_failed");
+        assert_eq!(body["errors"][0]["field"], "title");
+        assert_eq!(body["errors"][0]["message"], "must not be empty");
     }
 }
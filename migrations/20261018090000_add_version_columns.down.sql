@@ -0,0 +1,6 @@
+-- Add down migration script here
+alter table answers
+drop column version;
+
+alter table questions
+drop column version;
\ No newline at end of file
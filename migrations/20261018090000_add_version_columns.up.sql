@@ -0,0 +1,6 @@
+-- Add up migration script here
+alter table questions
+add column version integer not null default 1;
+
+alter table answers
+add column version integer not null default 1;
\ No newline at end of file
@@ -0,0 +1,28 @@
+-- Add up migration script here
+create table if not exists webhooks (
+    id serial primary key,
+    account_id integer not null,
+    url varchar(2048) not null,
+    event_types text[] not null,
+    tags text[],
+    secret varchar(255) not null,
+    created_on timestamp not null default now()
+);
+
+create table if not exists webhook_deliveries (
+    id serial primary key,
+    webhook_id integer not null references webhooks on delete cascade,
+    event_type varchar(50) not null,
+    payload text not null,
+    status varchar(20) not null default 'pending',
+    attempts integer not null default 0,
+    next_attempt_on timestamp not null default now(),
+    last_status_code integer,
+    last_error text,
+    created_on timestamp not null default now(),
+    delivered_on timestamp
+);
+
+create index if not exists webhook_deliveries_pending_idx
+on webhook_deliveries (next_attempt_on)
+where status = 'pending';
@@ -0,0 +1,6 @@
+-- Add down migration script here
+drop table if exists notification_preferences;
+
+drop table if exists notifications;
+
+drop type if exists notification_kind;
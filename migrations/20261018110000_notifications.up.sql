@@ -0,0 +1,25 @@
+-- Add up migration script here
+create type notification_kind as enum (
+    'answer', 'accepted_answer', 'mention'
+);
+
+create table if not exists notifications (
+    id serial primary key,
+    account_id integer not null,
+    kind notification_kind not null,
+    question_id integer references questions on delete cascade,
+    answer_id integer references answers on delete cascade,
+    actor_id integer,
+    read_on timestamp,
+    created_on timestamp not null default now()
+);
+
+create index if not exists notifications_account_idx
+on notifications (account_id, id);
+
+create table if not exists notification_preferences (
+    account_id integer primary key,
+    answers boolean not null default true,
+    accepted_answers boolean not null default true,
+    mentions boolean not null default true
+);
@@ -0,0 +1,23 @@
+-- Add down migration script here
+alter table notification_preferences
+drop column watches;
+
+delete from notifications
+where kind in ('watched_question', 'watched_tag');
+
+-- Postgres can't drop a value of an enum, so the type is recreated
+alter type notification_kind rename to notification_kind_old;
+
+create type notification_kind as enum (
+    'answer', 'accepted_answer', 'mention'
+);
+
+alter table notifications
+alter column kind type notification_kind
+using kind::text::notification_kind;
+
+drop type notification_kind_old;
+
+drop table if exists tag_watches;
+
+drop table if exists question_watches;
@@ -0,0 +1,23 @@
+-- Add up migration script here
+create table if not exists question_watches (
+    account_id integer not null,
+    question_id integer not null references questions on delete cascade,
+    created_on timestamp not null default now(),
+    primary key (account_id, question_id)
+);
+
+create table if not exists tag_watches (
+    account_id integer not null,
+    tag varchar(35) not null,
+    created_on timestamp not null default now(),
+    primary key (account_id, tag)
+);
+
+create index if not exists tag_watches_tag_idx on tag_watches (tag);
+
+alter type notification_kind add value if not exists 'watched_question';
+
+alter type notification_kind add value if not exists 'watched_tag';
+
+alter table notification_preferences
+add column watches boolean not null default true;
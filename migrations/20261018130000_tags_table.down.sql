@@ -0,0 +1,6 @@
+-- Add down migration script here
+drop index if exists questions_tags_idx;
+
+drop table if exists tag_synonyms;
+
+drop table if exists tags;
@@ -0,0 +1,64 @@
+-- Add up migration script here
+create table if not exists tags (
+    id serial primary key,
+    slug varchar(35) not null unique,
+    description text,
+    created_on timestamp not null default now()
+);
+
+-- Alternative spellings, replaced by the slug of their tag
+create table if not exists tag_synonyms (
+    synonym varchar(35) primary key,
+    tag_id integer not null references tags on delete cascade
+);
+
+-- Same rules as `validation::normalize_tag`
+create function pg_temp.normalize_tag(tag text) returns text as $$
+    select trim(both '-' from regexp_replace(
+        regexp_replace(
+            regexp_replace(lower(trim(tag)), '[[:space:]_-]+', '-', 'g'),
+            '[^[:alnum:]+#.-]', '', 'g'
+        ),
+        '-+', '-', 'g'
+    ))
+$$ language sql immutable;
+
+update questions set tags = array(
+    select slug from (
+        select pg_temp.normalize_tag(t) as slug, min(ord) as ord
+        from unnest(questions.tags) with ordinality as u(t, ord)
+        group by 1
+    ) normalized
+    where slug <> ''
+    order by ord
+)
+where tags is not null;
+
+insert into tags (slug)
+select distinct unnest(tags) from questions
+on conflict (slug) do nothing;
+
+update tag_watches set tag = pg_temp.normalize_tag(tag)
+where tag <> pg_temp.normalize_tag(tag)
+and not exists (
+    select 1 from tag_watches w
+    where w.account_id = tag_watches.account_id
+    and w.tag = pg_temp.normalize_tag(tag_watches.tag)
+);
+
+-- Duplicates of a watch which is already normalized
+delete from tag_watches where tag <> pg_temp.normalize_tag(tag);
+
+update webhooks set tags = array(
+    select slug from (
+        select pg_temp.normalize_tag(t) as slug, min(ord) as ord
+        from unnest(webhooks.tags) with ordinality as u(t, ord)
+        group by 1
+    ) normalized
+    where slug <> ''
+    order by ord
+)
+where tags is not null;
+
+create index if not exists questions_tags_idx
+on questions using gin (tags);
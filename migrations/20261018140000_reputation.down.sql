@@ -0,0 +1,13 @@
+-- Add down migration script here
+drop table if exists reputation_events;
+
+drop type if exists reputation_kind;
+
+drop index if exists answers_accepted_idx;
+
+alter table answers
+drop column accepted;
+
+drop table if exists answer_votes;
+
+drop table if exists question_votes;
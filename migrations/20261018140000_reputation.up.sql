@@ -0,0 +1,51 @@
+-- Add up migration script here
+create table if not exists question_votes (
+    account_id integer not null,
+    question_id integer not null references questions on delete cascade,
+    value smallint not null check (value in (-1, 1)),
+    created_on timestamp not null default now(),
+    primary key (account_id, question_id)
+);
+
+create table if not exists answer_votes (
+    account_id integer not null,
+    answer_id integer not null references answers on delete cascade,
+    value smallint not null check (value in (-1, 1)),
+    created_on timestamp not null default now(),
+    primary key (account_id, answer_id)
+);
+
+alter table answers
+add column accepted boolean not null default false;
+
+create unique index if not exists answers_accepted_idx
+on answers (corresponding_question) where accepted;
+
+create type reputation_kind as enum (
+    'question_upvoted',
+    'question_downvoted',
+    'answer_upvoted',
+    'answer_downvoted',
+    'answer_accepted'
+);
+
+-- Every change of reputation, the reputation of an account is the sum
+-- of its rows. The events outlive their post, which stays identified by
+-- `post_id`: the answer for the answer events, the question otherwise.
+create table if not exists reputation_events (
+    id serial primary key,
+    account_id integer not null,
+    kind reputation_kind not null,
+    points integer not null,
+    actor_id integer not null,
+    question_id integer references questions on delete set null,
+    answer_id integer references answers on delete set null,
+    post_id integer,
+    created_on timestamp not null default now()
+);
+
+create index if not exists reputation_events_account_idx
+on reputation_events (account_id);
+
+create index if not exists reputation_events_created_on_idx
+on reputation_events (created_on);
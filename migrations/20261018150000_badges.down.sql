@@ -0,0 +1,6 @@
+-- Add down migration script here
+drop table if exists badge_evaluations;
+
+drop table if exists account_badges;
+
+drop table if exists badges;
@@ -0,0 +1,24 @@
+-- Add up migration script here
+create table if not exists badges (
+    id serial primary key,
+    slug varchar(50) not null unique,
+    name varchar(100) not null,
+    description text not null,
+    rule jsonb not null,
+    created_on timestamp not null default now()
+);
+
+create table if not exists account_badges (
+    account_id integer not null,
+    badge_id integer not null references badges on delete cascade,
+    awarded_on timestamp not null default now(),
+    primary key (account_id, badge_id)
+);
+
+-- Single row, activity up to this point was already evaluated
+create table if not exists badge_evaluations (
+    id boolean primary key default true check (id),
+    evaluated_until timestamp not null default '-infinity'
+);
+
+insert into badge_evaluations default values;
@@ -0,0 +1,5 @@
+-- Add down migration script here
+alter table questions
+drop column duplicate_of;
+
+drop index if exists questions_title_trgm_idx;
@@ -0,0 +1,8 @@
+-- Add up migration script here
+create extension if not exists pg_trgm;
+
+create index if not exists questions_title_trgm_idx
+on questions using gin (title gin_trgm_ops);
+
+alter table questions
+add column duplicate_of integer references questions on delete set null;
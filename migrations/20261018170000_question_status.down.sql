@@ -0,0 +1,13 @@
+-- Add down migration script here
+alter table questions
+drop column close_reason,
+drop column status;
+
+drop type if exists close_reason;
+
+drop type if exists question_status;
+
+alter table accounts
+drop column role;
+
+drop type if exists account_role;
@@ -0,0 +1,19 @@
+-- Add up migration script here
+create type account_role as enum ('user', 'moderator');
+
+-- Moderators are appointed in the database
+alter table accounts
+add column role account_role not null default 'user';
+
+create type question_status as enum ('open', 'closed', 'locked', 'protected');
+
+create type close_reason as enum (
+    'duplicate', 'off_topic', 'unclear', 'too_broad', 'opinion_based'
+);
+
+alter table questions
+add column status question_status not null default 'open',
+add column close_reason close_reason;
+
+update questions set status = 'closed', close_reason = 'duplicate'
+where duplicate_of is not null;
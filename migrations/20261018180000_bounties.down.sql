@@ -0,0 +1,18 @@
+-- Add down migration script here
+drop table if exists bounties;
+
+delete from reputation_events
+where kind::text in ('bounty_offered', 'bounty_awarded');
+
+-- Enum values can't be dropped, the type is created again without them
+alter type reputation_kind rename to reputation_kind_old;
+create type reputation_kind as enum (
+    'question_upvoted',
+    'question_downvoted',
+    'answer_upvoted',
+    'answer_downvoted',
+    'answer_accepted'
+);
+alter table reputation_events
+alter column kind type reputation_kind using kind::text::reputation_kind;
+drop type reputation_kind_old;
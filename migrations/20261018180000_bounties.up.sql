@@ -0,0 +1,22 @@
+-- Add up migration script here
+alter type reputation_kind add value if not exists 'bounty_offered';
+alter type reputation_kind add value if not exists 'bounty_awarded';
+
+-- A bounty is open until it ends, `answer_id` stays null when it ended
+-- without an answer to award
+create table if not exists bounties (
+    id serial primary key,
+    question_id integer not null references questions on delete cascade,
+    account_id integer not null,
+    amount integer not null check (amount > 0),
+    created_on timestamp not null default now(),
+    expires_on timestamp not null,
+    answer_id integer references answers on delete set null,
+    ended_on timestamp
+);
+
+create unique index if not exists bounties_open_idx
+on bounties (question_id) where ended_on is null;
+
+create index if not exists bounties_expires_on_idx
+on bounties (expires_on) where ended_on is null;
@@ -0,0 +1,4 @@
+-- Add down migration script here
+alter table answers drop column if exists content_html;
+
+alter table questions drop column if exists content_html;
@@ -0,0 +1,8 @@
+-- Add up migration script here
+-- Rendered from the Markdown of `content` on write, existing rows are
+-- rendered when the server starts
+alter table questions
+add column if not exists content_html text not null default '';
+
+alter table answers
+add column if not exists content_html text not null default '';
@@ -0,0 +1,23 @@
+-- Add up migration script here
+-- Fenced code blocks of questions and answers, `answer_id` is null for
+-- the snippets of the question itself
+create table if not exists snippets (
+    id serial primary key,
+    question_id integer not null references questions on delete cascade,
+    answer_id integer references answers on delete cascade,
+    position integer not null,
+    language text,
+    detected boolean not null default false,
+    code text not null,
+    created_on timestamp not null default now()
+);
+
+create index if not exists snippets_question_idx
+on snippets (question_id);
+
+create index if not exists snippets_answer_idx on snippets (answer_id);
+
+create index if not exists snippets_language_idx on snippets (language);
+
+create index if not exists snippets_code_trgm_idx
+on snippets using gin (code gin_trgm_ops);
@@ -0,0 +1,20 @@
+-- Add up migration script here
+-- Files attached to questions and answers, `answer_id` is null for the
+-- attachments of the question itself. The content is in the blob store
+-- under `key`.
+create table if not exists attachments (
+    id serial primary key,
+    question_id integer not null references questions on delete cascade,
+    answer_id integer references answers on delete cascade,
+    account_id integer not null,
+    key text not null unique,
+    file_name text not null,
+    content_type text not null,
+    size integer not null,
+    created_on timestamp not null default now()
+);
+
+create index if not exists attachments_question_idx
+on attachments (question_id);
+
+create index if not exists attachments_answer_idx on attachments (answer_id);
@@ -0,0 +1,6 @@
+-- Add down migration script here
+drop table if exists mentions;
+
+drop index if exists accounts_username_idx;
+
+alter table accounts drop column if exists username;
@@ -0,0 +1,21 @@
+-- Add up migration script here
+-- Handle of the account in `@username` mentions, unique regardless of
+-- the case
+alter table accounts add column if not exists username text;
+
+create unique index if not exists accounts_username_idx
+on accounts (lower(username));
+
+-- Accounts mentioned in questions and answers, `answer_id` is null for
+-- the mentions in the question itself
+create table if not exists mentions (
+    id serial primary key,
+    account_id integer not null,
+    actor_id integer not null,
+    question_id integer not null references questions on delete cascade,
+    answer_id integer references answers on delete cascade,
+    created_on timestamp not null default now(),
+    unique nulls not distinct (account_id, question_id, answer_id)
+);
+
+create index if not exists mentions_account_idx on mentions (account_id);
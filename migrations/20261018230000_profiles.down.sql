@@ -0,0 +1,4 @@
+-- Add down migration script here
+alter table accounts
+drop column if exists display_name,
+drop column if exists avatar_url;
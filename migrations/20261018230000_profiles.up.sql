@@ -0,0 +1,5 @@
+-- Add up migration script here
+-- Public identity of the accounts, shown instead of the e-mail address
+alter table accounts
+add column if not exists display_name text,
+add column if not exists avatar_url text;
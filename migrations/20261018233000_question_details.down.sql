@@ -0,0 +1,10 @@
+-- Add down migration script here
+drop index if exists answers_account_idx;
+
+drop index if exists questions_account_idx;
+
+drop index if exists question_votes_question_idx;
+
+drop index if exists answers_question_idx;
+
+alter table questions drop column if exists updated_on;
@@ -0,0 +1,15 @@
+-- Add up migration script here
+-- Last edit of the title, content or tags by the author
+alter table questions add column if not exists updated_on timestamp;
+
+-- Answer counts, scores and the posts of an account are looked up for
+-- every question and answer listed
+create index if not exists answers_question_idx
+on answers (corresponding_question);
+
+create index if not exists question_votes_question_idx
+on question_votes (question_id);
+
+create index if not exists questions_account_idx on questions (account_id);
+
+create index if not exists answers_account_idx on answers (account_id);
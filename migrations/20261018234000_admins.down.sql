@@ -0,0 +1,11 @@
+-- Add down migration script here
+update accounts set role = 'moderator' where role::text = 'admin';
+
+-- Enum values can't be dropped, the type is created again without them
+alter table accounts alter column role drop default;
+alter type account_role rename to account_role_old;
+create type account_role as enum ('user', 'moderator');
+alter table accounts
+alter column role type account_role using role::text::account_role;
+alter table accounts alter column role set default 'user';
+drop type account_role_old;
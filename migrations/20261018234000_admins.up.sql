@@ -0,0 +1,3 @@
+-- Add up migration script here
+-- Administrators may also do everything moderators do
+alter type account_role add value if not exists 'admin';
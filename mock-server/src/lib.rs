@@ -2,7 +2,10 @@ use bytes::Bytes;
 use serde_json::json;
 use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
 use tokio::sync::{oneshot, oneshot::Sender};
+use warp::http::HeaderMap;
 use warp::{http, Filter, Reply};
 
 #[derive(Clone, Debug)]
@@ -14,6 +17,18 @@ pub struct OneshotHandler {
     pub sender: Sender<i32>,
 }
 
+/// Request received by the webhook receiver
+#[derive(Debug)]
+pub struct ReceivedWebhook {
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+pub struct WebhookHandler {
+    pub sender: Sender<i32>,
+    pub received: UnboundedReceiver<ReceivedWebhook>,
+}
+
 impl MockServer {
     pub fn new(bind_addr: SocketAddr) -> MockServer {
         MockServer { socket: bind_addr }
@@ -84,4 +99,122 @@ impl MockServer {
 
         OneshotHandler { sender: tx }
     }
+
+    /// Receives webhooks on `POST /webhooks/{status}`, answering
+    /// with the given status code
+    pub fn webhook_receiver(&self) -> WebhookHandler {
+        let (tx, rx) = oneshot::channel::<i32>();
+        let (received_tx, received_rx) = unbounded_channel();
+
+        let routes = warp::post()
+            .and(warp::path("webhooks"))
+            .and(warp::path::param::<u16>())
+            .and(warp::path::end())
+            .and(warp::header::headers_cloned())
+            .and(warp::body::bytes())
+            .map(move |status: u16, headers: HeaderMap, body: Bytes| {
+                let headers = headers
+                    .iter()
+                    .map(|(name, value)| {
+                        (
+                            name.to_string(),
+                            value.to_str().unwrap_or_default().to_string(),
+                        )
+                    })
+                    .collect();
+                let body = String::from_utf8(body.to_vec())
+                    .expect("Invalid UTF-8");
+                let _ =
+                    received_tx.send(ReceivedWebhook { headers, body });
+                warp::reply::with_status(
+                    warp::reply(),
+                    http::StatusCode::from_u16(status)
+                        .unwrap_or(http::StatusCode::OK),
+                )
+            });
+
+        let (_, server) = warp::serve(routes).bind_with_graceful_shutdown(
+            self.socket,
+            async {
+                rx.await.ok();
+            },
+        );
+
+        tokio::task::spawn(server);
+
+        WebhookHandler {
+            sender: tx,
+            received: received_rx,
+        }
+    }
+
+    /// Stand-in for an S3-compatible service, keeping the objects of
+    /// `PUT`, `GET` and `DELETE /{bucket}/{key}` in memory. Requests
+    /// without a signature are refused.
+    pub fn blob_store(&self) -> OneshotHandler {
+        let (tx, rx) = oneshot::channel::<i32>();
+        let objects: Arc<Mutex<HashMap<String, Bytes>>> =
+            Arc::new(Mutex::new(HashMap::new()));
+
+        let object = warp::path::param::<String>()
+            .and(warp::path::param::<String>())
+            .and(warp::path::end())
+            .map(|bucket: String, key: String| {
+                format!("{}/{}", bucket, key)
+            })
+            .and(warp::header::optional::<String>("authorization"))
+            .and(warp::body::bytes());
+        let routes = warp::method().and(object).map(
+            move |method: http::Method,
+                  path: String,
+                  authorization: Option<String>,
+                  body: Bytes| {
+                let signed = authorization.is_some_and(|value| {
+                    value.starts_with("AWS4-HMAC-SHA256 Credential=")
+                });
+                if !signed {
+                    return http::Response::builder()
+                        .status(http::StatusCode::FORBIDDEN)
+                        .body(Bytes::new())
+                        .unwrap();
+                }
+                let mut objects = objects.lock().unwrap();
+                let (status, body) = match method {
+                    http::Method::PUT => {
+                        objects.insert(path, body);
+                        (http::StatusCode::OK, Bytes::new())
+                    }
+                    http::Method::GET => match objects.get(&path) {
+                        Some(data) => (http::StatusCode::OK, data.clone()),
+                        None => {
+                            (http::StatusCode::NOT_FOUND, Bytes::new())
+                        }
+                    },
+                    http::Method::DELETE => {
+                        objects.remove(&path);
+                        (http::StatusCode::NO_CONTENT, Bytes::new())
+                    }
+                    _ => (
+                        http::StatusCode::METHOD_NOT_ALLOWED,
+                        Bytes::new(),
+                    ),
+                };
+                http::Response::builder()
+                    .status(status)
+                    .body(body)
+                    .unwrap()
+            },
+        );
+
+        let (_, server) = warp::serve(routes).bind_with_graceful_shutdown(
+            self.socket,
+            async {
+                rx.await.ok();
+            },
+        );
+
+        tokio::task::spawn(server);
+
+        OneshotHandler { sender: tx }
+    }
 }
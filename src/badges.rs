@@ -0,0 +1,88 @@
+use ::config::{Config, ConfigError, File, FileFormat};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::time::Duration;
+
+use crate::store::Store;
+use crate::types::badge::{BadgeDefinition, BadgeRule};
+use crate::validation::normalize_tag;
+
+/// Rules used unless another file is configured
+const DEFAULT_RULES: &str = include_str!("../badges.toml");
+const EVALUATION_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Deserialize)]
+struct RulesFile {
+    badges: Vec<BadgeDefinition>,
+}
+
+/// Reads the badge definitions of the TOML file at `path`, or the
+/// default ones
+pub fn load_rules(
+    path: Option<&str>,
+) -> Result<Vec<BadgeDefinition>, ConfigError> {
+    let builder = Config::builder();
+    let builder = match path {
+        Some(path) => builder
+            .add_source(File::with_name(path).format(FileFormat::Toml)),
+        None => builder
+            .add_source(File::from_str(DEFAULT_RULES, FileFormat::Toml)),
+    };
+    let file: RulesFile = builder.build()?.try_deserialize()?;
+
+    let mut slugs = HashSet::new();
+    file.badges
+        .into_iter()
+        .map(|mut badge| {
+            if !slugs.insert(badge.slug.clone()) {
+                return Err(ConfigError::Message(format!(
+                    "Badge {} is defined twice",
+                    badge.slug
+                )));
+            }
+            // Tags are stored as slugs
+            if let BadgeRule::TagAnswerScore { tag, .. } = &mut badge.rule
+            {
+                *tag = normalize_tag(tag);
+                if tag.is_empty() {
+                    return Err(ConfigError::Message(format!(
+                        "Badge {} has an invalid tag",
+                        badge.slug
+                    )));
+                }
+            }
+            Ok(badge)
+        })
+        .collect()
+}
+
+/// Awards the badges to the accounts with new activity, runs until the
+/// server stops. Every instance can run one, only one of them evaluates
+/// at a time.
+pub async fn run_evaluator(store: Store, badges: Vec<(i32, BadgeRule)>) {
+    loop {
+        // The store already logs the error
+        let _ = store.evaluate_badges(&badges).await;
+        tokio::time::sleep(EVALUATION_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod badges_tests {
+    use super::*;
+
+    #[test]
+    fn default_rules_are_valid() {
+        // Arrange
+        let path = None;
+        // Act
+        let badges = load_rules(path).unwrap();
+        // Assert
+        assert!(badges.len() >= 4);
+        assert_eq!(badges[0].rule, BadgeRule::QuestionsAsked { count: 1 });
+        assert!(badges.iter().any(|badge| matches!(
+            &badge.rule,
+            BadgeRule::TagAnswerScore { tag, .. } if tag == "rust"
+        )));
+    }
+}
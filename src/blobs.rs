@@ -0,0 +1,359 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use handle_errors::Error;
+use hmac::{Hmac, Mac};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::{Method, StatusCode};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::{event, Level};
+use warp::hyper::body::Bytes;
+
+use crate::config::Config;
+
+/// Headers covered by the signature of the S3 requests
+const SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+/// Characters escaped in the path of the S3 requests, everything but
+/// the unreserved characters of RFC 3986
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Keeps the content of the attachments, the database only has
+/// their metadata
+#[async_trait]
+pub trait BlobStore: std::fmt::Debug + Send + Sync {
+    async fn put(
+        &self,
+        key: &str,
+        content_type: &str,
+        data: Bytes,
+    ) -> Result<(), Error>;
+
+    async fn get(&self, key: &str) -> Result<Bytes, Error>;
+
+    /// Succeeds if the blob doesn't exist
+    async fn delete(&self, key: &str) -> Result<(), Error>;
+}
+
+/// The S3-compatible store if an endpoint is configured, the local
+/// filesystem otherwise
+pub fn from_config(config: &Config) -> Arc<dyn BlobStore> {
+    match &config.s3_endpoint {
+        Some(endpoint) => Arc::new(S3BlobStore::new(
+            endpoint,
+            &config.s3_bucket,
+            &config.s3_region,
+            &std::env::var("S3_ACCESS_KEY_ID")
+                .expect("S3 access key not set!"),
+            &std::env::var("S3_SECRET_ACCESS_KEY")
+                .expect("S3 secret key not set!"),
+        )),
+        None => Arc::new(FsBlobStore::new(&config.attachments_dir)),
+    }
+}
+
+fn storage_error(e: impl std::fmt::Display) -> Error {
+    event!(Level::ERROR, "{}", e);
+    Error::StorageError(e.to_string())
+}
+
+/// One file per blob in a directory
+#[derive(Debug, Clone)]
+pub struct FsBlobStore {
+    root: PathBuf,
+}
+
+impl FsBlobStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        FsBlobStore { root: root.into() }
+    }
+
+    /// Keys are generated by the server, anything which could leave the
+    /// directory is refused anyway
+    fn path(&self, key: &str) -> Result<PathBuf, Error> {
+        if !key.is_empty()
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        {
+            Ok(self.root.join(key))
+        } else {
+            Err(storage_error(format!("invalid blob key {:?}", key)))
+        }
+    }
+}
+
+#[async_trait]
+impl BlobStore for FsBlobStore {
+    async fn put(
+        &self,
+        key: &str,
+        _content_type: &str,
+        data: Bytes,
+    ) -> Result<(), Error> {
+        let path = self.path(key)?;
+        tokio::fs::create_dir_all(&self.root)
+            .await
+            .map_err(storage_error)?;
+        tokio::fs::write(path, data).await.map_err(storage_error)
+    }
+
+    async fn get(&self, key: &str) -> Result<Bytes, Error> {
+        let data = tokio::fs::read(self.path(key)?)
+            .await
+            .map_err(storage_error)?;
+        Ok(Bytes::from(data))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), Error> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(storage_error(e)),
+        }
+    }
+}
+
+/// Bucket of an S3-compatible service such as MinIO, addressed with
+/// path-style URLs and requests signed with AWS Signature Version 4
+#[derive(Clone)]
+pub struct S3BlobStore {
+    client: reqwest::Client,
+    endpoint: String,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+}
+
+// Leaves the secret key out of the logs
+impl std::fmt::Debug for S3BlobStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("S3BlobStore")
+            .field("endpoint", &self.endpoint)
+            .field("bucket", &self.bucket)
+            .field("region", &self.region)
+            .finish_non_exhaustive()
+    }
+}
+
+impl S3BlobStore {
+    pub fn new(
+        endpoint: &str,
+        bucket: &str,
+        region: &str,
+        access_key: &str,
+        secret_key: &str,
+    ) -> Self {
+        S3BlobStore {
+            client: reqwest::Client::new(),
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            bucket: bucket.to_string(),
+            region: region.to_string(),
+            access_key: access_key.to_string(),
+            secret_key: secret_key.to_string(),
+        }
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        key: &str,
+        content_type: Option<&str>,
+        body: Bytes,
+    ) -> Result<reqwest::Response, Error> {
+        let path = format!(
+            "/{}/{}",
+            utf8_percent_encode(&self.bucket, PATH_SEGMENT),
+            utf8_percent_encode(key, PATH_SEGMENT)
+        );
+        let url =
+            reqwest::Url::parse(&format!("{}{}", self.endpoint, path))
+                .map_err(storage_error)?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => {
+                return Err(storage_error("S3 endpoint has no host"))
+            }
+        };
+
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex::encode(Sha256::digest(&body));
+        let canonical_request = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method, path, host, payload_hash, amz_date, SIGNED_HEADERS, payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+        let signing_key =
+            signing_key(&self.secret_key, &date, &self.region, "s3");
+        let signature = hex::encode(hmac_sha256(
+            &signing_key,
+            string_to_sign.as_bytes(),
+        ));
+
+        let mut request = self
+            .client
+            .request(method, url)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header(
+                "authorization",
+                format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                    self.access_key, scope, SIGNED_HEADERS, signature
+                ),
+            );
+        if let Some(content_type) = content_type {
+            request = request.header("content-type", content_type);
+        }
+        request.body(body).send().await.map_err(storage_error)
+    }
+}
+
+#[async_trait]
+impl BlobStore for S3BlobStore {
+    async fn put(
+        &self,
+        key: &str,
+        content_type: &str,
+        data: Bytes,
+    ) -> Result<(), Error> {
+        let res = self
+            .send(Method::PUT, key, Some(content_type), data)
+            .await?;
+        match res.status() {
+            status if status.is_success() => Ok(()),
+            status => {
+                Err(storage_error(format!("PUT {}: {}", key, status)))
+            }
+        }
+    }
+
+    async fn get(&self, key: &str) -> Result<Bytes, Error> {
+        let res = self.send(Method::GET, key, None, Bytes::new()).await?;
+        match res.status() {
+            status if status.is_success() => {
+                res.bytes().await.map_err(storage_error)
+            }
+            status => {
+                Err(storage_error(format!("GET {}: {}", key, status)))
+            }
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), Error> {
+        let res =
+            self.send(Method::DELETE, key, None, Bytes::new()).await?;
+        match res.status() {
+            status
+                if status.is_success()
+                    || status == StatusCode::NOT_FOUND =>
+            {
+                Ok(())
+            }
+            status => {
+                Err(storage_error(format!("DELETE {}: {}", key, status)))
+            }
+        }
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key)
+        .expect("HMAC takes keys of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Key of AWS Signature Version 4 for a day, region and service
+fn signing_key(
+    secret_key: &str,
+    date: &str,
+    region: &str,
+    service: &str,
+) -> Vec<u8> {
+    let key = hmac_sha256(
+        format!("AWS4{}", secret_key).as_bytes(),
+        date.as_bytes(),
+    );
+    let key = hmac_sha256(&key, region.as_bytes());
+    let key = hmac_sha256(&key, service.as_bytes());
+    hmac_sha256(&key, b"aws4_request")
+}
+
+#[cfg(test)]
+mod blobs_tests {
+    use super::*;
+    use mock_server::MockServer;
+
+    #[test]
+    fn signing_key_matches_aws_example() {
+        // Arrange
+        let secret = "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY";
+        // Act
+        let key = signing_key(secret, "20120215", "us-east-1", "iam");
+        // Assert
+        assert_eq!(
+            hex::encode(key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+    }
+
+    #[tokio::test]
+    async fn filesystem_store_round_trip() {
+        // Arrange
+        let root = std::env::temp_dir()
+            .join(format!("blobs-{}", uuid::Uuid::new_v4()));
+        let blobs = FsBlobStore::new(&root);
+        // Act
+        let put = blobs.put("a-1", "text/plain", Bytes::from("log")).await;
+        let data = blobs.get("a-1").await;
+        let deleted = blobs.delete("a-1").await;
+        let missing = blobs.get("a-1").await;
+        let escaping = blobs.get("../a-1").await;
+        let _ = std::fs::remove_dir_all(root);
+        // Assert
+        assert!(put.is_ok());
+        assert_eq!(data.unwrap(), Bytes::from("log"));
+        assert!(deleted.is_ok());
+        assert!(matches!(missing, Err(Error::StorageError(_))));
+        assert!(matches!(escaping, Err(Error::StorageError(_))));
+    }
+
+    #[tokio::test]
+    async fn s3_store_round_trip() {
+        // Arrange
+        let socket =
+            "127.0.0.1:3032".parse().expect("Not a valid address");
+        let handler = MockServer::new(socket).blob_store();
+        let blobs = S3BlobStore::new(
+            "http://127.0.0.1:3032",
+            "attachments",
+            "us-east-1",
+            "access",
+            "secret",
+        );
+        // Act
+        let put = blobs.put("a-1", "image/png", Bytes::from("png")).await;
+        let data = blobs.get("a-1").await;
+        let deleted = blobs.delete("a-1").await;
+        let missing = blobs.get("a-1").await;
+        let _ = handler.sender.send(1);
+        // Assert
+        assert!(put.is_ok());
+        assert_eq!(data.unwrap(), Bytes::from("png"));
+        assert!(deleted.is_ok());
+        assert!(matches!(missing, Err(Error::StorageError(_))));
+    }
+}
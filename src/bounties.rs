@@ -0,0 +1,19 @@
+use std::time::Duration;
+use tracing::{event, Level};
+
+use crate::store::Store;
+
+const EXPIRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Awards the expired bounties to the top voted answers
+pub async fn run_expiry(store: Store) {
+    loop {
+        // The store already logs the error
+        if let Ok(ended) = store.award_expired_bounties().await {
+            if ended > 0 {
+                event!(Level::INFO, ended, "expired bounties");
+            }
+        }
+        tokio::time::sleep(EXPIRY_INTERVAL).await;
+    }
+}
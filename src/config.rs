@@ -26,6 +26,21 @@ pub struct Config {
     /// Database name
     #[clap(long, default_value = "rustywebdev")]
     pub db_name: String,
+    /// TOML file with the badge rules, the built-in rules if not set
+    #[clap(long)]
+    pub badges_file: Option<String>,
+    /// Directory of the attachments when no S3 endpoint is set
+    #[clap(long, default_value = "attachments")]
+    pub attachments_dir: String,
+    /// URL of an S3-compatible service keeping the attachments
+    #[clap(long)]
+    pub s3_endpoint: Option<String>,
+    /// Bucket of the attachments on the S3-compatible service
+    #[clap(long, default_value = "attachments")]
+    pub s3_bucket: String,
+    /// Region of the S3-compatible service
+    #[clap(long, default_value = "us-east-1")]
+    pub s3_region: String,
 }
 
 impl Config {
@@ -57,6 +72,14 @@ impl Config {
             .unwrap();
         let db_name =
             env::var("POSTGRES_DB").unwrap_or(config.db_name.to_owned());
+        let badges_file =
+            env::var("BADGES_FILE").ok().or(config.badges_file);
+        let attachments_dir =
+            env::var("ATTACHMENTS_DIR").unwrap_or(config.attachments_dir);
+        let s3_endpoint =
+            env::var("S3_ENDPOINT").ok().or(config.s3_endpoint);
+        let s3_bucket = env::var("S3_BUCKET").unwrap_or(config.s3_bucket);
+        let s3_region = env::var("S3_REGION").unwrap_or(config.s3_region);
 
         Ok(Config {
             log_level: config.log_level,
@@ -66,6 +89,11 @@ impl Config {
             db_host,
             db_port,
             db_name,
+            badges_file,
+            attachments_dir,
+            s3_endpoint,
+            s3_bucket,
+            s3_region,
         })
     }
 }
@@ -104,6 +132,11 @@ mod config_tests {
             db_host: "localhost".to_string(),
             db_port: 5432,
             db_name: "db".to_string(),
+            badges_file: None,
+            attachments_dir: "attachments".to_string(),
+            s3_endpoint: None,
+            s3_bucket: "attachments".to_string(),
+            s3_region: "us-east-1".to_string(),
         };
         // Act
         let result = Config::new().unwrap();
@@ -0,0 +1,294 @@
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgListener;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{event, Level};
+use utoipa::ToSchema;
+
+use crate::store::Store;
+use crate::types::answer::Answer;
+use crate::types::question::{Question, QuestionId};
+
+/// Postgres channel shared by every instance of the server
+const CHANNEL: &str = "qa_events";
+/// Number of events a slow subscriber can fall behind before
+/// it starts missing some
+const CAPACITY: usize = 256;
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Names of every kind of event, see `Event::name`
+pub const EVENT_TYPES: [&str; 4] = [
+    "question_added",
+    "question_updated",
+    "question_deleted",
+    "answer_added",
+];
+
+/// Change pushed to the clients following a question
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    QuestionAdded {
+        question: Question,
+    },
+    AnswerAdded {
+        answer: Answer,
+    },
+    QuestionUpdated {
+        question: Question,
+    },
+    QuestionDeleted {
+        question_id: QuestionId,
+        /// Tags of the question, which can't be looked up anymore
+        #[serde(default)]
+        tags: Vec<String>,
+    },
+}
+
+impl Event {
+    pub fn question_id(&self) -> i32 {
+        match self {
+            Event::QuestionAdded { question } => question.id.0,
+            Event::AnswerAdded { answer } => answer.question_id.0,
+            Event::QuestionUpdated { question } => question.id.0,
+            Event::QuestionDeleted { question_id, .. } => question_id.0,
+        }
+    }
+
+    /// Name of the event, used as the `event` field of Server-Sent Events
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::QuestionAdded { .. } => "question_added",
+            Event::AnswerAdded { .. } => "answer_added",
+            Event::QuestionUpdated { .. } => "question_updated",
+            Event::QuestionDeleted { .. } => "question_deleted",
+        }
+    }
+}
+
+/// Payload of a `NOTIFY`. Postgres limits it to 8000 bytes, so only
+/// the ids are sent and the other instances load the rows themselves.
+/// A deleted question can't be loaded, its few tags are sent along.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Notification {
+    origin: String,
+    #[serde(flatten)]
+    reference: Reference,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Reference {
+    QuestionAdded {
+        question_id: i32,
+    },
+    AnswerAdded {
+        answer_id: i32,
+    },
+    QuestionUpdated {
+        question_id: i32,
+    },
+    QuestionDeleted {
+        question_id: i32,
+        #[serde(default)]
+        tags: Vec<String>,
+    },
+}
+
+impl From<&Event> for Reference {
+    fn from(event: &Event) -> Self {
+        match event {
+            Event::QuestionAdded { question } => {
+                Reference::QuestionAdded {
+                    question_id: question.id.0,
+                }
+            }
+            Event::AnswerAdded { answer } => Reference::AnswerAdded {
+                answer_id: answer.id.0,
+            },
+            Event::QuestionUpdated { question } => {
+                Reference::QuestionUpdated {
+                    question_id: question.id.0,
+                }
+            }
+            Event::QuestionDeleted { question_id, tags } => {
+                Reference::QuestionDeleted {
+                    question_id: question_id.0,
+                    tags: tags.clone(),
+                }
+            }
+        }
+    }
+}
+
+/// Broadcasts the events published by the route handlers to the
+/// subscribers of this instance, and through `LISTEN/NOTIFY` to the
+/// subscribers of every other instance
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<Event>,
+    store: Store,
+    /// Identifies this instance, so it skips its own notifications
+    origin: String,
+}
+
+impl EventBus {
+    pub fn new(store: Store) -> Self {
+        let (sender, _) = broadcast::channel(CAPACITY);
+        EventBus {
+            sender,
+            store,
+            origin: uuid::Uuid::new_v4().to_string(),
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+
+    /// Never fails, a missed event must not fail the request
+    /// which caused it. Only broadcasts the event, the callers queue
+    /// its webhook deliveries with `webhooks::enqueue`.
+    pub async fn publish(&self, event: Event) {
+        let notification = Notification {
+            origin: self.origin.clone(),
+            reference: Reference::from(&event),
+        };
+        // An error only means nobody is subscribed on this instance
+        let _ = self.sender.send(event);
+
+        match serde_json::to_string(&notification) {
+            Ok(payload) => {
+                // The store already logs the error
+                let _ = self.store.notify(CHANNEL, &payload).await;
+            }
+            Err(e) => event!(Level::ERROR, "{:?}", e),
+        }
+    }
+
+    /// Forwards the notifications of the other instances to the local
+    /// subscribers, runs until the server stops
+    pub async fn listen(self) {
+        loop {
+            let mut listener =
+                match PgListener::connect_with(&self.store.connection)
+                    .await
+                {
+                    Ok(listener) => listener,
+                    Err(e) => {
+                        event!(Level::ERROR, "{:?}", e);
+                        tokio::time::sleep(RECONNECT_DELAY).await;
+                        continue;
+                    }
+                };
+            if let Err(e) = listener.listen(CHANNEL).await {
+                event!(Level::ERROR, "{:?}", e);
+                tokio::time::sleep(RECONNECT_DELAY).await;
+                continue;
+            }
+
+            // `recv` reconnects by itself when the connection is lost
+            loop {
+                match listener.recv().await {
+                    Ok(notification) => {
+                        self.forward(notification.payload()).await
+                    }
+                    Err(e) => {
+                        event!(Level::ERROR, "{:?}", e);
+                        break;
+                    }
+                }
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    }
+
+    async fn forward(&self, payload: &str) {
+        let notification =
+            match serde_json::from_str::<Notification>(payload) {
+                Ok(notification) => notification,
+                Err(e) => {
+                    event!(Level::WARN, "invalid notification: {:?}", e);
+                    return;
+                }
+            };
+        if notification.origin == self.origin {
+            return;
+        }
+
+        let event = match notification.reference {
+            Reference::QuestionAdded { question_id } => self
+                .store
+                .get_question_by_id(question_id)
+                .await
+                .map(|question| Event::QuestionAdded { question }),
+            Reference::AnswerAdded { answer_id } => self
+                .store
+                .get_answer_by_id(answer_id)
+                .await
+                .map(|answer| Event::AnswerAdded { answer }),
+            Reference::QuestionUpdated { question_id } => self
+                .store
+                .get_question_by_id(question_id)
+                .await
+                .map(|question| Event::QuestionUpdated { question }),
+            Reference::QuestionDeleted { question_id, tags } => {
+                Ok(Event::QuestionDeleted {
+                    question_id: QuestionId(question_id),
+                    tags,
+                })
+            }
+        };
+        // The row may be gone already, then a later event follows
+        if let Ok(event) = event {
+            let _ = self.sender.send(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod events_tests {
+    use super::*;
+    use crate::types::answer::AnswerId;
+
+    #[test]
+    fn event_is_tagged_with_its_type() {
+        // Arrange
+        let event = Event::AnswerAdded {
+            answer: Answer {
+                id: AnswerId(2),
+                content: "Like this".to_string(),
+                content_html: "<p>Like this</p>\n".to_string(),
+                question_id: QuestionId(1),
+                version: 1,
+                accepted: false,
+                author: None,
+                created_on: chrono::NaiveDateTime::default(),
+            },
+        };
+        // Act
+        let json = serde_json::to_value(&event).unwrap();
+        // Assert
+        assert_eq!(json["type"], "answer_added");
+        assert_eq!(json["answer"]["question_id"], 1);
+        assert_eq!(event.question_id(), 1);
+    }
+
+    #[test]
+    fn notification_only_carries_ids() {
+        // Arrange
+        let notification = Notification {
+            origin: "instance".to_string(),
+            reference: Reference::AnswerAdded { answer_id: 2 },
+        };
+        // Act
+        let payload = serde_json::to_string(&notification).unwrap();
+        let parsed: Notification = serde_json::from_str(&payload).unwrap();
+        // Assert
+        assert_eq!(
+            payload,
+            r#"{"origin":"instance","type":"answer_added","answer_id":2}"#
+        );
+        assert_eq!(parsed, notification);
+    }
+}
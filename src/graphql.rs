@@ -0,0 +1,498 @@
+use async_graphql::dataloader::{DataLoader, Loader};
+use async_graphql::{
+    Context, EmptySubscription, ErrorExtensions, InputObject, Object,
+    Schema,
+};
+use chrono::NaiveDateTime;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::instrument;
+
+use crate::events::EventBus;
+use crate::routes::answer::create_answer;
+use crate::routes::question::create_question;
+use crate::store::Store;
+use crate::types::account::{AccountInfo, Session};
+use crate::types::answer::{Answer, NewAnswer};
+use crate::types::question::{NewQuestion, Question, QuestionId};
+use crate::validation::Validate;
+
+pub type QaSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+
+const MAX_DEPTH: usize = 10;
+/// A list of questions counts once per question it may return
+const MAX_COMPLEXITY: usize = 2000;
+const DEFAULT_QUESTIONS_LIMIT: i32 = 20;
+const MAX_QUESTIONS_LIMIT: i32 = 100;
+
+pub fn build_schema(store: Store, events: EventBus) -> QaSchema {
+    Schema::build(QueryRoot, MutationRoot, EmptySubscription)
+        .data(store)
+        .data(events)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish()
+}
+
+fn questions_limit(limit: Option<i32>) -> i32 {
+    limit
+        .unwrap_or(DEFAULT_QUESTIONS_LIMIT)
+        .clamp(1, MAX_QUESTIONS_LIMIT)
+}
+
+/// Executes a GraphQL request. The data loaders are created for every
+/// request, so nothing is cached between requests.
+#[instrument(skip(schema))]
+pub async fn graphql(
+    session: Option<Session>,
+    store: Store,
+    schema: QaSchema,
+    request: async_graphql::Request,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut request = request
+        .data(DataLoader::new(AnswersLoader(store.clone()), tokio::spawn))
+        .data(DataLoader::new(AuthorLoader(store), tokio::spawn));
+    if let Some(session) = session {
+        request = request.data(session);
+    }
+
+    Ok(warp::reply::json(&schema.execute(request).await))
+}
+
+/// Keeps the stable code of `handle_errors::Error` in the `extensions`
+/// of the GraphQL error
+fn graphql_error(error: &handle_errors::Error) -> async_graphql::Error {
+    async_graphql::Error::new(error.to_string()).extend_with(|_, e| {
+        e.set("code", error.code());
+        if let handle_errors::Error::ValidationError(errors) = error {
+            if let Ok(errors) = serde_json::to_value(errors) {
+                if let Ok(errors) = async_graphql::Value::from_json(errors)
+                {
+                    e.set("errors", errors);
+                }
+            }
+        }
+    })
+}
+
+fn session<'a>(
+    ctx: &'a Context<'_>,
+) -> async_graphql::Result<&'a Session> {
+    ctx.data_opt::<Session>()
+        .ok_or_else(|| graphql_error(&handle_errors::Error::Unauthorized))
+}
+
+fn validate<T: Validate>(input: &T) -> async_graphql::Result<()> {
+    let errors = input.validate();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(graphql_error(&handle_errors::Error::ValidationError(
+            errors,
+        )))
+    }
+}
+
+/// Batches the answers of every question of a query in one database query
+pub struct AnswersLoader(Store);
+
+impl Loader<i32> for AnswersLoader {
+    type Value = Vec<Answer>;
+    type Error = Arc<handle_errors::Error>;
+
+    async fn load(
+        &self,
+        question_ids: &[i32],
+    ) -> Result<HashMap<i32, Self::Value>, Self::Error> {
+        let answers =
+            self.0.get_answers_by_question_ids(question_ids).await?;
+        let mut res: HashMap<i32, Vec<Answer>> = HashMap::new();
+        for answer in answers {
+            res.entry(answer.question_id.0).or_default().push(answer);
+        }
+        Ok(res)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AuthorOf {
+    Question(i32),
+    Answer(i32),
+}
+
+/// Batches the authors of every question and answer of a query
+pub struct AuthorLoader(Store);
+
+impl Loader<AuthorOf> for AuthorLoader {
+    type Value = AccountInfo;
+    type Error = Arc<handle_errors::Error>;
+
+    async fn load(
+        &self,
+        keys: &[AuthorOf],
+    ) -> Result<HashMap<AuthorOf, Self::Value>, Self::Error> {
+        let mut question_ids = Vec::new();
+        let mut answer_ids = Vec::new();
+        for key in keys {
+            match key {
+                AuthorOf::Question(id) => question_ids.push(*id),
+                AuthorOf::Answer(id) => answer_ids.push(*id),
+            }
+        }
+
+        let mut res = HashMap::new();
+        if !question_ids.is_empty() {
+            for (id, author) in
+                self.0.get_question_authors(&question_ids).await?
+            {
+                res.insert(AuthorOf::Question(id.0), author);
+            }
+        }
+        if !answer_ids.is_empty() {
+            for (id, author) in
+                self.0.get_answer_authors(&answer_ids).await?
+            {
+                res.insert(AuthorOf::Answer(id.0), author);
+            }
+        }
+        Ok(res)
+    }
+}
+
+pub struct QuestionObject(Question);
+
+#[Object(name = "Question")]
+impl QuestionObject {
+    async fn id(&self) -> i32 {
+        self.0.id.0
+    }
+
+    async fn title(&self) -> &str {
+        &self.0.title
+    }
+
+    async fn content(&self) -> &str {
+        &self.0.content
+    }
+
+    async fn content_html(&self) -> &str {
+        &self.0.content_html
+    }
+
+    async fn tags(&self) -> &Option<Vec<String>> {
+        &self.0.tags
+    }
+
+    async fn version(&self) -> i32 {
+        self.0.version
+    }
+
+    async fn status(&self) -> &str {
+        self.0.status.as_str()
+    }
+
+    async fn bounty(&self) -> Option<i32> {
+        self.0.bounty
+    }
+
+    async fn created_on(&self) -> String {
+        timestamp(&self.0.created_on)
+    }
+
+    async fn updated_on(&self) -> Option<String> {
+        self.0.updated_on.as_ref().map(timestamp)
+    }
+
+    async fn answer_count(&self) -> i64 {
+        self.0.answer_count
+    }
+
+    async fn score(&self) -> i64 {
+        self.0.score
+    }
+
+    async fn answers(
+        &self,
+        ctx: &Context<'_>,
+    ) -> async_graphql::Result<Vec<AnswerObject>> {
+        let answers = ctx
+            .data_unchecked::<DataLoader<AnswersLoader>>()
+            .load_one(self.0.id.0)
+            .await
+            .map_err(|e| graphql_error(&e))?;
+        Ok(answers
+            .unwrap_or_default()
+            .into_iter()
+            .map(AnswerObject)
+            .collect())
+    }
+
+    async fn author(
+        &self,
+        ctx: &Context<'_>,
+    ) -> async_graphql::Result<Option<AccountObject>> {
+        let author = ctx
+            .data_unchecked::<DataLoader<AuthorLoader>>()
+            .load_one(AuthorOf::Question(self.0.id.0))
+            .await
+            .map_err(|e| graphql_error(&e))?;
+        Ok(author.map(AccountObject))
+    }
+}
+
+/// Same format as the timestamps of the REST responses
+fn timestamp(value: &NaiveDateTime) -> String {
+    value.format("%Y-%m-%dT%H:%M:%S%.f").to_string()
+}
+
+pub struct AnswerObject(Answer);
+
+#[Object(name = "Answer")]
+impl AnswerObject {
+    async fn id(&self) -> i32 {
+        self.0.id.0
+    }
+
+    async fn content(&self) -> &str {
+        &self.0.content
+    }
+
+    async fn content_html(&self) -> &str {
+        &self.0.content_html
+    }
+
+    async fn question_id(&self) -> i32 {
+        self.0.question_id.0
+    }
+
+    async fn accepted(&self) -> bool {
+        self.0.accepted
+    }
+
+    async fn created_on(&self) -> String {
+        timestamp(&self.0.created_on)
+    }
+
+    async fn author(
+        &self,
+        ctx: &Context<'_>,
+    ) -> async_graphql::Result<Option<AccountObject>> {
+        let author = ctx
+            .data_unchecked::<DataLoader<AuthorLoader>>()
+            .load_one(AuthorOf::Answer(self.0.id.0))
+            .await
+            .map_err(|e| graphql_error(&e))?;
+        Ok(author.map(AccountObject))
+    }
+}
+
+pub struct AccountObject(AccountInfo);
+
+#[Object(name = "Account")]
+impl AccountObject {
+    async fn id(&self) -> i32 {
+        self.0.id.0
+    }
+
+    async fn username(&self) -> Option<&str> {
+        self.0.username.as_deref()
+    }
+
+    async fn display_name(&self) -> Option<&str> {
+        self.0.display_name.as_deref()
+    }
+
+    async fn avatar_url(&self) -> Option<&str> {
+        self.0.avatar_url.as_deref()
+    }
+
+    /// Only visible to the owner of the account
+    async fn email(&self, ctx: &Context<'_>) -> Option<&str> {
+        match ctx.data_opt::<Session>() {
+            Some(session) if session.account_id == self.0.id => {
+                Some(&self.0.email)
+            }
+            _ => None,
+        }
+    }
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// At most 100 questions, 20 by default
+    #[graphql(
+        complexity = "questions_limit(limit) as usize * child_complexity"
+    )]
+    async fn questions(
+        &self,
+        ctx: &Context<'_>,
+        limit: Option<i32>,
+        #[graphql(default)] offset: i32,
+        #[graphql(default)] featured: bool,
+    ) -> async_graphql::Result<Vec<QuestionObject>> {
+        let questions = ctx
+            .data_unchecked::<Store>()
+            .get_questions(Some(questions_limit(limit)), offset, featured)
+            .await
+            .map_err(|e| graphql_error(&e))?;
+        Ok(questions.into_iter().map(QuestionObject).collect())
+    }
+
+    async fn question(
+        &self,
+        ctx: &Context<'_>,
+        id: i32,
+    ) -> async_graphql::Result<Option<QuestionObject>> {
+        match ctx.data_unchecked::<Store>().get_question_by_id(id).await {
+            Ok(question) => Ok(Some(QuestionObject(question))),
+            Err(handle_errors::Error::DatabaseQueryError(
+                sqlx::Error::RowNotFound,
+            )) => Ok(None),
+            Err(e) => Err(graphql_error(&e)),
+        }
+    }
+
+    /// The account of the session token
+    async fn me(
+        &self,
+        ctx: &Context<'_>,
+    ) -> async_graphql::Result<AccountObject> {
+        let session = session(ctx)?;
+        let account = ctx
+            .data_unchecked::<Store>()
+            .get_account_by_id(&session.account_id)
+            .await
+            .map_err(|e| graphql_error(&e))?;
+        Ok(AccountObject(account))
+    }
+}
+
+#[derive(InputObject)]
+pub struct NewQuestionInput {
+    title: String,
+    content: String,
+    tags: Option<Vec<String>>,
+}
+
+#[derive(InputObject)]
+pub struct NewAnswerInput {
+    question_id: i32,
+    content: String,
+}
+
+pub struct MutationRoot;
+
+#[Object]
+impl MutationRoot {
+    async fn add_question(
+        &self,
+        ctx: &Context<'_>,
+        input: NewQuestionInput,
+    ) -> async_graphql::Result<QuestionObject> {
+        let session = session(ctx)?;
+        let new_question = NewQuestion {
+            title: input.title,
+            content: input.content,
+            tags: input.tags,
+        };
+        validate(&new_question)?;
+        let question = create_question(
+            ctx.data_unchecked::<Store>(),
+            ctx.data_unchecked::<EventBus>(),
+            &session.account_id,
+            new_question,
+        )
+        .await
+        .map_err(|e| graphql_error(&e))?;
+        Ok(QuestionObject(question))
+    }
+
+    async fn add_answer(
+        &self,
+        ctx: &Context<'_>,
+        input: NewAnswerInput,
+    ) -> async_graphql::Result<AnswerObject> {
+        let session = session(ctx)?;
+        let new_answer = NewAnswer {
+            content: input.content,
+            question_id: QuestionId(input.question_id),
+        };
+        validate(&new_answer)?;
+        let answer = create_answer(
+            ctx.data_unchecked::<Store>(),
+            ctx.data_unchecked::<EventBus>(),
+            &session.account_id,
+            new_answer,
+        )
+        .await
+        .map_err(|e| graphql_error(&e))?;
+        Ok(AnswerObject(answer))
+    }
+}
+
+#[cfg(test)]
+mod graphql_tests {
+    use super::*;
+
+    #[test]
+    fn schema_exposes_questions_answers_and_accounts() {
+        // Arrange
+        let schema =
+            Schema::build(QueryRoot, MutationRoot, EmptySubscription)
+                .finish();
+        // Act
+        let sdl = schema.sdl();
+        // Assert
+        assert!(sdl.contains("answers: [Answer!]!"));
+        assert!(sdl.contains("author: Account"));
+        assert!(sdl.contains("addQuestion(input: NewQuestionInput!)"));
+    }
+
+    #[tokio::test]
+    async fn mutations_require_a_session() {
+        // Arrange
+        let schema =
+            Schema::build(QueryRoot, MutationRoot, EmptySubscription)
+                .finish();
+        let query = r#"mutation {
+            addAnswer(input: { questionId: 1, content: "Hi" }) { id }
+        }"#;
+        // Act
+        let res = schema.execute(query).await;
+        // Assert
+        let extensions = res.errors[0].extensions.as_ref().unwrap();
+        assert_eq!(
+            extensions.get("code"),
+            Some(&async_graphql::Value::from("unauthorized"))
+        );
+    }
+
+    #[tokio::test]
+    async fn expensive_queries_are_refused() {
+        // Arrange
+        let store = Store {
+            connection: sqlx::postgres::PgPoolOptions::new()
+                .acquire_timeout(std::time::Duration::from_millis(10))
+                .connect_lazy("postgres://127.0.0.1:1/none")
+                .unwrap(),
+        };
+        let schema = build_schema(store.clone(), EventBus::new(store));
+        let query = r#"{
+            questions(limit: 100) {
+                id title content contentHtml tags version status bounty
+                createdOn updatedOn answerCount score
+                answers {
+                    id content contentHtml questionId accepted createdOn
+                    author { id username displayName avatarUrl email }
+                }
+                author { id username displayName avatarUrl email }
+            }
+        }"#;
+        // Act
+        let res = schema.execute(query).await;
+        // Assert
+        assert_eq!(res.errors[0].message, "Query is too complex.");
+    }
+}
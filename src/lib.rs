@@ -1,27 +1,59 @@
 #![warn(clippy::all)]
 
 use sqlx::migrate;
+use std::convert::Infallible;
+use std::sync::Arc;
 use tracing_subscriber::fmt::format::FmtSpan;
-use warp::{http::Method, reply::Reply, Filter};
+use warp::hyper::service::{make_service_fn, service_fn, Service};
+use warp::hyper::Server;
+use warp::{http::Method, path::FullPath, reply::Reply, Filter};
 
 use handle_errors::{return_error, Error};
 
+mod badges;
+mod blobs;
+mod bounties;
 pub mod config;
+mod events;
+mod graphql;
+mod markdown;
+mod mentions;
+mod openapi;
 mod profanity;
 mod routes;
+mod snippets;
 mod store;
 mod types;
+mod validation;
+mod webhooks;
+
+/// Sent in the `Deprecation` header of the unversioned routes
+/// (RFC 9745), the date they got replaced by `/api/v1`
+const UNVERSIONED_DEPRECATION: &str = "@1792281600";
 
 async fn build_routes(
     store: store::Store,
+    events: events::EventBus,
+    blobs: Arc<dyn blobs::BlobStore>,
 ) -> impl Filter<Extract = impl Reply> + Clone {
+    let schema = graphql::build_schema(store.clone(), events.clone());
     let store_filter = warp::any().map(move || store.clone());
+    let events_filter = warp::any().map(move || events.clone());
+    let blobs_filter = warp::any().map(move || blobs.clone());
+    let schema_filter = warp::any().map(move || schema.clone());
 
     let cors = warp::cors()
         .allow_any_origin()
-        .allow_header("content-type")
+        .allow_headers(vec![
+            "content-type",
+            "authorization",
+            "if-match",
+            "if-none-match",
+        ])
+        .expose_headers(vec!["etag", "location", "deprecation", "link"])
         .allow_methods(&[
             Method::PUT,
+            Method::PATCH,
             Method::DELETE,
             Method::POST,
             Method::GET,
@@ -38,7 +70,7 @@ async fn build_routes(
                 "get_questions request",
                 method = %info.method(),
                 path = %info.path(),
-                id = %uuid::Uuid::new_v4(),
+                id = %handle_errors::request_id(),
             )
         }));
 
@@ -46,8 +78,10 @@ async fn build_routes(
         .and(warp::path("questions"))
         .and(warp::path::end())
         .and(routes::authentication::auth())
+        .and(routes::base_path())
         .and(store_filter.clone())
-        .and(warp::body::json())
+        .and(events_filter.clone())
+        .and(validation::json_body())
         .and_then(routes::question::add_question);
 
     let update_question = warp::put()
@@ -56,33 +90,103 @@ async fn build_routes(
         .and(warp::path::end())
         .and(routes::authentication::auth())
         .and(store_filter.clone())
-        .and(warp::body::json())
+        .and(events_filter.clone())
+        .and(warp::header::optional::<String>("if-match"))
+        .and(validation::json_body())
         .and_then(routes::question::update_question);
 
+    let patch_question = warp::patch()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and(events_filter.clone())
+        .and(warp::header::optional::<String>("if-match"))
+        .and(validation::merge_patch_body())
+        .and_then(routes::question::patch_question);
+
     let delete_question = warp::delete()
         .and(warp::path("questions"))
         .and(warp::path::param::<i32>())
         .and(warp::path::end())
         .and(routes::authentication::auth())
         .and(store_filter.clone())
+        .and(events_filter.clone())
+        .and(blobs_filter.clone())
+        .and(warp::header::optional::<String>("if-match"))
         .and_then(routes::question::delete_question);
 
     let get_question_by_id = warp::get()
         .and(warp::path("questions"))
         .and(warp::path::param::<i32>())
         .and(warp::path::end())
+        .and(routes::base_path())
         .and(store_filter.clone())
+        .and(warp::header::optional::<String>("if-none-match"))
+        .and(warp::query())
         .and_then(routes::question::get_question_by_id);
 
+    let get_similar_questions = warp::get()
+        .and(warp::path("questions"))
+        .and(warp::path("similar"))
+        .and(warp::path::end())
+        .and(warp::query())
+        .and(store_filter.clone())
+        .and_then(routes::question::get_similar_questions);
+
+    let mark_duplicate = warp::post()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("duplicate"))
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and(validation::json_body())
+        .and_then(routes::question::mark_duplicate);
+
+    let unmark_duplicate = warp::delete()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("duplicate"))
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and_then(routes::question::unmark_duplicate);
+
+    let set_question_status = warp::put()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("status"))
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and(events_filter.clone())
+        .and(validation::json_body())
+        .and_then(routes::question::set_question_status);
+
     let add_answer = warp::post()
         .and(warp::path("questions"))
         .and(warp::path("answers"))
         .and(warp::path::end())
         .and(routes::authentication::auth())
+        .and(routes::base_path())
         .and(store_filter.clone())
-        .and(warp::body::form())
+        .and(events_filter.clone())
+        .and(validation::form_body())
         .and_then(routes::answer::add_answer);
 
+    let add_answer_v2 = warp::post()
+        .and(warp::path("questions"))
+        .and(warp::path("answers"))
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(routes::base_path())
+        .and(store_filter.clone())
+        .and(events_filter.clone())
+        .and(validation::json_body())
+        .and_then(routes::answer::add_answer_v2);
+
     let get_answers_by_question_id = warp::get()
         .and(warp::path("questions"))
         .and(warp::path::param::<i32>())
@@ -91,32 +195,608 @@ async fn build_routes(
         .and(store_filter.clone())
         .and_then(routes::answer::get_answers_by_question_id);
 
+    let get_answer_by_id = warp::get()
+        .and(warp::path("answers"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path::end())
+        .and(store_filter.clone())
+        .and_then(routes::answer::get_answer_by_id);
+
+    let question_events = warp::get()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("events"))
+        .and(warp::path::end())
+        .and(store_filter.clone())
+        .and(events_filter.clone())
+        .and_then(routes::events::question_events);
+
+    let websocket = warp::get()
+        .and(warp::path("ws"))
+        .and(warp::path::end())
+        .and(warp::ws())
+        .and(events_filter)
+        .and_then(routes::events::websocket);
+
+    let add_webhook = warp::post()
+        .and(warp::path("webhooks"))
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(routes::base_path())
+        .and(store_filter.clone())
+        .and(validation::json_body())
+        .and_then(routes::webhook::add_webhook);
+
+    let get_webhooks = warp::get()
+        .and(warp::path("webhooks"))
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and_then(routes::webhook::get_webhooks);
+
+    let delete_webhook = warp::delete()
+        .and(warp::path("webhooks"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and_then(routes::webhook::delete_webhook);
+
+    let get_webhook_deliveries = warp::get()
+        .and(warp::path("webhooks"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("deliveries"))
+        .and(warp::path::end())
+        .and(warp::query())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and_then(routes::webhook::get_webhook_deliveries);
+
+    let get_notifications = warp::get()
+        .and(warp::path("notifications"))
+        .and(warp::path::end())
+        .and(warp::query())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and_then(routes::notification::get_notifications);
+
+    let get_mentions = warp::get()
+        .and(warp::path("accounts"))
+        .and(warp::path("me"))
+        .and(warp::path("mentions"))
+        .and(warp::path::end())
+        .and(warp::query())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and_then(routes::notification::get_mentions);
+
+    let read_notification = warp::post()
+        .and(warp::path("notifications"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("read"))
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and_then(routes::notification::read_notification);
+
+    let read_all_notifications = warp::post()
+        .and(warp::path("notifications"))
+        .and(warp::path("read-all"))
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and_then(routes::notification::read_all_notifications);
+
+    let get_notification_preferences = warp::get()
+        .and(warp::path("notifications"))
+        .and(warp::path("preferences"))
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and_then(routes::notification::get_notification_preferences);
+
+    let update_notification_preferences = warp::put()
+        .and(warp::path("notifications"))
+        .and(warp::path("preferences"))
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and(validation::json_body())
+        .and_then(routes::notification::update_notification_preferences);
+
+    let watch_question = warp::post()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("watch"))
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and_then(routes::watch::watch_question);
+
+    let unwatch_question = warp::delete()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("watch"))
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and_then(routes::watch::unwatch_question);
+
+    let watch_tag = warp::post()
+        .and(warp::path("tags"))
+        .and(warp::path::param::<String>())
+        .and(warp::path("watch"))
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and_then(routes::watch::watch_tag);
+
+    let unwatch_tag = warp::delete()
+        .and(warp::path("tags"))
+        .and(warp::path::param::<String>())
+        .and(warp::path("watch"))
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and_then(routes::watch::unwatch_tag);
+
+    let get_watching = warp::get()
+        .and(warp::path("accounts"))
+        .and(warp::path("me"))
+        .and(warp::path("watching"))
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and_then(routes::watch::get_watching);
+
+    let get_tags = warp::get()
+        .and(warp::path("tags"))
+        .and(warp::path::end())
+        .and(warp::query())
+        .and(store_filter.clone())
+        .and_then(routes::tag::get_tags);
+
+    let get_tag_questions = warp::get()
+        .and(warp::path("tags"))
+        .and(warp::path::param::<String>())
+        .and(warp::path("questions"))
+        .and(warp::path::end())
+        .and(warp::query())
+        .and(store_filter.clone())
+        .and_then(routes::tag::get_tag_questions);
+
+    let update_tag = warp::put()
+        .and(warp::path("tags"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and(validation::json_body())
+        .and_then(routes::tag::update_tag);
+
+    let vote_question = warp::put()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("vote"))
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and(validation::json_body())
+        .and_then(routes::vote::vote_question);
+
+    let unvote_question = warp::delete()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("vote"))
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and_then(routes::vote::unvote_question);
+
+    let vote_answer = warp::put()
+        .and(warp::path("answers"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("vote"))
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and(validation::json_body())
+        .and_then(routes::vote::vote_answer);
+
+    let unvote_answer = warp::delete()
+        .and(warp::path("answers"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("vote"))
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and_then(routes::vote::unvote_answer);
+
+    let accept_answer = warp::post()
+        .and(warp::path("answers"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("accept"))
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and_then(routes::answer::accept_answer);
+
+    let unaccept_answer = warp::delete()
+        .and(warp::path("answers"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("accept"))
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and_then(routes::answer::unaccept_answer);
+
+    let get_bounty = warp::get()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("bounty"))
+        .and(warp::path::end())
+        .and(store_filter.clone())
+        .and_then(routes::bounty::get_bounty);
+
+    let offer_bounty = warp::post()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("bounty"))
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and(validation::json_body())
+        .and_then(routes::bounty::offer_bounty);
+
+    let award_bounty = warp::post()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("bounty"))
+        .and(warp::path("award"))
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and(validation::json_body())
+        .and_then(routes::bounty::award_bounty);
+
+    let get_reputation = warp::get()
+        .and(warp::path("accounts"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("reputation"))
+        .and(warp::path::end())
+        .and(store_filter.clone())
+        .and_then(routes::reputation::get_reputation);
+
+    let get_leaderboard = warp::get()
+        .and(warp::path("leaderboard"))
+        .and(warp::path::end())
+        .and(warp::query())
+        .and(store_filter.clone())
+        .and_then(routes::reputation::get_leaderboard);
+
+    let get_badges = warp::get()
+        .and(warp::path("badges"))
+        .and(warp::path::end())
+        .and(store_filter.clone())
+        .and_then(routes::badge::get_badges);
+
+    let get_account_badges = warp::get()
+        .and(warp::path("accounts"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("badges"))
+        .and(warp::path::end())
+        .and(store_filter.clone())
+        .and_then(routes::badge::get_account_badges);
+
     let registration = warp::post()
         .and(warp::path("registration"))
         .and(warp::path::end())
+        .and(routes::base_path())
         .and(store_filter.clone())
-        .and(warp::body::json())
+        .and(validation::json_body())
         .and_then(routes::authentication::register);
 
     let login = warp::post()
         .and(warp::path("login"))
         .and(warp::path::end())
         .and(store_filter.clone())
+        .and(warp::body::content_length_limit(validation::MAX_BODY_SIZE))
         .and(warp::body::json())
         .and_then(routes::authentication::login);
 
-    get_questions
+    let graphql = warp::post()
+        .and(warp::path("graphql"))
+        .and(warp::path::end())
+        .and(routes::authentication::optional_auth())
+        .and(store_filter.clone())
+        .and(schema_filter)
+        .and(warp::body::content_length_limit(validation::MAX_BODY_SIZE))
+        .and(warp::body::json())
+        .and_then(graphql::graphql);
+
+    let openapi = warp::get()
+        .and(warp::path("openapi.json"))
+        .and(warp::path::end())
+        .and_then(openapi::get_openapi);
+
+    let openapi_v2 = warp::get()
+        .and(warp::path("api"))
+        .and(warp::path("v2"))
+        .and(warp::path("openapi.json"))
+        .and(warp::path::end())
+        .and_then(openapi::get_openapi_v2);
+
+    let docs = warp::get()
+        .and(warp::path("docs"))
+        .and(warp::path::end())
+        .and_then(openapi::get_docs);
+
+    // Every group is boxed, a single `Or` chain of all the routes nests
+    // its futures deep enough to overflow the stack of a worker thread
+    let question_routes = get_questions
         .or(add_question)
-        .or(add_answer)
         .or(update_question)
+        .or(patch_question)
         .or(delete_question)
         .or(get_question_by_id)
+        .or(get_similar_questions)
+        .or(mark_duplicate)
+        .or(unmark_duplicate)
+        .or(set_question_status)
         .or(get_answers_by_question_id)
-        .or(registration)
+        .or(get_answer_by_id)
+        .or(question_events)
+        .or(websocket)
+        .boxed();
+
+    let webhook_routes = add_webhook
+        .or(get_webhooks)
+        .or(delete_webhook)
+        .or(get_webhook_deliveries)
+        .boxed();
+
+    let notification_routes = get_notifications
+        .or(get_mentions)
+        .or(read_notification)
+        .or(read_all_notifications)
+        .or(get_notification_preferences)
+        .or(update_notification_preferences)
+        .or(watch_question)
+        .or(unwatch_question)
+        .or(watch_tag)
+        .or(unwatch_tag)
+        .or(get_watching)
+        .boxed();
+
+    let get_snippets = warp::get()
+        .and(warp::path("snippets"))
+        .and(warp::path::end())
+        .and(warp::query())
+        .and(routes::base_path())
+        .and(store_filter.clone())
+        .and_then(routes::snippet::get_snippets);
+
+    // Leaves room for the headers and boundaries of the other parts
+    let upload_limit = types::attachment::MAX_ATTACHMENT_SIZE + 64 * 1024;
+
+    let add_question_attachment = warp::post()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("attachments"))
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(routes::base_path())
+        .and(store_filter.clone())
+        .and(blobs_filter.clone())
+        .and(warp::body::content_length_limit(upload_limit))
+        .and(warp::multipart::form().max_length(upload_limit))
+        .and_then(routes::attachment::add_question_attachment);
+
+    let add_answer_attachment = warp::post()
+        .and(warp::path("answers"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("attachments"))
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(routes::base_path())
+        .and(store_filter.clone())
+        .and(blobs_filter.clone())
+        .and(warp::body::content_length_limit(upload_limit))
+        .and(warp::multipart::form().max_length(upload_limit))
+        .and_then(routes::attachment::add_answer_attachment);
+
+    let get_question_attachments = warp::get()
+        .and(warp::path("questions"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("attachments"))
+        .and(warp::path::end())
+        .and(store_filter.clone())
+        .and_then(routes::attachment::get_question_attachments);
+
+    let get_answer_attachments = warp::get()
+        .and(warp::path("answers"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("attachments"))
+        .and(warp::path::end())
+        .and(store_filter.clone())
+        .and_then(routes::attachment::get_answer_attachments);
+
+    let get_attachment = warp::get()
+        .and(warp::path("attachments"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path::end())
+        .and(store_filter.clone())
+        .and(blobs_filter.clone())
+        .and_then(routes::attachment::get_attachment);
+
+    let delete_attachment = warp::delete()
+        .and(warp::path("attachments"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and(blobs_filter)
+        .and_then(routes::attachment::delete_attachment);
+
+    let attachment_routes = add_question_attachment
+        .or(add_answer_attachment)
+        .or(get_question_attachments)
+        .or(get_answer_attachments)
+        .or(get_attachment)
+        .or(delete_attachment)
+        .boxed();
+
+    let import_questions = warp::post()
+        .and(warp::path("admin"))
+        .and(warp::path("import"))
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and(warp::header::optional::<String>("content-type"))
+        .and(warp::body::content_length_limit(
+            types::transfer::MAX_IMPORT_SIZE,
+        ))
+        .and(warp::body::bytes())
+        .and_then(routes::admin::import_questions);
+
+    let export_questions = warp::get()
+        .and(warp::path("admin"))
+        .and(warp::path("export"))
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and_then(routes::admin::export_questions);
+
+    let admin_routes = import_questions.or(export_questions).boxed();
+
+    let tag_routes = get_tags
+        .or(get_tag_questions)
+        .or(update_tag)
+        .or(get_snippets)
+        .boxed();
+
+    let reputation_routes = vote_question
+        .or(unvote_question)
+        .or(vote_answer)
+        .or(unvote_answer)
+        .or(accept_answer)
+        .or(unaccept_answer)
+        .or(get_reputation)
+        .or(get_leaderboard)
+        .or(get_badges)
+        .or(get_account_badges)
+        .or(get_bounty)
+        .or(offer_bounty)
+        .or(award_bounty)
+        .boxed();
+
+    let get_user = warp::get()
+        .and(warp::path("users"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path::end())
+        .and(store_filter.clone())
+        .and_then(routes::user::get_user);
+
+    let get_user_questions = warp::get()
+        .and(warp::path("users"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("questions"))
+        .and(warp::path::end())
+        .and(warp::query())
+        .and(store_filter.clone())
+        .and_then(routes::user::get_user_questions);
+
+    let get_user_answers = warp::get()
+        .and(warp::path("users"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("answers"))
+        .and(warp::path::end())
+        .and(warp::query())
+        .and(store_filter.clone())
+        .and_then(routes::user::get_user_answers);
+
+    let get_user_activity = warp::get()
+        .and(warp::path("users"))
+        .and(warp::path::param::<i32>())
+        .and(warp::path("activity"))
+        .and(warp::path::end())
+        .and(warp::query())
+        .and(store_filter.clone())
+        .and_then(routes::user::get_user_activity);
+
+    let update_profile = warp::put()
+        .and(warp::path("accounts"))
+        .and(warp::path("me"))
+        .and(warp::path("profile"))
+        .and(warp::path::end())
+        .and(routes::authentication::auth())
+        .and(store_filter.clone())
+        .and(validation::json_body())
+        .and_then(routes::user::update_profile);
+
+    let account_routes = registration
         .or(login)
+        .or(get_user)
+        .or(get_user_questions)
+        .or(get_user_answers)
+        .or(get_user_activity)
+        .or(update_profile)
+        .boxed();
+
+    let shared = question_routes
+        .or(webhook_routes)
+        .or(notification_routes)
+        .or(tag_routes)
+        .or(reputation_routes)
+        .or(account_routes)
+        .or(attachment_routes)
+        .or(admin_routes)
+        .boxed();
+
+    // Only the handlers whose contract changed in v2 differ between the
+    // versions, a v2 request never falls back to a v1 handler
+    let v1 = add_answer.or(shared.clone()).boxed();
+    let v2 = add_answer_v2.or(shared).boxed();
+
+    let api_v1 = warp::path("api").and(warp::path("v1")).and(v1.clone());
+    let api_v2 = warp::path("api").and(warp::path("v2")).and(v2);
+
+    // The routes from before versioning, kept as aliases of v1
+    let unversioned =
+        warp::path::full().and(v1).map(|path: FullPath, reply| {
+            let successor = format!(
+                "<{}{}>; rel=\"successor-version\"",
+                routes::BASE_PATH,
+                path.as_str()
+            );
+            warp::reply::with_header(
+                warp::reply::with_header(
+                    reply,
+                    "deprecation",
+                    UNVERSIONED_DEPRECATION,
+                ),
+                "link",
+                successor,
+            )
+        });
+
+    api_v1
+        .or(api_v2)
+        .or(graphql)
+        .or(openapi)
+        .or(openapi_v2)
+        .or(docs)
+        .or(unversioned)
         .with(cors)
-        .with(warp::trace::request())
         .recover(return_error)
+        .with(warp::trace(|info| {
+            tracing::info_span!(
+                "request",
+                method = %info.method(),
+                path = %info.path(),
+                id = %handle_errors::request_id(),
+            )
+        }))
 }
 
 pub async fn setup_store(
@@ -154,10 +834,51 @@ pub async fn setup_store(
         .with_span_events(FmtSpan::CLOSE)
         .init();
 
+    // Applies changed reputation points to the existing ledger, the
+    // store already logs the error
+    let _ = store.recompute_reputation().await;
+    // Renders the Markdown of content written before the HTML was cached
+    let _ = store.render_missing_html().await;
+    // Indexes the snippets of content written before they were indexed
+    let _ = store.index_missing_snippets().await;
+
     Ok(store)
 }
 
 pub async fn run(config: config::Config, store: store::Store) {
-    let routes = build_routes(store).await;
-    warp::serve(routes).run(([127, 0, 0, 1], config.port)).await;
+    let events = events::EventBus::new(store.clone());
+    tokio::spawn(events.clone().listen());
+    tokio::spawn(webhooks::run_worker(store.clone()));
+    let badges = badges::load_rules(config.badges_file.as_deref())
+        .expect("Badge rules cannot be loaded!");
+    let badges = store
+        .sync_badges(&badges)
+        .await
+        .expect("Badges cannot be saved!");
+    tokio::spawn(badges::run_evaluator(store.clone(), badges));
+    tokio::spawn(bounties::run_expiry(store.clone()));
+    let blobs = blobs::from_config(&config);
+    let routes = build_routes(store, events, blobs).await;
+
+    // Every request runs with its own id, shared by its trace span and
+    // its problem details
+    let service = warp::service(routes);
+    let make_service = make_service_fn(move |_| {
+        let service = service.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                // The filters already run when the future is created
+                let id = uuid::Uuid::new_v4().to_string();
+                let response = handle_errors::REQUEST_ID
+                    .sync_scope(id.clone(), || {
+                        service.clone().call(request)
+                    });
+                handle_errors::REQUEST_ID.scope(id, response)
+            }))
+        }
+    });
+    let addr = ([127, 0, 0, 1], config.port).into();
+    if let Err(e) = Server::bind(&addr).serve(make_service).await {
+        tracing::event!(tracing::Level::ERROR, "{:?}", e);
+    }
 }
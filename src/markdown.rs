@@ -0,0 +1,118 @@
+use pulldown_cmark::{
+    html, CodeBlockKind, Event, Options, Parser, Tag, TagEnd,
+};
+
+/// Renders CommonMark to HTML without scripts, iframes, styles or event
+/// handlers. Fenced code blocks keep their language as a
+/// `language-<name>` class for syntax highlighters.
+pub fn render(source: &str) -> String {
+    let mut unsafe_html = String::new();
+    html::push_html(
+        &mut unsafe_html,
+        Parser::new_ext(source, Options::empty()),
+    );
+
+    ammonia::Builder::default()
+        .add_tag_attributes("code", &["class"])
+        .attribute_filter(|element, attribute, value| {
+            match (element, attribute) {
+                ("code", "class") if !is_language_class(value) => None,
+                _ => Some(value.into()),
+            }
+        })
+        .clean(&unsafe_html)
+        .to_string()
+}
+
+/// Info string and code of every fenced code block, in order
+pub fn fenced_code_blocks(source: &str) -> Vec<(String, String)> {
+    let mut blocks = Vec::new();
+    let mut current: Option<(String, String)> = None;
+    for event in Parser::new_ext(source, Options::empty()) {
+        match event {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) => {
+                current = Some((info.to_string(), String::new()));
+            }
+            Event::Text(text) => {
+                if let Some((_, code)) = current.as_mut() {
+                    code.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                blocks.extend(current.take());
+            }
+            _ => (),
+        }
+    }
+    blocks
+}
+
+/// Text of the prose, without code spans and code blocks. Blocks and
+/// line breaks are separated by a newline.
+pub fn prose_text(source: &str) -> String {
+    let mut text = String::new();
+    let mut in_code_block = false;
+    for event in Parser::new_ext(source, Options::empty()) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(TagEnd::CodeBlock) => in_code_block = false,
+            Event::Text(chunk) if !in_code_block => text.push_str(&chunk),
+            Event::Code(_) => text.push(' '),
+            Event::SoftBreak | Event::HardBreak | Event::End(_) => {
+                text.push('\n')
+            }
+            _ => (),
+        }
+    }
+    text
+}
+
+fn is_language_class(class: &str) -> bool {
+    class.strip_prefix("language-").is_some_and(|language| {
+        !language.is_empty()
+            && language
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+#-_.".contains(c))
+    })
+}
+
+#[cfg(test)]
+mod markdown_tests {
+    use super::*;
+
+    #[test]
+    fn renders_commonmark_without_unsafe_html() {
+        // Arrange
+        let source = "# Title\n\n```rust\nfn main() {}\n```\n\n\
+            <script>alert(1)</script><iframe src=\"x\"></iframe>\n\n\
+            [link](javascript:alert(1)) <b onclick=\"x\">bold</b>";
+        // Act
+        let html = render(source);
+        // Assert
+        assert!(html.contains("<h1>Title</h1>"));
+        assert!(
+            html.contains("<code class=\"language-rust\">fn main() {}")
+        );
+        assert!(!html.contains("script"));
+        assert!(!html.contains("iframe"));
+        assert!(!html.contains("javascript"));
+        assert!(!html.contains("onclick"));
+    }
+
+    #[test]
+    fn only_fenced_code_blocks_are_extracted() {
+        // Arrange
+        let source = "Text `inline`\n\n    indented\n\n\
+            ```rust title\nfn a() {}\n```\n\n~~~\nb\n~~~\n";
+        // Act
+        let blocks = fenced_code_blocks(source);
+        // Assert
+        assert_eq!(
+            blocks,
+            vec![
+                ("rust title".to_string(), "fn a() {}\n".to_string()),
+                (String::new(), "b\n".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use crate::markdown::prose_text;
+
+pub const MIN_USERNAME_LENGTH: usize = 3;
+pub const MAX_USERNAME_LENGTH: usize = 30;
+/// Mentions past this number in a single post are ignored
+pub const MAX_MENTIONS: usize = 10;
+
+fn is_username_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+pub fn is_valid_username(username: &str) -> bool {
+    (MIN_USERNAME_LENGTH..=MAX_USERNAME_LENGTH).contains(&username.len())
+        && username.chars().all(is_username_char)
+}
+
+/// Lowercase usernames of the `@username` mentions of a Markdown text,
+/// in order and without duplicates. Mentions in code and the `@` of
+/// e-mail addresses are left out.
+pub fn extract(content: &str) -> Vec<String> {
+    let text = prose_text(content);
+    let mut usernames: Vec<String> = Vec::new();
+    let mut previous = None;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let after_word = previous.is_some_and(|p: char| {
+            is_username_char(p) || ".-@".contains(p)
+        });
+        previous = Some(c);
+        if c != '@' || after_word {
+            continue;
+        }
+        let start = i + 1;
+        let mut end = start;
+        while let Some((j, c)) = chars.peek().copied() {
+            if !is_username_char(c) {
+                break;
+            }
+            end = j + c.len_utf8();
+            previous = Some(c);
+            chars.next();
+        }
+        let username = text[start..end].to_ascii_lowercase();
+        if is_valid_username(&username) && !usernames.contains(&username) {
+            usernames.push(username);
+            if usernames.len() == MAX_MENTIONS {
+                break;
+            }
+        }
+    }
+    usernames
+}
+
+#[cfg(test)]
+mod mentions_tests {
+    use super::*;
+
+    #[test]
+    fn mentions_outside_code() {
+        // Arrange
+        let content = "Thanks @Alice and @bob_2, ask me@mail.com\n\n\
+            Not `@inline` nor @ab\n\n\
+            ```java\n@Override\n```\n\
+            @alice again";
+        // Act
+        let usernames = extract(content);
+        // Assert
+        assert_eq!(usernames, ["alice", "bob_2"]);
+    }
+}
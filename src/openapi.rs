@@ -0,0 +1,319 @@
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, SecurityScheme};
+use utoipa::openapi::server::ServerBuilder;
+use utoipa::{Modify, OpenApi, Path};
+
+use crate::routes;
+
+/// OpenAPI document of every route registered in `build_routes`
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Q&A web service API"),
+    servers((url = "/api/v1", description = "Current version of the API")),
+    paths(
+        routes::question::get_questions,
+        routes::question::add_question,
+        routes::question::update_question,
+        routes::question::patch_question,
+        routes::question::delete_question,
+        routes::question::get_question_by_id,
+        routes::question::get_similar_questions,
+        routes::question::mark_duplicate,
+        routes::question::unmark_duplicate,
+        routes::question::set_question_status,
+        routes::bounty::get_bounty,
+        routes::bounty::offer_bounty,
+        routes::bounty::award_bounty,
+        routes::answer::add_answer,
+        routes::answer::get_answers_by_question_id,
+        routes::answer::get_answer_by_id,
+        routes::answer::accept_answer,
+        routes::answer::unaccept_answer,
+        routes::attachment::add_question_attachment,
+        routes::attachment::add_answer_attachment,
+        routes::attachment::get_question_attachments,
+        routes::attachment::get_answer_attachments,
+        routes::attachment::get_attachment,
+        routes::attachment::delete_attachment,
+        routes::vote::vote_question,
+        routes::vote::unvote_question,
+        routes::vote::vote_answer,
+        routes::vote::unvote_answer,
+        routes::reputation::get_reputation,
+        routes::reputation::get_leaderboard,
+        routes::badge::get_badges,
+        routes::badge::get_account_badges,
+        routes::events::question_events,
+        routes::webhook::add_webhook,
+        routes::webhook::get_webhooks,
+        routes::webhook::delete_webhook,
+        routes::webhook::get_webhook_deliveries,
+        routes::notification::get_notifications,
+        routes::notification::get_mentions,
+        routes::notification::read_notification,
+        routes::notification::read_all_notifications,
+        routes::notification::get_notification_preferences,
+        routes::notification::update_notification_preferences,
+        routes::watch::watch_question,
+        routes::watch::unwatch_question,
+        routes::watch::watch_tag,
+        routes::watch::unwatch_tag,
+        routes::watch::get_watching,
+        routes::tag::get_tags,
+        routes::tag::get_tag_questions,
+        routes::tag::update_tag,
+        routes::snippet::get_snippets,
+        routes::authentication::register,
+        routes::authentication::login,
+        routes::user::get_user,
+        routes::user::get_user_questions,
+        routes::user::get_user_answers,
+        routes::user::get_user_activity,
+        routes::user::update_profile,
+        routes::admin::import_questions,
+        routes::admin::export_questions,
+    ),
+    modifiers(&TokenSecurity),
+    tags(
+        (name = "questions", description = "Asking and editing questions"),
+        (name = "answers", description = "Answering questions"),
+        (name = "attachments", description = "Files attached to questions and answers"),
+        (name = "accounts", description = "Registration and login"),
+        (name = "users", description = "Public profiles and activity of the accounts"),
+        (name = "notifications", description = "Inbox of the account"),
+        (name = "votes", description = "Voting on questions and answers"),
+        (name = "reputation", description = "Reputation earned from votes and accepted answers"),
+        (name = "badges", description = "Badges awarded automatically for activity"),
+        (name = "bounties", description = "Reputation offered for answers to a question"),
+        (name = "tags", description = "Tag descriptions and synonyms"),
+        (name = "snippets", description = "Code blocks of questions and answers"),
+        (name = "watching", description = "Following questions and tags"),
+        (name = "webhooks", description = "Outgoing webhooks for question and answer events"),
+        (name = "admin", description = "Import and export of the questions and answers"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Declares the PASETO token sent in the `Authorization` header
+struct TokenSecurity;
+
+impl Modify for TokenSecurity {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "token",
+                SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(
+                    "Authorization",
+                ))),
+            )
+        }
+    }
+}
+
+/// OpenAPI document of `/api/v2`, the v1 document with the handlers
+/// `build_routes` registers in front of the v1 routes
+pub fn api_v2() -> utoipa::openapi::OpenApi {
+    use routes::answer::__path_add_answer_v2 as AddAnswerV2;
+
+    let mut openapi = ApiDoc::openapi();
+    openapi.servers = Some(vec![ServerBuilder::new()
+        .url("/api/v2")
+        .description(Some("Answers take a JSON body"))
+        .build()]);
+    openapi.paths.add_path_operation(
+        AddAnswerV2::path(),
+        AddAnswerV2::methods(),
+        AddAnswerV2::operation(),
+    );
+    openapi
+}
+
+const DOCS_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+  <head>
+    <title>Q&amp;A web service API</title>
+    <meta charset="utf-8"/>
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+  </head>
+  <body>
+    <redoc spec-url="/openapi.json"></redoc>
+    <script src="https://cdn.redoc.ly/redoc/latest/bundles/redoc.standalone.js"></script>
+  </body>
+</html>
+"#;
+
+pub async fn get_openapi() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&ApiDoc::openapi()))
+}
+
+pub async fn get_openapi_v2() -> Result<impl warp::Reply, warp::Rejection>
+{
+    Ok(warp::reply::json(&api_v2()))
+}
+
+pub async fn get_docs() -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::html(DOCS_PAGE))
+}
+
+#[cfg(test)]
+mod openapi_tests {
+    use super::*;
+    use sqlx::postgres::PgPoolOptions;
+    use std::collections::BTreeSet;
+    use std::env;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use warp::http::StatusCode;
+    use warp::{Filter, Reply};
+
+    use crate::blobs::{BlobStore, FsBlobStore};
+    use crate::events::EventBus;
+    use crate::routes::authentication::issue_token;
+    use crate::store::Store;
+    use crate::types::account::AccountId;
+
+    const METHODS: [&str; 5] = ["GET", "POST", "PUT", "PATCH", "DELETE"];
+
+    /// Documented methods of every path of a document, with the path
+    /// parameters as `1`
+    fn documented_routes(
+        openapi: &utoipa::openapi::OpenApi,
+    ) -> BTreeSet<(&'static str, String)> {
+        let mut routes = BTreeSet::new();
+        for (path, item) in openapi.paths.paths.iter() {
+            let path = path
+                .split('/')
+                .map(|s| if s.starts_with('{') { "1" } else { s })
+                .collect::<Vec<&str>>()
+                .join("/");
+            let documented = [
+                item.get.is_some(),
+                item.post.is_some(),
+                item.put.is_some(),
+                item.patch.is_some(),
+                item.delete.is_some(),
+            ];
+            for (method, documented) in METHODS.into_iter().zip(documented)
+            {
+                if documented {
+                    routes.insert((method, path.clone()));
+                }
+            }
+        }
+        routes
+    }
+
+    /// Paths a new route would likely take: the prefixes of the documented
+    /// paths, alone or followed by an id
+    fn candidate_paths(
+        documented: &BTreeSet<(&str, String)>,
+    ) -> BTreeSet<String> {
+        let mut candidates = BTreeSet::new();
+        for (_, path) in documented {
+            let mut prefix = String::new();
+            for segment in path.split('/').skip(1) {
+                prefix = format!("{}/{}", prefix, segment);
+                candidates.insert(prefix.clone());
+                candidates.insert(format!("{}/1", prefix));
+            }
+        }
+        candidates
+    }
+
+    /// Routes of `build_routes`, on a database which can't be reached
+    async fn routes() -> impl Filter<Extract = impl Reply> + Clone {
+        let store = Store {
+            connection: PgPoolOptions::new()
+                .acquire_timeout(Duration::from_millis(10))
+                .connect_lazy("postgres://127.0.0.1:1/none")
+                .unwrap(),
+        };
+        let events = EventBus::new(store.clone());
+        let blobs: Arc<dyn BlobStore> =
+            Arc::new(FsBlobStore::new(std::env::temp_dir()));
+        crate::build_routes(store, events, blobs).await
+    }
+
+    /// Sends every method on every candidate path through the routes. A
+    /// route matches whenever the answer is neither 404 nor 405.
+    async fn undocumented_or_unserved(
+        openapi: &utoipa::openapi::OpenApi,
+        prefix: &str,
+    ) -> Vec<String> {
+        let routes = routes().await;
+        let documented = documented_routes(openapi);
+        let mut mismatches = Vec::new();
+        for path in candidate_paths(&documented) {
+            for method in METHODS {
+                let status = warp::test::request()
+                    .method(method)
+                    .path(&format!("{}{}", prefix, path))
+                    .reply(&routes)
+                    .await
+                    .status();
+                let served = status != StatusCode::NOT_FOUND
+                    && status != StatusCode::METHOD_NOT_ALLOWED;
+                if served != documented.contains(&(method, path.clone())) {
+                    mismatches.push(format!("{} {}", method, path));
+                }
+            }
+        }
+        mismatches
+    }
+
+    #[tokio::test]
+    async fn every_route_is_documented() {
+        // Arrange
+        let openapi = ApiDoc::openapi();
+        // Act
+        let mismatches =
+            undocumented_or_unserved(&openapi, "/api/v1").await;
+        // Assert
+        assert_eq!(mismatches, Vec::<String>::new());
+    }
+
+    #[tokio::test]
+    async fn v2_answers_take_json() {
+        // Arrange
+        let openapi = api_v2();
+        // Act
+        let mismatches =
+            undocumented_or_unserved(&openapi, "/api/v2").await;
+        let json = serde_json::to_value(&openapi).unwrap();
+        // Assert
+        assert_eq!(mismatches, Vec::<String>::new());
+        let body =
+            &json["paths"]["/questions/answers"]["post"]["requestBody"];
+        assert!(body["content"]["application/json"].is_object());
+    }
+
+    #[tokio::test]
+    async fn v2_answers_refuse_form_bodies() {
+        // Arrange
+        env::set_var("PASETO_KEY", "RANDOM WORDS WINTER DIST POP OS!");
+        let token = issue_token(AccountId(1));
+        let routes = routes().await;
+        // Act
+        let res = warp::test::request()
+            .method("POST")
+            .path("/api/v2/questions/answers")
+            .header("Authorization", token)
+            .header("content-type", "application/x-www-form-urlencoded")
+            .body("question_id=1&content=Hello")
+            .reply(&routes)
+            .await;
+        // Assert
+        assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[test]
+    fn document_is_openapi_3_1() {
+        // Arrange
+        let openapi = ApiDoc::openapi();
+        // Act
+        let json = serde_json::to_value(&openapi).unwrap();
+        // Assert
+        assert!(json["openapi"].as_str().unwrap().starts_with("3.1"));
+        assert!(json["components"]["schemas"]["Question"].is_object());
+        assert!(json["components"]["securitySchemes"]["token"].is_object());
+    }
+}
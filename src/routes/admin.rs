@@ -0,0 +1,231 @@
+use futures_util::stream;
+use handle_errors::{Error, FieldError, Problem};
+use std::collections::HashSet;
+use std::io;
+use tracing::instrument;
+use warp::hyper::body::Bytes;
+use warp::hyper::Body;
+
+use crate::store::Store;
+use crate::types::account::{AccountId, Session};
+use crate::types::transfer::{
+    parse_map, parse_ndjson, ExportedQuestion, FailedRow, ImportReport,
+    ImportedQuestion, ImportedRow, EXPORT_BATCH_SIZE, IMPORT_BATCH_SIZE,
+};
+use crate::validation::{field_error, Validate};
+
+const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+async fn require_admin(
+    store: &Store,
+    account_id: &AccountId,
+) -> Result<(), Error> {
+    if store.is_admin(account_id).await? {
+        Ok(())
+    } else {
+        Err(Error::AdminRequired)
+    }
+}
+
+/// Checks the authors of a parsed question, who must exist
+fn check_authors(
+    question: &ImportedQuestion,
+    accounts: &HashSet<i32>,
+) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    let mut check = |field: &str, account_id: &Option<AccountId>| {
+        if let Some(account_id) = account_id {
+            if !accounts.contains(&account_id.0) {
+                errors.push(field_error(
+                    field,
+                    "must be an existing account",
+                ));
+            }
+        }
+    };
+    check("account_id", &question.account_id);
+    for (index, answer) in question.answers.iter().enumerate() {
+        check(
+            &format!("answers[{}].account_id", index),
+            &answer.account_id,
+        );
+    }
+    errors
+}
+
+fn failed_row(row: &str, error: &Error) -> FailedRow {
+    FailedRow {
+        row: row.to_string(),
+        errors: vec![field_error("row", &error.to_string())],
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/import",
+    tag = "admin",
+    request_body(
+        content = ImportedQuestion,
+        description = "One question per line as `application/x-ndjson`, or an object of questions keyed by their old ids as `application/json`",
+        content_type = "application/x-ndjson",
+    ),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Imported and rejected rows", body = ImportReport),
+        (status = 400, description = "Malformed body", body = Problem, content_type = "application/problem+json"),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not an administrator", body = Problem, content_type = "application/problem+json"),
+        (status = 413, description = "Body too large", body = Problem, content_type = "application/problem+json"),
+        (status = 415, description = "Neither NDJSON nor JSON", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument(skip(body))]
+pub async fn import_questions(
+    session: Session,
+    store: Store,
+    content_type: Option<String>,
+    body: Bytes,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let account_id = session.account_id;
+    require_admin(&store, &account_id).await?;
+
+    let body = std::str::from_utf8(&body)
+        .map_err(|e| Error::InvalidBody(e.to_string()))?;
+    let essence = content_type
+        .as_deref()
+        .and_then(|ct| ct.split(';').next())
+        .map(|ct| ct.trim().to_ascii_lowercase());
+    let parsed = match essence.as_deref() {
+        Some(NDJSON_CONTENT_TYPE) => parse_ndjson(body),
+        Some("application/json") => parse_map(body)
+            .map_err(|e| Error::InvalidBody(e.to_string()))?,
+        _ => {
+            return Err(warp::reject::custom(Error::UnsupportedMediaType(
+                NDJSON_CONTENT_TYPE.to_string(),
+            )))
+        }
+    };
+
+    let account_ids: Vec<i32> = parsed
+        .iter()
+        .filter_map(|(_, question)| question.as_ref().ok())
+        .flat_map(|question| {
+            let answers = question.answers.iter().map(|a| &a.account_id);
+            std::iter::once(&question.account_id).chain(answers)
+        })
+        .filter_map(|account_id| account_id.as_ref().map(|id| id.0))
+        .collect();
+    let accounts: HashSet<i32> = store
+        .existing_accounts(&account_ids)
+        .await?
+        .into_iter()
+        .map(|account_id| account_id.0)
+        .collect();
+
+    let mut report = ImportReport::default();
+    let mut rows = Vec::new();
+    let mut questions = Vec::new();
+    for (row, question) in parsed {
+        let question = match question {
+            Ok(question) => question,
+            Err(error) => {
+                report.failed.push(FailedRow {
+                    row,
+                    errors: vec![error],
+                });
+                continue;
+            }
+        };
+        let mut errors = question.validate();
+        errors.extend(check_authors(&question, &accounts));
+        if errors.is_empty() {
+            rows.push(row);
+            questions.push(question);
+        } else {
+            report.failed.push(FailedRow { row, errors });
+        }
+    }
+
+    for question in questions.iter_mut() {
+        question.tags = store.normalize_tags(question.tags.take()).await?;
+    }
+    for (rows, questions) in rows
+        .chunks(IMPORT_BATCH_SIZE)
+        .zip(questions.chunks(IMPORT_BATCH_SIZE))
+    {
+        let results =
+            match store.import_questions(questions, &account_id).await {
+                Ok(results) => results,
+                // The rows of the committed batches are still reported
+                Err(e) => {
+                    for row in rows {
+                        report.failed.push(failed_row(row, &e));
+                    }
+                    continue;
+                }
+            };
+        for (row, result) in rows.iter().zip(results) {
+            match result {
+                Ok(question_id) => report.imported.push(ImportedRow {
+                    row: row.clone(),
+                    question_id,
+                }),
+                Err(e) => report.failed.push(failed_row(row, &e)),
+            }
+        }
+    }
+
+    Ok(warp::reply::json(&report))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/export",
+    tag = "admin",
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "One question with its answers per line, oldest first",
+            body = ExportedQuestion, content_type = "application/x-ndjson"),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not an administrator", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn export_questions(
+    session: Session,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    require_admin(&store, &session.account_id).await?;
+
+    // The questions are read in batches as the client consumes the
+    // response, a failing batch aborts it
+    let lines = stream::try_unfold(0, move |after| {
+        let store = store.clone();
+        async move {
+            let questions = store
+                .export_questions(after, EXPORT_BATCH_SIZE)
+                .await
+                .map_err(|e| io::Error::other(e.to_string()))?;
+            let last = match questions.last() {
+                Some(question) => question.id.0,
+                None => return Ok(None),
+            };
+            let mut lines = Vec::new();
+            for question in &questions {
+                serde_json::to_writer(&mut lines, question)?;
+                lines.push(b'\n');
+            }
+            Ok::<_, io::Error>(Some((Bytes::from(lines), last)))
+        }
+    });
+
+    Ok(warp::reply::with_header(
+        warp::reply::with_header(
+            warp::http::Response::new(Body::wrap_stream(lines)),
+            "content-type",
+            NDJSON_CONTENT_TYPE,
+        ),
+        "content-disposition",
+        "attachment; filename=\"export.ndjson\"",
+    ))
+}
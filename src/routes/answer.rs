@@ -1,36 +1,144 @@
+use handle_errors::{Error, Problem};
 use tracing::instrument;
 use warp::http::StatusCode;
 
+use crate::events::{Event, EventBus};
 use crate::profanity::check_profanity;
+use crate::routes::notification::{notify_accepted_answer, notify_answer};
 use crate::store::Store;
-use crate::types::account::Session;
-use crate::types::answer::NewAnswer;
+use crate::types::account::{AccountId, Session};
+use crate::types::answer::{Answer, NewAnswer};
+use crate::types::question::{QuestionId, QuestionStatus};
+use crate::types::reputation::Privilege;
+use crate::webhooks;
 
+/// Refuses answers on closed and locked questions. Protected questions
+/// can only be answered with enough reputation. Unknown questions are
+/// left to the foreign key of the insert.
+pub async fn check_can_answer(
+    store: &Store,
+    question_id: &QuestionId,
+    account_id: &AccountId,
+) -> Result<(), Error> {
+    match store.get_question_status(question_id.0).await {
+        Ok(QuestionStatus::Open) => Ok(()),
+        Ok(QuestionStatus::Closed) => Err(Error::QuestionClosed),
+        Ok(QuestionStatus::Locked) => Err(Error::QuestionLocked),
+        Ok(QuestionStatus::Protected) => Privilege::AnswerProtected
+            .check(store.get_reputation(account_id).await?),
+        Err(Error::DatabaseQueryError(sqlx::Error::RowNotFound)) => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Checks, censors, stores and announces a new answer, shared by the
+/// REST and GraphQL APIs which validate it beforehand
+pub async fn create_answer(
+    store: &Store,
+    events: &EventBus,
+    account_id: &AccountId,
+    new_answer: NewAnswer,
+) -> Result<Answer, Error> {
+    check_can_answer(store, &new_answer.question_id, account_id).await?;
+    let new_answer = NewAnswer {
+        content: check_profanity(new_answer.content).await?,
+        question_id: new_answer.question_id,
+    };
+
+    let answer = store.add_answer(new_answer, account_id.clone()).await?;
+    notify_answer(store, &answer, account_id).await;
+    let event = Event::AnswerAdded {
+        answer: answer.clone(),
+    };
+    webhooks::enqueue(store, &event).await;
+    events.publish(event).await;
+    Ok(answer)
+}
+
+#[utoipa::path(
+    post,
+    path = "/questions/answers",
+    tag = "answers",
+    request_body(
+        content = NewAnswer,
+        content_type = "application/x-www-form-urlencoded",
+        description = "Form encoded, unlike the other routes, `/api/v2` takes JSON"
+    ),
+    security(("token" = [])),
+    responses(
+        (status = 201, description = "Answer created", body = Answer,
+            headers(("location" = String, description = "URL of the answer"))),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not enough reputation to answer a protected question", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "The question is closed or locked", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid answer", body = Problem, content_type = "application/problem+json"),
+        (status = 502, description = "Profanity check failed", body = Problem, content_type = "application/problem+json"),
+    )
+)]
 #[instrument]
 pub async fn add_answer(
     session: Session,
+    base_path: &'static str,
     store: Store,
+    events: EventBus,
     new_answer: NewAnswer,
 ) -> Result<impl warp::Reply, warp::Rejection> {
-    let account_id = session.account_id;
-    let content = match check_profanity(new_answer.content).await {
-        Ok(res) => res,
-        Err(e) => return Err(warp::reject::custom(e)),
-    };
-
-    let new_answer = NewAnswer {
-        content,
-        question_id: new_answer.question_id,
-    };
-
-    match store.add_answer(new_answer, account_id).await {
-        Ok(_) => {
-            Ok(warp::reply::with_status("Answer added!", StatusCode::OK))
+    match create_answer(&store, &events, &session.account_id, new_answer)
+        .await
+    {
+        Ok(answer) => {
+            let location =
+                format!("{}/answers/{}", base_path, answer.id.0);
+            Ok(warp::reply::with_status(
+                warp::reply::with_header(
+                    warp::reply::json(&answer),
+                    "location",
+                    location,
+                ),
+                StatusCode::CREATED,
+            ))
         }
         Err(e) => Err(warp::reject::custom(e)),
     }
 }
 
+/// Same as `add_answer`, but with a JSON body like every other route.
+/// Registered in front of the v1 routes of `/api/v2`.
+#[utoipa::path(
+    post,
+    path = "/questions/answers",
+    tag = "answers",
+    request_body = NewAnswer,
+    security(("token" = [])),
+    responses(
+        (status = 201, description = "Answer created", body = Answer,
+            headers(("location" = String, description = "URL of the answer"))),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not enough reputation to answer a protected question", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "The question is closed or locked", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid answer", body = Problem, content_type = "application/problem+json"),
+        (status = 502, description = "Profanity check failed", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn add_answer_v2(
+    session: Session,
+    base_path: &'static str,
+    store: Store,
+    events: EventBus,
+    new_answer: NewAnswer,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    add_answer(session, base_path, store, events, new_answer).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/questions/{id}/answers",
+    tag = "answers",
+    params(("id" = i32, Path, description = "Question id")),
+    responses(
+        (status = 200, description = "Answers of the question", body = Vec<Answer>),
+    )
+)]
 #[instrument]
 pub async fn get_answers_by_question_id(
     question_id: i32,
@@ -43,3 +151,112 @@ pub async fn get_answers_by_question_id(
 
     Ok(warp::reply::json(&res))
 }
+
+#[utoipa::path(
+    get,
+    path = "/answers/{id}",
+    tag = "answers",
+    params(("id" = i32, Path, description = "Answer id")),
+    responses(
+        (status = 200, description = "The answer", body = Answer),
+        (status = 404, description = "Answer not found", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn get_answer_by_id(
+    answer_id: i32,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let answer = store.get_answer_by_id(answer_id).await?;
+    Ok(warp::reply::json(&answer))
+}
+
+#[utoipa::path(
+    post,
+    path = "/answers/{id}/accept",
+    tag = "answers",
+    params(("id" = i32, Path, description = "Answer id")),
+    security(("token" = [])),
+    responses(
+        (status = 204, description = "Answer accepted, replacing the previously accepted one"),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not the owner of the question", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Answer not found", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn accept_answer(
+    answer_id: i32,
+    session: Session,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let (author, question_id) = store.get_answer_owner(answer_id).await?;
+    if !store
+        .is_question_owner(question_id.0, &session.account_id)
+        .await?
+    {
+        return Err(warp::reject::custom(handle_errors::Error::Forbidden));
+    }
+
+    store
+        .accept_answer(
+            answer_id,
+            question_id.0,
+            &author,
+            &session.account_id,
+        )
+        .await?;
+    notify_accepted_answer(
+        &store,
+        answer_id,
+        question_id.0,
+        &author,
+        &session.account_id,
+    )
+    .await;
+    Ok(warp::reply::with_status(
+        warp::reply(),
+        StatusCode::NO_CONTENT,
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/answers/{id}/accept",
+    tag = "answers",
+    params(("id" = i32, Path, description = "Answer id")),
+    security(("token" = [])),
+    responses(
+        (status = 204, description = "Answer no longer accepted"),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not the owner of the question", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "The answer isn't accepted", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn unaccept_answer(
+    answer_id: i32,
+    session: Session,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let (_, question_id) = store.get_answer_owner(answer_id).await?;
+    if !store
+        .is_question_owner(question_id.0, &session.account_id)
+        .await?
+    {
+        return Err(warp::reject::custom(handle_errors::Error::Forbidden));
+    }
+
+    if store.unaccept_answer(answer_id).await? {
+        Ok(warp::reply::with_status(
+            warp::reply(),
+            StatusCode::NO_CONTENT,
+        ))
+    } else {
+        Err(warp::reject::custom(
+            handle_errors::Error::DatabaseQueryError(
+                sqlx::Error::RowNotFound,
+            ),
+        ))
+    }
+}
@@ -0,0 +1,345 @@
+use futures_util::TryStreamExt;
+use handle_errors::{Error, Problem};
+use std::sync::Arc;
+use tracing::{event, instrument, Level};
+use warp::http::StatusCode;
+use warp::hyper::body::{Buf, Bytes};
+use warp::multipart::FormData;
+
+use crate::blobs::BlobStore;
+use crate::routes::question::unwritten_error;
+use crate::store::Store;
+use crate::types::account::{AccountId, Session};
+use crate::types::answer::AnswerId;
+use crate::types::attachment::{
+    check_file, sanitize_file_name, Attachment, NewAttachment,
+    MAX_ATTACHMENT_SIZE,
+};
+use crate::types::question::QuestionId;
+use crate::validation::field_error;
+
+/// File of the `file` part of a multipart form, with its name and
+/// declared content type
+struct Upload {
+    file_name: Option<String>,
+    content_type: Option<String>,
+    data: Vec<u8>,
+}
+
+async fn read_upload(mut form: FormData) -> Result<Upload, Error> {
+    while let Some(part) = form
+        .try_next()
+        .await
+        .map_err(|e| Error::InvalidBody(e.to_string()))?
+    {
+        if part.name() != "file" {
+            continue;
+        }
+        let file_name = part.filename().map(str::to_string);
+        let content_type = part.content_type().map(str::to_string);
+        let mut data = Vec::new();
+        let mut chunks = part.stream();
+        while let Some(chunk) = chunks
+            .try_next()
+            .await
+            .map_err(|e| Error::InvalidBody(e.to_string()))?
+        {
+            data.extend_from_slice(chunk.chunk());
+            if data.len() as u64 > MAX_ATTACHMENT_SIZE {
+                return Err(Error::ValidationError(vec![field_error(
+                    "file",
+                    "must not exceed 5 MiB",
+                )]));
+            }
+        }
+        return Ok(Upload {
+            file_name,
+            content_type,
+            data,
+        });
+    }
+    Err(Error::ValidationError(vec![field_error(
+        "file",
+        "is required",
+    )]))
+}
+
+/// Saves the content before the metadata, the content is deleted again
+/// if the metadata can't be saved
+async fn save_upload(
+    base_path: &str,
+    store: &Store,
+    blobs: &Arc<dyn BlobStore>,
+    account_id: &AccountId,
+    question_id: QuestionId,
+    answer_id: Option<AnswerId>,
+    form: FormData,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let upload = read_upload(form).await?;
+    let content_type =
+        check_file(upload.content_type.as_deref(), &upload.data)
+            .map_err(|e| Error::ValidationError(vec![e]))?;
+    let attachment = NewAttachment {
+        question_id,
+        answer_id,
+        key: uuid::Uuid::new_v4().to_string(),
+        file_name: sanitize_file_name(upload.file_name.as_deref()),
+        content_type: content_type.to_string(),
+        size: upload.data.len() as i32,
+    };
+
+    blobs
+        .put(&attachment.key, content_type, Bytes::from(upload.data))
+        .await?;
+    let saved = match store.add_attachment(&attachment, account_id).await {
+        Ok(Some(saved)) => Ok(saved),
+        Ok(None) => Err(unwritten_error(
+            store,
+            attachment.question_id.0,
+            Error::DatabaseQueryError(sqlx::Error::RowNotFound),
+        )
+        .await),
+        Err(e) => Err(e),
+    };
+    let attachment = match saved {
+        Ok(attachment) => attachment,
+        Err(e) => {
+            let _ = blobs.delete(&attachment.key).await;
+            return Err(warp::reject::custom(e));
+        }
+    };
+
+    let location = format!("{}/attachments/{}", base_path, attachment.id);
+    Ok(warp::reply::with_status(
+        warp::reply::with_header(
+            warp::reply::json(&attachment),
+            "location",
+            location,
+        ),
+        StatusCode::CREATED,
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/questions/{id}/attachments",
+    tag = "attachments",
+    params(("id" = i32, Path, description = "Question id")),
+    request_body(content = String, content_type = "multipart/form-data",
+        description = "PNG, JPEG, GIF, WebP, PDF or text file of at most 5 MiB in the `file` part"),
+    security(("token" = [])),
+    responses(
+        (status = 201, description = "File attached to the question", body = Attachment,
+            headers(("location" = String, description = "URL of the content"))),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not the owner of the question", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "The question is locked", body = Problem, content_type = "application/problem+json"),
+        (status = 413, description = "Body too large", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Missing, too large or unsupported file", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument(skip(form))]
+pub async fn add_question_attachment(
+    question_id: i32,
+    session: Session,
+    base_path: &'static str,
+    store: Store,
+    blobs: Arc<dyn BlobStore>,
+    form: FormData,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let account_id = session.account_id;
+    if !store.is_question_owner(question_id, &account_id).await? {
+        return Err(warp::reject::custom(Error::Forbidden));
+    }
+    save_upload(
+        base_path,
+        &store,
+        &blobs,
+        &account_id,
+        QuestionId(question_id),
+        None,
+        form,
+    )
+    .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/answers/{id}/attachments",
+    tag = "attachments",
+    params(("id" = i32, Path, description = "Answer id")),
+    request_body(content = String, content_type = "multipart/form-data",
+        description = "PNG, JPEG, GIF, WebP, PDF or text file of at most 5 MiB in the `file` part"),
+    security(("token" = [])),
+    responses(
+        (status = 201, description = "File attached to the answer", body = Attachment,
+            headers(("location" = String, description = "URL of the content"))),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not the owner of the answer", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Answer not found", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "The question is locked", body = Problem, content_type = "application/problem+json"),
+        (status = 413, description = "Body too large", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Missing, too large or unsupported file", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument(skip(form))]
+pub async fn add_answer_attachment(
+    answer_id: i32,
+    session: Session,
+    base_path: &'static str,
+    store: Store,
+    blobs: Arc<dyn BlobStore>,
+    form: FormData,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let account_id = session.account_id;
+    let (author, question_id) = store.get_answer_owner(answer_id).await?;
+    if author != account_id {
+        return Err(warp::reject::custom(Error::Forbidden));
+    }
+    save_upload(
+        base_path,
+        &store,
+        &blobs,
+        &account_id,
+        question_id,
+        Some(AnswerId(answer_id)),
+        form,
+    )
+    .await
+}
+
+#[utoipa::path(
+    get,
+    path = "/questions/{id}/attachments",
+    tag = "attachments",
+    params(("id" = i32, Path, description = "Question id")),
+    responses(
+        (status = 200, description = "Files attached to the question, oldest first", body = Vec<Attachment>),
+        (status = 404, description = "Question not found", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn get_question_attachments(
+    question_id: i32,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if !store.question_exists(question_id).await? {
+        return Err(warp::reject::custom(Error::DatabaseQueryError(
+            sqlx::Error::RowNotFound,
+        )));
+    }
+    let attachments = store.get_attachments(question_id, None).await?;
+    Ok(warp::reply::json(&attachments))
+}
+
+#[utoipa::path(
+    get,
+    path = "/answers/{id}/attachments",
+    tag = "attachments",
+    params(("id" = i32, Path, description = "Answer id")),
+    responses(
+        (status = 200, description = "Files attached to the answer, oldest first", body = Vec<Attachment>),
+        (status = 404, description = "Answer not found", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn get_answer_attachments(
+    answer_id: i32,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let (_, question_id) = store.get_answer_owner(answer_id).await?;
+    let attachments = store
+        .get_attachments(question_id.0, Some(answer_id))
+        .await?;
+    Ok(warp::reply::json(&attachments))
+}
+
+#[utoipa::path(
+    get,
+    path = "/attachments/{id}",
+    tag = "attachments",
+    params(("id" = i32, Path, description = "Attachment id")),
+    responses(
+        (status = 200, description = "Content of the file, shown inline for images and downloaded otherwise"),
+        (status = 404, description = "Attachment not found", body = Problem, content_type = "application/problem+json"),
+        (status = 500, description = "The file storage is unavailable", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn get_attachment(
+    attachment_id: i32,
+    store: Store,
+    blobs: Arc<dyn BlobStore>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let attachment = store.get_attachment(attachment_id).await?;
+    let data = blobs.get(&attachment.key).await?;
+    let disposition = if attachment.is_image() {
+        "inline".to_string()
+    } else {
+        format!("attachment; filename=\"{}\"", attachment.file_name)
+    };
+
+    // The content type was checked on upload, browsers mustn't guess
+    // another one
+    Ok(warp::reply::with_header(
+        warp::reply::with_header(
+            warp::reply::with_header(
+                warp::http::Response::new(data),
+                "content-type",
+                attachment.content_type,
+            ),
+            "content-disposition",
+            disposition,
+        ),
+        "x-content-type-options",
+        "nosniff",
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/attachments/{id}",
+    tag = "attachments",
+    params(("id" = i32, Path, description = "Attachment id")),
+    security(("token" = [])),
+    responses(
+        (status = 204, description = "Attachment deleted"),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not the owner of the attachment", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Attachment not found", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn delete_attachment(
+    attachment_id: i32,
+    session: Session,
+    store: Store,
+    blobs: Arc<dyn BlobStore>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let attachment = store.get_attachment(attachment_id).await?;
+    if !store
+        .delete_attachment(attachment_id, &session.account_id)
+        .await?
+    {
+        return Err(warp::reject::custom(Error::Forbidden));
+    }
+    // The attachment is gone either way, a leftover blob is only logged
+    if let Err(e) = blobs.delete(&attachment.key).await {
+        event!(Level::WARN, "blob {} not deleted: {}", attachment.key, e);
+    }
+
+    Ok(warp::reply::with_status(
+        warp::reply(),
+        StatusCode::NO_CONTENT,
+    ))
+}
+
+/// Deletes the blobs of the attachments of a deleted question, their
+/// metadata went with the question
+pub async fn delete_blobs(blobs: &Arc<dyn BlobStore>, keys: Vec<String>) {
+    for key in keys {
+        if let Err(e) = blobs.delete(&key).await {
+            event!(Level::WARN, "blob {} not deleted: {}", key, e);
+        }
+    }
+}
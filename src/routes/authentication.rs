@@ -1,14 +1,25 @@
 use argon2::{self, Config};
 use chrono::prelude::*;
-use handle_errors::Error;
+use handle_errors::{Error, Problem};
 use rand::Rng;
 use std::{env, future};
 use warp::http::StatusCode;
 use warp::Filter;
 
 use crate::store::Store;
-use crate::types::account::{Account, AccountId, Session};
+use crate::types::account::{Account, AccountId, AccountInfo, Session};
 
+#[utoipa::path(
+    post,
+    path = "/login",
+    tag = "accounts",
+    request_body = Account,
+    responses(
+        (status = 200, description = "PASETO token to send in the `Authorization` header", body = String),
+        (status = 401, description = "Wrong e-mail/password combination", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Account not found", body = Problem, content_type = "application/problem+json"),
+    )
+)]
 pub async fn login(
     store: Store,
     login: Account,
@@ -44,7 +55,7 @@ fn verify_password(
     argon2::verify_encoded(hash, password)
 }
 
-fn issue_token(account_id: AccountId) -> String {
+pub fn issue_token(account_id: AccountId) -> String {
     let current_date_time = Utc::now();
     let dt = current_date_time + chrono::Duration::days(1);
     let key = env::var("PASETO_KEY").unwrap();
@@ -59,7 +70,20 @@ fn issue_token(account_id: AccountId) -> String {
         .expect("Failed to construct paseto token with builder!")
 }
 
+#[utoipa::path(
+    post,
+    path = "/registration",
+    tag = "accounts",
+    request_body = Account,
+    responses(
+        (status = 201, description = "Account created", body = AccountInfo,
+            headers(("location" = String, description = "URL of the profile of the account"))),
+        (status = 409, description = "Account already exists", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid account", body = Problem, content_type = "application/problem+json"),
+    )
+)]
 pub async fn register(
+    base_path: &'static str,
     store: Store,
     account: Account,
 ) -> Result<impl warp::Reply, warp::Rejection> {
@@ -69,11 +93,21 @@ pub async fn register(
         id: account.id,
         email: account.email,
         password: hashed_password,
+        username: account.username,
     };
 
     match store.add_account(account).await {
-        Ok(_) => {
-            Ok(warp::reply::with_status("Account added!", StatusCode::OK))
+        Ok(account) => {
+            let account = AccountInfo::from(account);
+            let location = format!("{}/users/{}", base_path, account.id.0);
+            Ok(warp::reply::with_status(
+                warp::reply::with_header(
+                    warp::reply::json(&account),
+                    "location",
+                    location,
+                ),
+                StatusCode::CREATED,
+            ))
         }
         Err(e) => Err(warp::reject::custom(e)),
     }
@@ -117,6 +151,26 @@ pub fn auth(
     })
 }
 
+/// Like `auth`, but lets anonymous requests through
+pub fn optional_auth(
+) -> impl Filter<Extract = (Option<Session>,), Error = warp::Rejection> + Clone
+{
+    warp::header::optional::<String>("Authorization").and_then(
+        |token: Option<String>| {
+            let session = match token.map(verify_token) {
+                Some(Ok(session)) => Some(session),
+                Some(Err(_)) => {
+                    return future::ready(Err(warp::reject::custom(
+                        handle_errors::Error::Unauthorized,
+                    )))
+                }
+                None => None,
+            };
+            future::ready(Ok(session))
+        },
+    )
+}
+
 #[cfg(test)]
 mod authentication_tests {
     use super::*;
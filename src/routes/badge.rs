@@ -0,0 +1,46 @@
+use handle_errors::Problem;
+use tracing::instrument;
+
+use crate::store::Store;
+use crate::types::account::AccountId;
+use crate::types::badge::{AccountBadge, Badge};
+
+#[utoipa::path(
+    get,
+    path = "/badges",
+    tag = "badges",
+    responses(
+        (status = 200, description = "Every badge and how often it was awarded", body = Vec<Badge>),
+    )
+)]
+#[instrument]
+pub async fn get_badges(
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match store.get_badges().await {
+        Ok(badges) => Ok(warp::reply::json(&badges)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/accounts/{id}/badges",
+    tag = "badges",
+    params(("id" = i32, Path, description = "Account id")),
+    responses(
+        (status = 200, description = "Badges the account earned, latest first", body = Vec<AccountBadge>),
+        (status = 404, description = "Account not found", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn get_account_badges(
+    account_id: i32,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let account = store.get_account_by_id(&AccountId(account_id)).await?;
+    match store.get_account_badges(&account.id).await {
+        Ok(badges) => Ok(warp::reply::json(&badges)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
@@ -0,0 +1,133 @@
+use handle_errors::{Error, Problem};
+use tracing::instrument;
+use warp::http::StatusCode;
+
+use crate::store::Store;
+use crate::types::account::Session;
+use crate::types::bounty::{Bounty, BountyAward, NewBounty};
+use crate::types::question::QuestionStatus;
+use crate::validation::field_error;
+
+#[utoipa::path(
+    get,
+    path = "/questions/{id}/bounty",
+    tag = "bounties",
+    params(("id" = i32, Path, description = "Question id")),
+    responses(
+        (status = 200, description = "Open bounty of the question", body = Bounty),
+        (status = 404, description = "The question has no open bounty", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn get_bounty(
+    question_id: i32,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bounty = store.get_open_bounty(question_id).await?;
+    Ok(warp::reply::json(&bounty))
+}
+
+#[utoipa::path(
+    post,
+    path = "/questions/{id}/bounty",
+    tag = "bounties",
+    params(("id" = i32, Path, description = "Question id")),
+    request_body = NewBounty,
+    security(("token" = [])),
+    responses(
+        (status = 201, description = "Bounty opened, its amount is taken from the reputation of the owner", body = Bounty),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not the owner of the question, or not enough reputation", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "The question is closed, locked or already has an open bounty", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid bounty", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn offer_bounty(
+    question_id: i32,
+    session: Session,
+    store: Store,
+    new_bounty: NewBounty,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let account_id = session.account_id;
+    if !store.is_question_owner(question_id, &account_id).await? {
+        return Err(warp::reject::custom(Error::Forbidden));
+    }
+    match store.get_question_status(question_id).await? {
+        QuestionStatus::Closed => {
+            return Err(warp::reject::custom(Error::QuestionClosed))
+        }
+        QuestionStatus::Locked => {
+            return Err(warp::reject::custom(Error::QuestionLocked))
+        }
+        QuestionStatus::Open | QuestionStatus::Protected => (),
+    }
+
+    // The store checks the reputation in the transaction of the debit
+    let bounty = store
+        .offer_bounty(question_id, &account_id, &new_bounty)
+        .await?;
+    Ok(warp::reply::with_status(
+        warp::reply::json(&bounty),
+        StatusCode::CREATED,
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/questions/{id}/bounty/award",
+    tag = "bounties",
+    params(("id" = i32, Path, description = "Question id")),
+    request_body = BountyAward,
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Bounty awarded to the answer", body = Bounty),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not the owner of the bounty", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "The question has no open bounty", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Not an answer of another account to the question", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn award_bounty(
+    question_id: i32,
+    session: Session,
+    store: Store,
+    award: BountyAward,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bounty = store.get_open_bounty(question_id).await?;
+    if bounty.account_id != session.account_id {
+        return Err(warp::reject::custom(Error::Forbidden));
+    }
+
+    let answer_id = award.answer_id.0;
+    let author = match store.get_answer_owner(answer_id).await {
+        Ok((author, answered)) if answered.0 == question_id => author,
+        Ok(_)
+        | Err(Error::DatabaseQueryError(sqlx::Error::RowNotFound)) => {
+            return Err(warp::reject::custom(Error::ValidationError(
+                vec![field_error(
+                    "answer_id",
+                    "must be an answer to the question",
+                )],
+            )));
+        }
+        Err(e) => return Err(warp::reject::custom(e)),
+    };
+    if author == bounty.account_id {
+        return Err(warp::reject::custom(Error::ValidationError(vec![
+            field_error(
+                "answer_id",
+                "must be an answer of another account",
+            ),
+        ])));
+    }
+
+    match store.award_bounty(&bounty, answer_id, &author).await? {
+        Some(bounty) => Ok(warp::reply::json(&bounty)),
+        // The bounty expired in the meantime
+        None => Err(warp::reject::custom(Error::DatabaseQueryError(
+            sqlx::Error::RowNotFound,
+        ))),
+    }
+}
@@ -0,0 +1,150 @@
+use futures_util::{SinkExt, StreamExt};
+use handle_errors::Problem;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::convert::Infallible;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_stream::wrappers::BroadcastStream;
+use tracing::{event, instrument, Level};
+use warp::filters::ws::{Message, WebSocket, Ws};
+
+use crate::events::{Event, EventBus};
+use crate::store::Store;
+
+#[utoipa::path(
+    get,
+    path = "/questions/{id}/events",
+    tag = "questions",
+    params(("id" = i32, Path, description = "Question id")),
+    responses(
+        (status = 200, description = "Server-Sent Events stream of the changes to the question, \
+            the `event` field is the `type` of the event", body = Event, content_type = "text/event-stream"),
+        (status = 404, description = "Question not found", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn question_events(
+    question_id: i32,
+    store: Store,
+    events: EventBus,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    // Subscribe first, so nothing published during the check is missed
+    let receiver = events.subscribe();
+    if !store.question_exists(question_id).await? {
+        return Err(warp::reject::custom(
+            handle_errors::Error::DatabaseQueryError(
+                sqlx::Error::RowNotFound,
+            ),
+        ));
+    }
+
+    // Events missed by a lagging client are skipped
+    let stream = tokio_stream::StreamExt::filter_map(
+        BroadcastStream::new(receiver),
+        move |event| match event {
+            Ok(event) if event.question_id() == question_id => {
+                warp::sse::Event::default()
+                    .event(event.name())
+                    .json_data(&event)
+                    .ok()
+                    .map(Ok::<_, Infallible>)
+            }
+            _ => None,
+        },
+    );
+
+    Ok(warp::sse::reply(warp::sse::keep_alive().stream(stream)))
+}
+
+/// Message a WebSocket client sends to follow only some questions,
+/// like `{"subscribe": 1}`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Subscription {
+    Subscribe(i32),
+    Unsubscribe(i32),
+}
+
+/// Pushes every event as a JSON text message, or only the events of
+/// the questions the client subscribed to
+pub async fn websocket(
+    ws: Ws,
+    events: EventBus,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(ws.on_upgrade(move |socket| forward_events(socket, events)))
+}
+
+async fn forward_events(socket: WebSocket, events: EventBus) {
+    let (mut sender, mut messages) = socket.split();
+    let mut receiver = events.subscribe();
+    let mut questions = HashSet::new();
+
+    loop {
+        tokio::select! {
+            message = messages.next() => match message {
+                Some(Ok(message)) if message.is_text() => {
+                    let text = message.to_str().unwrap_or_default();
+                    match serde_json::from_str(text) {
+                        Ok(Subscription::Subscribe(id)) => {
+                            questions.insert(id);
+                        }
+                        Ok(Subscription::Unsubscribe(id)) => {
+                            questions.remove(&id);
+                        }
+                        Err(e) => {
+                            event!(Level::WARN, "invalid message: {:?}", e)
+                        }
+                    }
+                }
+                Some(Ok(message)) if message.is_close() => break,
+                Some(Ok(_)) => {}
+                Some(Err(e)) => {
+                    event!(Level::WARN, "{:?}", e);
+                    break;
+                }
+                None => break,
+            },
+            event = receiver.recv() => match event {
+                Ok(event) => {
+                    if !questions.is_empty()
+                        && !questions.contains(&event.question_id())
+                    {
+                        continue;
+                    }
+                    let text = match serde_json::to_string(&event) {
+                        Ok(text) => text,
+                        Err(e) => {
+                            event!(Level::ERROR, "{:?}", e);
+                            continue;
+                        }
+                    };
+                    if sender.send(Message::text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(missed)) => {
+                    event!(Level::WARN, missed, "websocket client lagging");
+                }
+                Err(RecvError::Closed) => break,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod events_routes_tests {
+    use super::*;
+
+    #[test]
+    fn subscription_messages() {
+        // Arrange
+        let subscribe = r#"{"subscribe": 1}"#;
+        let unsubscribe = r#"{"unsubscribe": 2}"#;
+        // Act
+        let subscribe = serde_json::from_str(subscribe).unwrap();
+        let unsubscribe = serde_json::from_str(unsubscribe).unwrap();
+        // Assert
+        assert!(matches!(subscribe, Subscription::Subscribe(1)));
+        assert!(matches!(unsubscribe, Subscription::Unsubscribe(2)));
+    }
+}
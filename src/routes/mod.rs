@@ -1,3 +1,35 @@
+use std::convert::Infallible;
+use warp::{path::FullPath, Filter};
+
+/// Base path of the current API version, also served without prefix
+pub const BASE_PATH: &str = "/api/v1";
+
+/// Base path of the API version a request was sent to, used to build
+/// the URLs sent back to clients
+pub fn base_path(
+) -> impl Filter<Extract = (&'static str,), Error = Infallible> + Clone {
+    warp::path::full().map(|path: FullPath| {
+        if path.as_str().starts_with("/api/v2/") {
+            "/api/v2"
+        } else {
+            BASE_PATH
+        }
+    })
+}
+
+pub mod admin;
 pub mod answer;
+pub mod attachment;
 pub mod authentication;
+pub mod badge;
+pub mod bounty;
+pub mod events;
+pub mod notification;
 pub mod question;
+pub mod reputation;
+pub mod snippet;
+pub mod tag;
+pub mod user;
+pub mod vote;
+pub mod watch;
+pub mod webhook;
@@ -0,0 +1,361 @@
+use handle_errors::Problem;
+use std::collections::HashMap;
+use tracing::instrument;
+use warp::http::StatusCode;
+
+use crate::mentions;
+use crate::store::Store;
+use crate::types::account::{AccountId, Session};
+use crate::types::answer::Answer;
+use crate::types::mention::Mention;
+use crate::types::notification::{
+    NotificationKind, NotificationList, NotificationPreferences,
+};
+use crate::types::pagination::{extract_pagination, Pagination};
+use crate::types::question::Question;
+
+// The `notify_*` functions never fail, a missed notification must not
+// fail the request which caused it. The store already logs the errors.
+
+/// Tells the owner and the watchers of the question about a new answer
+pub async fn notify_answer(
+    store: &Store,
+    answer: &Answer,
+    author: &AccountId,
+) {
+    let question_id = answer.question_id.0;
+    notify_mentions(
+        store,
+        question_id,
+        Some(answer.id.0),
+        &answer.content,
+        author,
+    )
+    .await;
+    let owner = store.get_question_owner(question_id).await.ok();
+    if let Some(owner) = &owner {
+        let _ = store
+            .add_notifications(
+                NotificationKind::Answer,
+                &[owner.0],
+                Some(question_id),
+                Some(answer.id.0),
+                author,
+            )
+            .await;
+    }
+
+    // The owner already got the notification above
+    let watchers: Vec<i32> = store
+        .get_question_watchers(question_id)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|watcher| owner.as_ref().map(|o| o.0) != Some(*watcher))
+        .collect();
+    if !watchers.is_empty() {
+        let _ = store
+            .add_notifications(
+                NotificationKind::WatchedQuestion,
+                &watchers,
+                Some(question_id),
+                Some(answer.id.0),
+                author,
+            )
+            .await;
+    }
+}
+
+/// Tells the accounts mentioned in a question or an answer, only once
+/// per post as edits go through here again
+pub async fn notify_mentions(
+    store: &Store,
+    question_id: i32,
+    answer_id: Option<i32>,
+    content: &str,
+    author: &AccountId,
+) {
+    let usernames = mentions::extract(content);
+    if usernames.is_empty() {
+        return;
+    }
+    let mentioned = store
+        .add_mentions(&usernames, question_id, answer_id, author)
+        .await
+        .unwrap_or_default();
+    if !mentioned.is_empty() {
+        let _ = store
+            .add_notifications(
+                NotificationKind::Mention,
+                &mentioned,
+                Some(question_id),
+                answer_id,
+                author,
+            )
+            .await;
+    }
+}
+
+/// Tells the author of an answer it was accepted
+pub async fn notify_accepted_answer(
+    store: &Store,
+    answer_id: i32,
+    question_id: i32,
+    author: &AccountId,
+    accepter: &AccountId,
+) {
+    let _ = store
+        .add_notifications(
+            NotificationKind::AcceptedAnswer,
+            &[author.0],
+            Some(question_id),
+            Some(answer_id),
+            accepter,
+        )
+        .await;
+}
+
+/// Tells the watchers of the tags of a new question
+pub async fn notify_question_added(
+    store: &Store,
+    question: &Question,
+    author: &AccountId,
+) {
+    notify_mentions(store, question.id.0, None, &question.content, author)
+        .await;
+    // The tags of a stored question are already canonical slugs
+    let tags = match &question.tags {
+        Some(tags) => tags,
+        None => return,
+    };
+    let watchers = store.get_tag_watchers(tags).await.unwrap_or_default();
+    if !watchers.is_empty() {
+        let _ = store
+            .add_notifications(
+                NotificationKind::WatchedTag,
+                &watchers,
+                Some(question.id.0),
+                None,
+                author,
+            )
+            .await;
+    }
+}
+
+/// Tells the watchers of a question it changed
+pub async fn notify_question_updated(
+    store: &Store,
+    question: &Question,
+    author: &AccountId,
+) {
+    notify_mentions(store, question.id.0, None, &question.content, author)
+        .await;
+    let watchers = store
+        .get_question_watchers(question.id.0)
+        .await
+        .unwrap_or_default();
+    if !watchers.is_empty() {
+        let _ = store
+            .add_notifications(
+                NotificationKind::WatchedQuestion,
+                &watchers,
+                Some(question.id.0),
+                None,
+                author,
+            )
+            .await;
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/notifications",
+    tag = "notifications",
+    params(
+        ("unread" = Option<bool>, Query, description = "Only the unread notifications"),
+        ("limit" = Option<i32>, Query, description = "Maximum number of notifications, requires `offset`"),
+        ("offset" = Option<i32>, Query, description = "Number of notifications to skip, requires `limit`"),
+    ),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Notifications of the account, latest first", body = NotificationList),
+        (status = 400, description = "Invalid pagination", body = Problem, content_type = "application/problem+json"),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn get_notifications(
+    mut params: HashMap<String, String>,
+    session: Session,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let unread_only = params
+        .remove("unread")
+        .map(|unread| unread == "true")
+        .unwrap_or(false);
+    let mut pagination = Pagination::default();
+    if !params.is_empty() {
+        pagination = extract_pagination(params)?;
+    }
+
+    let notifications = store.get_notifications(
+        &session.account_id,
+        unread_only,
+        pagination.limit,
+        pagination.offset,
+    );
+    let unread_count =
+        store.count_unread_notifications(&session.account_id);
+    let (notifications, unread_count) =
+        tokio::join!(notifications, unread_count);
+
+    Ok(warp::reply::json(&NotificationList {
+        unread_count: unread_count?,
+        notifications: notifications?,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/accounts/me/mentions",
+    tag = "notifications",
+    params(
+        ("limit" = Option<i32>, Query, description = "Maximum number of mentions, requires `offset`"),
+        ("offset" = Option<i32>, Query, description = "Number of mentions to skip, requires `limit`"),
+    ),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Questions and answers mentioning the account, latest first", body = Vec<Mention>),
+        (status = 400, description = "Invalid pagination", body = Problem, content_type = "application/problem+json"),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn get_mentions(
+    params: HashMap<String, String>,
+    session: Session,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut pagination = Pagination::default();
+    if !params.is_empty() {
+        pagination = extract_pagination(params)?;
+    }
+
+    let mentions = store
+        .get_mentions(
+            &session.account_id,
+            pagination.limit,
+            pagination.offset,
+        )
+        .await?;
+    Ok(warp::reply::json(&mentions))
+}
+
+#[utoipa::path(
+    post,
+    path = "/notifications/{id}/read",
+    tag = "notifications",
+    params(("id" = i32, Path, description = "Notification id")),
+    security(("token" = [])),
+    responses(
+        (status = 204, description = "Notification marked as read"),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Notification not found", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn read_notification(
+    notification_id: i32,
+    session: Session,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match store
+        .mark_notification_read(notification_id, &session.account_id)
+        .await
+    {
+        Ok(true) => Ok(warp::reply::with_status(
+            warp::reply(),
+            StatusCode::NO_CONTENT,
+        )),
+        Ok(false) => Err(warp::reject::custom(
+            handle_errors::Error::DatabaseQueryError(
+                sqlx::Error::RowNotFound,
+            ),
+        )),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/notifications/read-all",
+    tag = "notifications",
+    security(("token" = [])),
+    responses(
+        (status = 204, description = "Every notification marked as read"),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn read_all_notifications(
+    session: Session,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match store.mark_all_notifications_read(&session.account_id).await {
+        Ok(_) => Ok(warp::reply::with_status(
+            warp::reply(),
+            StatusCode::NO_CONTENT,
+        )),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/notifications/preferences",
+    tag = "notifications",
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Kinds of notifications the account receives", body = NotificationPreferences),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn get_notification_preferences(
+    session: Session,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match store
+        .get_notification_preferences(&session.account_id)
+        .await
+    {
+        Ok(preferences) => Ok(warp::reply::json(&preferences)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/notifications/preferences",
+    tag = "notifications",
+    request_body = NotificationPreferences,
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Preferences saved", body = NotificationPreferences),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn update_notification_preferences(
+    session: Session,
+    store: Store,
+    preferences: NotificationPreferences,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match store
+        .set_notification_preferences(&session.account_id, preferences)
+        .await
+    {
+        Ok(preferences) => Ok(warp::reply::json(&preferences)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
@@ -1,98 +1,385 @@
+use handle_errors::{Error, Problem};
 use std::collections::HashMap;
+use std::sync::Arc;
 use tracing::{event, instrument, Level};
 use warp::http::StatusCode;
+use warp::Reply;
 
+use crate::blobs::BlobStore;
+use crate::events::{Event, EventBus};
 use crate::profanity::check_profanity;
+use crate::routes::attachment::delete_blobs;
+use crate::routes::notification::{
+    notify_question_added, notify_question_updated,
+};
 use crate::store::Store;
-use crate::types::account::Session;
+use crate::types::account::{AccountId, Session};
+use crate::types::etag::{etag, extract_if_match, matches_if_none_match};
 use crate::types::pagination::{extract_pagination, Pagination};
-use crate::types::question::{NewQuestion, Question};
+use crate::types::question::{
+    Duplicate, NewQuestion, Question, QuestionId, QuestionPatch,
+    QuestionStatus, SimilarQuestion, StatusChange,
+};
+use crate::types::reputation::Privilege;
+use crate::validation::{check_text, field_error, MAX_TITLE_LENGTH};
+use crate::webhooks;
 
+const DEFAULT_SIMILAR_LIMIT: i32 = 5;
+const MAX_SIMILAR_LIMIT: i32 = 20;
+
+/// Censors, stores and announces a new question, shared by the REST and
+/// GraphQL APIs which validate it beforehand
+pub async fn create_question(
+    store: &Store,
+    events: &EventBus,
+    account_id: &AccountId,
+    new_question: NewQuestion,
+) -> Result<Question, Error> {
+    let (title, content) =
+        censor_question(new_question.title, new_question.content).await?;
+    let question = NewQuestion {
+        title,
+        content,
+        tags: store.normalize_tags(new_question.tags).await?,
+    };
+
+    let question =
+        store.add_question(question, account_id.clone()).await?;
+    notify_question_added(store, &question, account_id).await;
+    let event = Event::QuestionAdded {
+        question: question.clone(),
+    };
+    webhooks::enqueue(store, &event).await;
+    events.publish(event).await;
+    Ok(question)
+}
+
+#[utoipa::path(
+    post,
+    path = "/questions",
+    tag = "questions",
+    request_body = NewQuestion,
+    security(("token" = [])),
+    responses(
+        (status = 201, description = "Question created", body = Question,
+            headers(("location" = String, description = "URL of the question"))),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid question", body = Problem, content_type = "application/problem+json"),
+        (status = 502, description = "Profanity check failed", body = Problem, content_type = "application/problem+json"),
+    )
+)]
 #[instrument]
 pub async fn add_question(
     session: Session,
+    base_path: &'static str,
     store: Store,
+    events: EventBus,
     new_question: NewQuestion,
 ) -> Result<impl warp::Reply, warp::Rejection> {
-    let account_id = session.account_id;
-    // Uses tokio::join! to wrap the async function that returns future, without awaiting it
-    // tokio::spawn (parallelism) and tokio::join! (concurrent)
-    let title = check_profanity(new_question.title);
-    let content = check_profanity(new_question.content);
-    // Run both on parallel, returning a tuple that contains the result for both title and content
-    let (title, content) = tokio::join!(title, content);
-
-    // Check if title has an error
-    match title.is_err() {
-        true => return Err(warp::reject::custom(title.unwrap_err())),
-        false => (),
-    }
-    // Check if content has an error
-    match content.is_err() {
-        true => return Err(warp::reject::custom(content.unwrap_err())),
-        false => (),
-    }
-
-    let question = NewQuestion {
-        title: title.unwrap(),
-        content: content.unwrap(),
-        tags: new_question.tags,
-    };
-
-    match store.add_question(question, account_id).await {
-        Ok(_) => {
-            Ok(warp::reply::with_status("Question added", StatusCode::OK))
+    match create_question(
+        &store,
+        &events,
+        &session.account_id,
+        new_question,
+    )
+    .await
+    {
+        Ok(question) => {
+            let location =
+                format!("{}/questions/{}", base_path, question.id.0);
+            Ok(warp::reply::with_status(
+                warp::reply::with_header(
+                    warp::reply::json(&question),
+                    "location",
+                    location,
+                ),
+                StatusCode::CREATED,
+            ))
         }
         Err(e) => Err(warp::reject::custom(e)),
     }
 }
 
+#[utoipa::path(
+    put,
+    path = "/questions/{id}",
+    tag = "questions",
+    params(
+        ("id" = i32, Path, description = "Question id"),
+        ("If-Match" = String, Header, description = "ETag of the question, or `*`"),
+    ),
+    request_body = Question,
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Question replaced", body = Question,
+            headers(("etag" = String, description = "New version of the question"))),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not the owner of the question", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "The question is locked", body = Problem, content_type = "application/problem+json"),
+        (status = 412, description = "The question was modified in the meantime", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid question", body = Problem, content_type = "application/problem+json"),
+        (status = 428, description = "Missing If-Match header", body = Problem, content_type = "application/problem+json"),
+    )
+)]
 #[instrument]
 pub async fn update_question(
     question_id: i32,
     session: Session,
     store: Store,
+    events: EventBus,
+    if_match: Option<String>,
     question: Question,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let account_id = session.account_id;
+    let expected_versions = extract_if_match(if_match)?;
 
     if store.is_question_owner(question_id, &account_id).await? {
-        // Uses tokio::join! to wrap the async function that returns future, without awaiting it
-        let title = check_profanity(question.title);
-        let content = check_profanity(question.content);
-        // Run both concurrently, returning a tuple that contains the result for both title and content
-        let (title, content) = tokio::join!(title, content);
-
-        if title.is_ok() && content.is_ok() {
-            let question = Question {
-                id: question.id,
-                title: title.unwrap(),
-                content: content.unwrap(),
-                tags: question.tags,
-            };
-            match store
-                .update_question(question, question_id, account_id)
-                .await
-            {
-                Ok(res) => Ok(warp::reply::json(&res)),
-                Err(e) => Err(warp::reject::custom(e)),
+        match censor_question(question.title, question.content).await {
+            Ok((title, content)) => {
+                let question = Question {
+                    id: question.id,
+                    title,
+                    content,
+                    content_html: question.content_html,
+                    tags: store.normalize_tags(question.tags).await?,
+                    version: question.version,
+                    status: question.status,
+                    close_reason: question.close_reason,
+                    bounty: question.bounty,
+                    author: question.author,
+                    created_on: question.created_on,
+                    updated_on: question.updated_on,
+                    answer_count: question.answer_count,
+                    score: question.score,
+                };
+                match store
+                    .update_question(
+                        question,
+                        question_id,
+                        account_id.clone(),
+                        expected_versions,
+                    )
+                    .await
+                {
+                    Ok(Some(res)) => {
+                        notify_question_updated(&store, &res, &account_id)
+                            .await;
+                        let event = Event::QuestionUpdated {
+                            question: res.clone(),
+                        };
+                        webhooks::enqueue(&store, &event).await;
+                        events.publish(event).await;
+                        Ok(warp::reply::with_header(
+                            warp::reply::json(&res),
+                            "etag",
+                            etag(res.version),
+                        ))
+                    }
+                    Ok(None) => Err(warp::reject::custom(
+                        unwritten_error(
+                            &store,
+                            question_id,
+                            Error::PreconditionFailed,
+                        )
+                        .await,
+                    )),
+                    Err(e) => Err(warp::reject::custom(e)),
+                }
             }
-        } else {
-            Err(warp::reject::custom(
-                title.expect_err("Expected API call to have failed here"),
-            ))
+            Err(e) => Err(warp::reject::custom(e)),
         }
     } else {
-        Err(warp::reject::custom(handle_errors::Error::Unauthorized))
+        Err(warp::reject::custom(handle_errors::Error::Forbidden))
     }
 }
 
+#[utoipa::path(
+    patch,
+    path = "/questions/{id}",
+    tag = "questions",
+    params(
+        ("id" = i32, Path, description = "Question id"),
+        ("If-Match" = String, Header, description = "ETag of the question, or `*`"),
+    ),
+    request_body(
+        content = QuestionPatch,
+        content_type = "application/merge-patch+json",
+        description = "RFC 7396 merge patch, `null` removes the tags"
+    ),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Question updated", body = Question,
+            headers(("etag" = String, description = "New version of the question"))),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not the owner of the question", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "The question is locked", body = Problem, content_type = "application/problem+json"),
+        (status = 412, description = "The question was modified in the meantime", body = Problem, content_type = "application/problem+json"),
+        (status = 415, description = "Body is not a merge patch", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid patch", body = Problem, content_type = "application/problem+json"),
+        (status = 428, description = "Missing If-Match header", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn patch_question(
+    question_id: i32,
+    session: Session,
+    store: Store,
+    events: EventBus,
+    if_match: Option<String>,
+    patch: QuestionPatch,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let account_id = session.account_id;
+    let expected_versions = extract_if_match(if_match)?;
+
+    if !store.is_question_owner(question_id, &account_id).await? {
+        return Err(warp::reject::custom(handle_errors::Error::Forbidden));
+    }
+    let current = store.get_question_by_id(question_id).await?;
+    if current.status == QuestionStatus::Locked {
+        return Err(warp::reject::custom(Error::QuestionLocked));
+    }
+
+    // Only the members which actually change go through the profanity check
+    let title = censor_if_changed(patch.title.flatten(), &current.title);
+    let content =
+        censor_if_changed(patch.content.flatten(), &current.content);
+    let (title, content) = tokio::join!(title, content);
+    let tags = match patch.tags {
+        Some(tags) => Some(store.normalize_tags(tags).await?),
+        None => None,
+    };
+    let patch = QuestionPatch {
+        title: title?.map(Some),
+        content: content?.map(Some),
+        tags: tags.filter(|tags| *tags != current.tags),
+    };
+
+    if patch.title.is_none()
+        && patch.content.is_none()
+        && patch.tags.is_none()
+    {
+        // Nothing changes, but a stale `If-Match` still has to fail
+        if let Some(versions) = &expected_versions {
+            if !versions.contains(&current.version) {
+                return Err(warp::reject::custom(
+                    handle_errors::Error::PreconditionFailed,
+                ));
+            }
+        }
+        let etag = etag(current.version);
+        return Ok(warp::reply::with_header(
+            warp::reply::json(&current),
+            "etag",
+            etag,
+        ));
+    }
+
+    match store
+        .patch_question(
+            patch,
+            question_id,
+            account_id.clone(),
+            expected_versions,
+        )
+        .await
+    {
+        Ok(Some(res)) => {
+            notify_question_updated(&store, &res, &account_id).await;
+            let event = Event::QuestionUpdated {
+                question: res.clone(),
+            };
+            webhooks::enqueue(&store, &event).await;
+            events.publish(event).await;
+            Ok(warp::reply::with_header(
+                warp::reply::json(&res),
+                "etag",
+                etag(res.version),
+            ))
+        }
+        Ok(None) => Err(warp::reject::custom(
+            unwritten_error(
+                &store,
+                question_id,
+                Error::PreconditionFailed,
+            )
+            .await,
+        )),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+/// Error of a write which matched no question because of its status,
+/// `QuestionLocked` if the question is locked, else `otherwise`
+pub async fn unwritten_error(
+    store: &Store,
+    question_id: i32,
+    otherwise: Error,
+) -> Error {
+    match store.get_question_status(question_id).await {
+        Ok(QuestionStatus::Locked) => Error::QuestionLocked,
+        Ok(_) => otherwise,
+        Err(e) => e,
+    }
+}
+
+/// Runs the profanity check on the title and the content of a question,
+/// returning both censored
+pub async fn censor_question(
+    title: String,
+    content: String,
+) -> Result<(String, String), handle_errors::Error> {
+    // Uses tokio::join! to wrap the async function that returns future, without awaiting it
+    // tokio::spawn (parallelism) and tokio::join! (concurrent)
+    let title = check_profanity(title);
+    let content = check_profanity(content);
+    // Run both concurrently, returning a tuple that contains the result for both title and content
+    let (title, content) = tokio::join!(title, content);
+
+    Ok((title?, content?))
+}
+
+async fn censor_if_changed(
+    value: Option<String>,
+    current: &str,
+) -> Result<Option<String>, handle_errors::Error> {
+    match value {
+        Some(value) if value != current => {
+            check_profanity(value).await.map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/questions",
+    tag = "questions",
+    params(
+        ("limit" = Option<i32>, Query, description = "Maximum number of questions, requires `offset`"),
+        ("offset" = Option<i32>, Query, description = "Number of questions to skip, requires `limit`"),
+        ("featured" = Option<bool>, Query, description = "Only questions with an open bounty, the largest first"),
+    ),
+    responses(
+        (status = 200, description = "List of questions", body = Vec<Question>),
+        (status = 400, description = "Invalid pagination", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid `featured`", body = Problem, content_type = "application/problem+json"),
+    )
+)]
 #[instrument]
 pub async fn get_questions(
-    params: HashMap<String, String>,
+    mut params: HashMap<String, String>,
     store: Store,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     event!(target: "rusty-web-development", Level::INFO, "querying questions");
+    let featured = match params.remove("featured").as_deref() {
+        None | Some("false") => false,
+        Some("true") => true,
+        Some(_) => {
+            return Err(warp::reject::custom(Error::ValidationError(
+                vec![field_error("featured", "must be true or false")],
+            )));
+        }
+    };
     let mut pagination = Pagination::default();
     if !params.is_empty() {
         event!(Level::INFO, pagination = true);
@@ -100,7 +387,7 @@ pub async fn get_questions(
     }
     event!(Level::INFO, pagination = false);
     let res: Vec<Question> = match store
-        .get_questions(pagination.limit, pagination.offset)
+        .get_questions(pagination.limit, pagination.offset, featured)
         .await
     {
         Ok(res) => res,
@@ -111,35 +398,378 @@ pub async fn get_questions(
     Ok(warp::reply::json(&res))
 }
 
+#[utoipa::path(
+    get,
+    path = "/questions/{id}",
+    tag = "questions",
+    params(
+        ("id" = i32, Path, description = "Question id"),
+        ("redirect" = Option<bool>, Query, description = "`false` returns a duplicate instead of redirecting to its canonical question"),
+        ("If-None-Match" = Option<String>, Header, description = "ETag of a cached copy"),
+    ),
+    responses(
+        (status = 200, description = "The question", body = Question,
+            headers(
+                ("etag" = String, description = "Version of the question"),
+                ("link" = Option<String>, description = "Canonical question of a duplicate, `rel=\"canonical\"`"),
+            )),
+        (status = 302, description = "The question is a duplicate",
+            headers(("location" = String, description = "URL of the canonical question"))),
+        (status = 304, description = "The cached copy is still current"),
+        (status = 404, description = "Question not found", body = Problem, content_type = "application/problem+json"),
+    )
+)]
 #[instrument]
 pub async fn get_question_by_id(
     question_id: i32,
+    base_path: &'static str,
     store: Store,
+    if_none_match: Option<String>,
+    params: HashMap<String, String>,
 ) -> Result<impl warp::Reply, warp::Rejection> {
+    let duplicate_of = store.get_duplicate_of(question_id).await?;
+    let canonical_url = duplicate_of
+        .map(|canonical| format!("{}/questions/{}", base_path, canonical));
+    let redirect =
+        params.get("redirect").map(String::as_str) != Some("false");
+    if let (Some(url), true) = (&canonical_url, redirect) {
+        return Ok(warp::reply::with_header(
+            warp::reply::with_status(warp::reply(), StatusCode::FOUND),
+            "location",
+            url.as_str(),
+        )
+        .into_response());
+    }
+
     let res = match store.get_question_by_id(question_id).await {
         Ok(res) => res,
         Err(e) => return Err(warp::reject::custom(e)),
     };
 
-    Ok(warp::reply::json(&res))
+    let etag = etag(res.version);
+    match if_none_match {
+        Some(header) if matches_if_none_match(&header, res.version) => {
+            Ok(warp::reply::with_header(
+                warp::reply::with_status(
+                    warp::reply(),
+                    StatusCode::NOT_MODIFIED,
+                ),
+                "etag",
+                etag,
+            )
+            .into_response())
+        }
+        _ => {
+            let mut res = warp::reply::with_header(
+                warp::reply::json(&res),
+                "etag",
+                etag,
+            )
+            .into_response();
+            if let Some(url) = canonical_url {
+                if let Ok(link) =
+                    format!("<{}>; rel=\"canonical\"", url).parse()
+                {
+                    res.headers_mut().insert("link", link);
+                }
+            }
+            Ok(res)
+        }
+    }
 }
 
+#[utoipa::path(
+    get,
+    path = "/questions/similar",
+    tag = "questions",
+    params(
+        ("title" = String, Query, description = "Title of the new question"),
+        ("limit" = Option<i32>, Query, description = "Maximum number of questions, 5 by default and at most 20"),
+    ),
+    responses(
+        (status = 200, description = "Likely duplicates, most similar first", body = Vec<SimilarQuestion>),
+        (status = 400, description = "Invalid limit", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid title", body = Problem, content_type = "application/problem+json"),
+    )
+)]
 #[instrument]
-pub async fn delete_question(
+pub async fn get_similar_questions(
+    params: HashMap<String, String>,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let title = params.get("title").map(String::as_str).unwrap_or("");
+    let mut errors = Vec::new();
+    check_text(&mut errors, "title", title, MAX_TITLE_LENGTH);
+    if !errors.is_empty() {
+        return Err(warp::reject::custom(Error::ValidationError(errors)));
+    }
+    let limit = match params.get("limit") {
+        Some(limit) => limit
+            .parse::<i32>()
+            .map_err(Error::ParseInt)?
+            .clamp(1, MAX_SIMILAR_LIMIT),
+        None => DEFAULT_SIMILAR_LIMIT,
+    };
+
+    match store.get_similar_questions(title.trim(), limit).await {
+        Ok(questions) => Ok(warp::reply::json(&questions)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+/// The owner of a question, a moderator, or an account with the
+/// privilege, may close it
+async fn check_can_close(
+    store: &Store,
+    question_id: i32,
+    account_id: &AccountId,
+) -> Result<(), Error> {
+    if store.get_question_owner(question_id).await? == *account_id
+        || store.is_moderator(account_id).await?
+    {
+        return Ok(());
+    }
+    Privilege::CloseQuestions
+        .check(store.get_reputation(account_id).await?)
+}
+
+#[utoipa::path(
+    post,
+    path = "/questions/{id}/duplicate",
+    tag = "questions",
+    params(("id" = i32, Path, description = "Question id")),
+    request_body = Duplicate,
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Question marked as a duplicate, of the canonical question of the given one", body = Duplicate),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Neither the owner nor enough reputation", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Question not found", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "The question is locked", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid canonical question", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn mark_duplicate(
     question_id: i32,
     session: Session,
     store: Store,
+    duplicate: Duplicate,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    check_can_close(&store, question_id, &session.account_id).await?;
+
+    // Duplicates point to the canonical question directly
+    let target = duplicate.duplicate_of.0;
+    let canonical = match store.get_duplicate_of(target).await {
+        Ok(duplicate_of) => duplicate_of.unwrap_or(target),
+        Err(Error::DatabaseQueryError(sqlx::Error::RowNotFound)) => {
+            return Err(warp::reject::custom(Error::ValidationError(
+                vec![field_error(
+                    "duplicate_of",
+                    "must be an existing question",
+                )],
+            )));
+        }
+        Err(e) => return Err(warp::reject::custom(e)),
+    };
+    if target == question_id || canonical == question_id {
+        return Err(warp::reject::custom(Error::ValidationError(vec![
+            field_error("duplicate_of", "must be another question"),
+        ])));
+    }
+
+    if !store.mark_duplicate(question_id, canonical).await? {
+        return Err(warp::reject::custom(
+            unwritten_error(
+                &store,
+                question_id,
+                Error::DatabaseQueryError(sqlx::Error::RowNotFound),
+            )
+            .await,
+        ));
+    }
+    Ok(warp::reply::json(&Duplicate {
+        duplicate_of: QuestionId(canonical),
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/questions/{id}/duplicate",
+    tag = "questions",
+    params(("id" = i32, Path, description = "Question id")),
+    security(("token" = [])),
+    responses(
+        (status = 204, description = "Question no longer a duplicate"),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Neither the owner nor enough reputation", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "The question isn't a duplicate", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn unmark_duplicate(
+    question_id: i32,
+    session: Session,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    check_can_close(&store, question_id, &session.account_id).await?;
+    if store.unmark_duplicate(question_id).await? {
+        Ok(warp::reply::with_status(
+            warp::reply(),
+            StatusCode::NO_CONTENT,
+        ))
+    } else {
+        Err(warp::reject::custom(Error::DatabaseQueryError(
+            sqlx::Error::RowNotFound,
+        )))
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/questions/{id}/status",
+    tag = "questions",
+    params(("id" = i32, Path, description = "Question id")),
+    request_body = StatusChange,
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Status changed", body = Question,
+            headers(("etag" = String, description = "New version of the question"))),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not a moderator", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Question not found", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "The question can't go from its status to the new one", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid status change", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn set_question_status(
+    question_id: i32,
+    session: Session,
+    store: Store,
+    events: EventBus,
+    change: StatusChange,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let account_id = session.account_id;
-    if store.is_question_owner(question_id, &account_id).await? {
-        match store.delete_question(question_id, account_id).await {
-            Ok(_) => Ok(warp::reply::with_status(
-                format!("Question {} deleted", question_id),
-                StatusCode::OK,
-            )),
-            Err(e) => Err(warp::reject::custom(e)),
+    if !store.is_moderator(&account_id).await? {
+        return Err(warp::reject::custom(Error::ModeratorRequired));
+    }
+
+    let from = store.get_question_status(question_id).await?;
+    if !from.can_become(change.status) {
+        return Err(warp::reject::custom(Error::InvalidStatusTransition(
+            from.as_str().to_string(),
+            change.status.as_str().to_string(),
+        )));
+    }
+    match store
+        .set_question_status(
+            question_id,
+            from,
+            change.status,
+            change.reason,
+        )
+        .await?
+    {
+        Some(res) => {
+            notify_question_updated(&store, &res, &account_id).await;
+            let event = Event::QuestionUpdated {
+                question: res.clone(),
+            };
+            webhooks::enqueue(&store, &event).await;
+            events.publish(event).await;
+            Ok(warp::reply::with_header(
+                warp::reply::json(&res),
+                "etag",
+                etag(res.version),
+            ))
         }
+        // Another moderator changed the status in the meantime
+        None => Err(warp::reject::custom(Error::InvalidStatusTransition(
+            from.as_str().to_string(),
+            change.status.as_str().to_string(),
+        ))),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/questions/{id}",
+    tag = "questions",
+    params(
+        ("id" = i32, Path, description = "Question id"),
+        ("If-Match" = String, Header, description = "ETag of the question, or `*`"),
+    ),
+    security(("token" = [])),
+    responses(
+        (status = 204, description = "Question deleted"),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Neither the owner of the question nor a moderator", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Question not found", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "The question is locked, unless deleted by a moderator", body = Problem, content_type = "application/problem+json"),
+        (status = 412, description = "The question was modified in the meantime", body = Problem, content_type = "application/problem+json"),
+        (status = 428, description = "Missing If-Match header", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn delete_question(
+    question_id: i32,
+    session: Session,
+    store: Store,
+    events: EventBus,
+    blobs: Arc<dyn BlobStore>,
+    if_match: Option<String>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let account_id = session.account_id;
+    let expected_versions = extract_if_match(if_match)?;
+    let moderator = store.is_moderator(&account_id).await?;
+    // Read before the attachments go with the question
+    let keys = store.get_attachment_keys(question_id).await?;
+    let deleted = match store
+        .delete_question(
+            question_id,
+            account_id.clone(),
+            moderator,
+            expected_versions,
+        )
+        .await
+    {
+        Ok(deleted) => deleted,
+        Err(e) => return Err(warp::reject::custom(e)),
+    };
+
+    if let Some(tags) = deleted {
+        delete_blobs(&blobs, keys).await;
+        let event = Event::QuestionDeleted {
+            question_id: QuestionId(question_id),
+            tags,
+        };
+        webhooks::enqueue(&store, &event).await;
+        events.publish(event).await;
+        Ok(warp::reply::with_status(
+            warp::reply(),
+            StatusCode::NO_CONTENT,
+        ))
+    } else if !store.question_exists(question_id).await? {
+        Err(warp::reject::custom(
+            handle_errors::Error::DatabaseQueryError(
+                sqlx::Error::RowNotFound,
+            ),
+        ))
+    } else if moderator {
+        Err(warp::reject::custom(
+            handle_errors::Error::PreconditionFailed,
+        ))
+    } else if store.is_question_owner(question_id, &account_id).await? {
+        Err(warp::reject::custom(
+            unwritten_error(
+                &store,
+                question_id,
+                Error::PreconditionFailed,
+            )
+            .await,
+        ))
     } else {
-        Err(warp::reject::custom(handle_errors::Error::Unauthorized))
+        Err(warp::reject::custom(handle_errors::Error::Forbidden))
     }
 }
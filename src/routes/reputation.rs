@@ -0,0 +1,76 @@
+use handle_errors::Problem;
+use std::collections::HashMap;
+use tracing::instrument;
+
+use crate::store::Store;
+use crate::types::account::AccountId;
+use crate::types::pagination::{extract_pagination, Pagination};
+use crate::types::reputation::{
+    LeaderboardEntry, LeaderboardWindow, Privilege, Reputation,
+};
+
+#[utoipa::path(
+    get,
+    path = "/accounts/{id}/reputation",
+    tag = "reputation",
+    params(("id" = i32, Path, description = "Account id")),
+    responses(
+        (status = 200, description = "Reputation and privileges of the account", body = Reputation),
+        (status = 404, description = "Account not found", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn get_reputation(
+    account_id: i32,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let account = store.get_account_by_id(&AccountId(account_id)).await?;
+    let reputation = store.get_reputation(&account.id).await?;
+    Ok(warp::reply::json(&Reputation {
+        account_id: account.id,
+        reputation,
+        privileges: Privilege::granted(reputation),
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/leaderboard",
+    tag = "reputation",
+    params(
+        ("window" = Option<String>, Query, description = "`week`, `month` or `all`, defaults to `all`"),
+        ("limit" = Option<i32>, Query, description = "Maximum number of accounts, requires `offset`"),
+        ("offset" = Option<i32>, Query, description = "Number of accounts to skip, requires `limit`"),
+    ),
+    responses(
+        (status = 200, description = "Accounts which earned the most reputation during the window", body = Vec<LeaderboardEntry>),
+        (status = 400, description = "Invalid pagination", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid window", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn get_leaderboard(
+    mut params: HashMap<String, String>,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let window = match params.remove("window") {
+        Some(window) => window.parse()?,
+        None => LeaderboardWindow::All,
+    };
+    let mut pagination = Pagination::default();
+    if !params.is_empty() {
+        pagination = extract_pagination(params)?;
+    }
+
+    match store
+        .get_leaderboard(
+            window.seconds(),
+            pagination.limit,
+            pagination.offset,
+        )
+        .await
+    {
+        Ok(entries) => Ok(warp::reply::json(&entries)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
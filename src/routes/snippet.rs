@@ -0,0 +1,64 @@
+use handle_errors::{Error, Problem};
+use std::collections::HashMap;
+use tracing::instrument;
+
+use crate::snippets::normalize_language;
+use crate::store::Store;
+use crate::types::pagination::{extract_pagination, Pagination};
+use crate::types::snippet::Snippet;
+use crate::validation::{check_text, MAX_TITLE_LENGTH};
+
+#[utoipa::path(
+    get,
+    path = "/snippets",
+    tag = "snippets",
+    params(
+        ("lang" = Option<String>, Query, description = "Language of the snippets, aliases like `rs` are accepted"),
+        ("q" = Option<String>, Query, description = "Text the code contains, case insensitive"),
+        ("limit" = Option<i32>, Query, description = "Maximum number of snippets, requires `offset`"),
+        ("offset" = Option<i32>, Query, description = "Number of snippets to skip, requires `limit`"),
+    ),
+    responses(
+        (status = 200, description = "Code snippets of questions and answers, latest first", body = Vec<Snippet>),
+        (status = 400, description = "Invalid pagination", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid search", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn get_snippets(
+    mut params: HashMap<String, String>,
+    base_path: &'static str,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let language = params
+        .remove("lang")
+        .and_then(|lang| normalize_language(&lang));
+    let query = params.remove("q");
+    if let Some(query) = &query {
+        let mut errors = Vec::new();
+        check_text(&mut errors, "q", query, MAX_TITLE_LENGTH);
+        if !errors.is_empty() {
+            return Err(warp::reject::custom(Error::ValidationError(
+                errors,
+            )));
+        }
+    }
+    let mut pagination = Pagination::default();
+    if !params.is_empty() {
+        pagination = extract_pagination(params)?;
+    }
+
+    match store
+        .search_snippets(
+            base_path,
+            language.as_deref(),
+            query.as_deref(),
+            pagination.limit,
+            pagination.offset,
+        )
+        .await
+    {
+        Ok(snippets) => Ok(warp::reply::json(&snippets)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
@@ -0,0 +1,164 @@
+use handle_errors::{Error, Problem};
+use percent_encoding::percent_decode_str;
+use std::collections::HashMap;
+use tracing::instrument;
+
+use crate::store::Store;
+use crate::types::account::Session;
+use crate::types::pagination::{extract_pagination, Pagination};
+use crate::types::question::Question;
+use crate::types::reputation::Privilege;
+use crate::types::tag::{Tag, TagUpdate};
+use crate::validation::{
+    check_text, field_error, normalize_tag, MAX_TAG_LENGTH,
+};
+
+/// Decodes the tag of the path into its slug
+pub fn tag_param(tag: &str) -> Result<String, Error> {
+    let tag = percent_decode_str(tag).decode_utf8_lossy();
+    let mut errors = Vec::new();
+    check_text(&mut errors, "tag", &tag, MAX_TAG_LENGTH);
+    let slug = normalize_tag(&tag);
+    if errors.is_empty() && slug.is_empty() {
+        errors
+            .push(field_error("tag", "must contain a letter or a digit"));
+    }
+    if errors.is_empty() {
+        Ok(slug)
+    } else {
+        Err(Error::ValidationError(errors))
+    }
+}
+
+fn pagination(
+    params: HashMap<String, String>,
+) -> Result<Pagination, Error> {
+    if params.is_empty() {
+        Ok(Pagination::default())
+    } else {
+        extract_pagination(params)
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/tags",
+    tag = "tags",
+    params(
+        ("limit" = Option<i32>, Query, description = "Maximum number of tags, requires `offset`"),
+        ("offset" = Option<i32>, Query, description = "Number of tags to skip, requires `limit`"),
+    ),
+    responses(
+        (status = 200, description = "Tags, most used first", body = Vec<Tag>),
+        (status = 400, description = "Invalid pagination", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn get_tags(
+    params: HashMap<String, String>,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pagination = pagination(params)?;
+    match store
+        .get_tags(None, pagination.limit, pagination.offset)
+        .await
+    {
+        Ok(tags) => Ok(warp::reply::json(&tags)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/tags/{slug}/questions",
+    tag = "tags",
+    params(
+        ("slug" = String, Path, description = "Slug or synonym of the tag"),
+        ("limit" = Option<i32>, Query, description = "Maximum number of questions, requires `offset`"),
+        ("offset" = Option<i32>, Query, description = "Number of questions to skip, requires `limit`"),
+    ),
+    responses(
+        (status = 200, description = "Questions with the tag, latest first", body = Vec<Question>),
+        (status = 400, description = "Invalid pagination", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid tag", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn get_tag_questions(
+    slug: String,
+    params: HashMap<String, String>,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let slug = store.canonical_tag(&tag_param(&slug)?).await?;
+    let pagination = pagination(params)?;
+    match store
+        .get_questions_by_tag(&slug, pagination.limit, pagination.offset)
+        .await
+    {
+        Ok(questions) => Ok(warp::reply::json(&questions)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/tags/{slug}",
+    tag = "tags",
+    params(("slug" = String, Path, description = "Slug or synonym of the tag")),
+    request_body = TagUpdate,
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Tag created or updated", body = Tag),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Not enough reputation to edit tags", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid tag", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn update_tag(
+    slug: String,
+    session: Session,
+    store: Store,
+    update: TagUpdate,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    Privilege::EditTags
+        .check(store.get_reputation(&session.account_id).await?)?;
+    let slug = store.canonical_tag(&tag_param(&slug)?).await?;
+    let synonyms = update.synonym_slugs();
+    if synonyms.contains(&slug) {
+        return Err(warp::reject::custom(Error::ValidationError(vec![
+            field_error("synonyms", "must not contain the tag itself"),
+        ])));
+    }
+
+    store
+        .update_tag(&slug, update.description, synonyms)
+        .await?;
+    let mut tags = store.get_tags(Some(&slug), None, 0).await?;
+    match tags.pop() {
+        Some(tag) => Ok(warp::reply::json(&tag)),
+        None => Err(warp::reject::custom(Error::DatabaseQueryError(
+            sqlx::Error::RowNotFound,
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tag_tests {
+    use super::*;
+
+    #[test]
+    fn tag_param_is_decoded_and_normalized() {
+        // Arrange
+        let encoded = "C%2B%2B";
+        let too_long = "a".repeat(MAX_TAG_LENGTH + 1);
+        // Act
+        let tag = tag_param(encoded);
+        let invalid = tag_param(&too_long);
+        let empty = tag_param("%21%21");
+        // Assert
+        assert_eq!(tag.unwrap(), "c++");
+        assert!(matches!(invalid, Err(Error::ValidationError(_))));
+        assert!(matches!(empty, Err(Error::ValidationError(_))));
+    }
+}
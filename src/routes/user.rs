@@ -0,0 +1,161 @@
+use handle_errors::Problem;
+use std::collections::HashMap;
+use tracing::instrument;
+
+use crate::store::Store;
+use crate::types::account::{AccountId, Session};
+use crate::types::answer::Answer;
+use crate::types::pagination::{extract_pagination, Pagination};
+use crate::types::profile::{Activity, Profile, ProfileUpdate};
+use crate::types::question::Question;
+
+fn pagination(
+    params: HashMap<String, String>,
+) -> Result<Pagination, warp::Rejection> {
+    if params.is_empty() {
+        Ok(Pagination::default())
+    } else {
+        Ok(extract_pagination(params)?)
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/users/{id}",
+    tag = "users",
+    params(("id" = i32, Path, description = "Account id")),
+    responses(
+        (status = 200, description = "Public profile of the account", body = Profile),
+        (status = 404, description = "Account not found", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn get_user(
+    account_id: i32,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let profile = store.get_profile(&AccountId(account_id)).await?;
+    Ok(warp::reply::json(&profile))
+}
+
+#[utoipa::path(
+    get,
+    path = "/users/{id}/questions",
+    tag = "users",
+    params(
+        ("id" = i32, Path, description = "Account id"),
+        ("limit" = Option<i32>, Query, description = "Maximum number of questions, requires `offset`"),
+        ("offset" = Option<i32>, Query, description = "Number of questions to skip, requires `limit`"),
+    ),
+    responses(
+        (status = 200, description = "Questions of the account, latest first", body = Vec<Question>),
+        (status = 400, description = "Invalid pagination", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Account not found", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn get_user_questions(
+    account_id: i32,
+    params: HashMap<String, String>,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pagination = pagination(params)?;
+    let account_id = AccountId(account_id);
+    store.get_account_by_id(&account_id).await?;
+    let questions = store
+        .get_questions_by_account(
+            &account_id,
+            pagination.limit,
+            pagination.offset,
+        )
+        .await?;
+    Ok(warp::reply::json(&questions))
+}
+
+#[utoipa::path(
+    get,
+    path = "/users/{id}/answers",
+    tag = "users",
+    params(
+        ("id" = i32, Path, description = "Account id"),
+        ("limit" = Option<i32>, Query, description = "Maximum number of answers, requires `offset`"),
+        ("offset" = Option<i32>, Query, description = "Number of answers to skip, requires `limit`"),
+    ),
+    responses(
+        (status = 200, description = "Answers of the account, latest first", body = Vec<Answer>),
+        (status = 400, description = "Invalid pagination", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Account not found", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn get_user_answers(
+    account_id: i32,
+    params: HashMap<String, String>,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pagination = pagination(params)?;
+    let account_id = AccountId(account_id);
+    store.get_account_by_id(&account_id).await?;
+    let answers = store
+        .get_answers_by_account(
+            &account_id,
+            pagination.limit,
+            pagination.offset,
+        )
+        .await?;
+    Ok(warp::reply::json(&answers))
+}
+
+#[utoipa::path(
+    get,
+    path = "/users/{id}/activity",
+    tag = "users",
+    params(
+        ("id" = i32, Path, description = "Account id"),
+        ("limit" = Option<i32>, Query, description = "Maximum number of entries, requires `offset`"),
+        ("offset" = Option<i32>, Query, description = "Number of entries to skip, requires `limit`"),
+    ),
+    responses(
+        (status = 200, description = "Questions asked and answers given by the account, latest first", body = Vec<Activity>),
+        (status = 400, description = "Invalid pagination", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Account not found", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn get_user_activity(
+    account_id: i32,
+    params: HashMap<String, String>,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let pagination = pagination(params)?;
+    let account_id = AccountId(account_id);
+    store.get_account_by_id(&account_id).await?;
+    let activity = store
+        .get_activity(&account_id, pagination.limit, pagination.offset)
+        .await?;
+    Ok(warp::reply::json(&activity))
+}
+
+#[utoipa::path(
+    put,
+    path = "/accounts/me/profile",
+    tag = "users",
+    request_body = ProfileUpdate,
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Public profile of the account", body = Profile),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 409, description = "Username taken by another account", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid profile", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn update_profile(
+    session: Session,
+    store: Store,
+    update: ProfileUpdate,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let profile =
+        store.update_profile(&session.account_id, &update).await?;
+    Ok(warp::reply::json(&profile))
+}
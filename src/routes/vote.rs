@@ -0,0 +1,154 @@
+use handle_errors::{Error, Problem};
+use tracing::instrument;
+
+use crate::store::Store;
+use crate::types::account::{AccountId, Session};
+use crate::types::reputation::Privilege;
+use crate::types::vote::{Score, Vote, VoteTarget};
+
+/// Records the vote after checking the voter may cast it
+async fn vote(
+    store: &Store,
+    target: VoteTarget,
+    post_id: i32,
+    voter: &AccountId,
+    value: Option<i16>,
+) -> Result<Score, Error> {
+    let owner = match target {
+        VoteTarget::Question => store.get_question_owner(post_id).await?,
+        VoteTarget::Answer => store.get_answer_owner(post_id).await?.0,
+    };
+    if owner == *voter {
+        return Err(Error::Forbidden);
+    }
+    if value.is_some_and(|value| value < 0) {
+        Privilege::VoteDown.check(store.get_reputation(voter).await?)?;
+    }
+    store.set_vote(target, post_id, &owner, voter, value).await
+}
+
+#[utoipa::path(
+    put,
+    path = "/questions/{id}/vote",
+    tag = "votes",
+    params(("id" = i32, Path, description = "Question id")),
+    request_body = Vote,
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Vote recorded", body = Score),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Own question, or not enough reputation to vote down", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Question not found", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid vote", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn vote_question(
+    question_id: i32,
+    session: Session,
+    store: Store,
+    body: Vote,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let score = vote(
+        &store,
+        VoteTarget::Question,
+        question_id,
+        &session.account_id,
+        Some(body.value),
+    )
+    .await?;
+    Ok(warp::reply::json(&score))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/questions/{id}/vote",
+    tag = "votes",
+    params(("id" = i32, Path, description = "Question id")),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Vote withdrawn", body = Score),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Own question", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Question not found", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn unvote_question(
+    question_id: i32,
+    session: Session,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let score = vote(
+        &store,
+        VoteTarget::Question,
+        question_id,
+        &session.account_id,
+        None,
+    )
+    .await?;
+    Ok(warp::reply::json(&score))
+}
+
+#[utoipa::path(
+    put,
+    path = "/answers/{id}/vote",
+    tag = "votes",
+    params(("id" = i32, Path, description = "Answer id")),
+    request_body = Vote,
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Vote recorded", body = Score),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Own answer, or not enough reputation to vote down", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Answer not found", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid vote", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn vote_answer(
+    answer_id: i32,
+    session: Session,
+    store: Store,
+    body: Vote,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let score = vote(
+        &store,
+        VoteTarget::Answer,
+        answer_id,
+        &session.account_id,
+        Some(body.value),
+    )
+    .await?;
+    Ok(warp::reply::json(&score))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/answers/{id}/vote",
+    tag = "votes",
+    params(("id" = i32, Path, description = "Answer id")),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Vote withdrawn", body = Score),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Own answer", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Answer not found", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn unvote_answer(
+    answer_id: i32,
+    session: Session,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let score = vote(
+        &store,
+        VoteTarget::Answer,
+        answer_id,
+        &session.account_id,
+        None,
+    )
+    .await?;
+    Ok(warp::reply::json(&score))
+}
@@ -0,0 +1,156 @@
+use handle_errors::{Error, Problem};
+use tracing::instrument;
+use warp::http::StatusCode;
+
+use crate::routes::tag::tag_param;
+use crate::store::Store;
+use crate::types::account::Session;
+use crate::types::watch::WatchList;
+
+fn not_found() -> warp::Rejection {
+    warp::reject::custom(Error::DatabaseQueryError(
+        sqlx::Error::RowNotFound,
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/questions/{id}/watch",
+    tag = "watching",
+    params(("id" = i32, Path, description = "Question id")),
+    security(("token" = [])),
+    responses(
+        (status = 204, description = "Question watched"),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Question not found", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn watch_question(
+    question_id: i32,
+    session: Session,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if !store.question_exists(question_id).await? {
+        return Err(not_found());
+    }
+    store
+        .watch_question(question_id, &session.account_id)
+        .await?;
+    Ok(warp::reply::with_status(
+        warp::reply(),
+        StatusCode::NO_CONTENT,
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/questions/{id}/watch",
+    tag = "watching",
+    params(("id" = i32, Path, description = "Question id")),
+    security(("token" = [])),
+    responses(
+        (status = 204, description = "Question no longer watched"),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "The question isn't watched", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn unwatch_question(
+    question_id: i32,
+    session: Session,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if store
+        .unwatch_question(question_id, &session.account_id)
+        .await?
+    {
+        Ok(warp::reply::with_status(
+            warp::reply(),
+            StatusCode::NO_CONTENT,
+        ))
+    } else {
+        Err(not_found())
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/tags/{tag}/watch",
+    tag = "watching",
+    params(("tag" = String, Path, description = "Tag, compared without case")),
+    security(("token" = [])),
+    responses(
+        (status = 204, description = "Tag watched"),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid tag", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn watch_tag(
+    tag: String,
+    session: Session,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let tag = store.canonical_tag(&tag_param(&tag)?).await?;
+    store.watch_tag(&tag, &session.account_id).await?;
+    Ok(warp::reply::with_status(
+        warp::reply(),
+        StatusCode::NO_CONTENT,
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/tags/{tag}/watch",
+    tag = "watching",
+    params(("tag" = String, Path, description = "Tag, compared without case")),
+    security(("token" = [])),
+    responses(
+        (status = 204, description = "Tag no longer watched"),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "The tag isn't watched", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid tag", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn unwatch_tag(
+    tag: String,
+    session: Session,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let tag = store.canonical_tag(&tag_param(&tag)?).await?;
+    if store.unwatch_tag(&tag, &session.account_id).await? {
+        Ok(warp::reply::with_status(
+            warp::reply(),
+            StatusCode::NO_CONTENT,
+        ))
+    } else {
+        Err(not_found())
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/accounts/me/watching",
+    tag = "watching",
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Questions and tags the account watches", body = WatchList),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn get_watching(
+    session: Session,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let questions = store.get_watched_questions(&session.account_id);
+    let tags = store.get_watched_tags(&session.account_id);
+    let (questions, tags) = tokio::join!(questions, tags);
+
+    Ok(warp::reply::json(&WatchList {
+        questions: questions?,
+        tags: tags?,
+    }))
+}
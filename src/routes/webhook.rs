@@ -0,0 +1,145 @@
+use handle_errors::Problem;
+use std::collections::HashMap;
+use tracing::instrument;
+use warp::http::StatusCode;
+
+use crate::store::Store;
+use crate::types::account::Session;
+use crate::types::pagination::{extract_pagination, Pagination};
+use crate::types::webhook::{NewWebhook, Webhook, WebhookDelivery};
+
+#[utoipa::path(
+    post,
+    path = "/webhooks",
+    tag = "webhooks",
+    request_body = NewWebhook,
+    security(("token" = [])),
+    responses(
+        (status = 201, description = "Webhook registered", body = Webhook,
+            headers(("location" = String, description = "URL of the delivery log"))),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid webhook", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument(skip(new_webhook))]
+pub async fn add_webhook(
+    session: Session,
+    base_path: &'static str,
+    store: Store,
+    new_webhook: NewWebhook,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match store.add_webhook(new_webhook, &session.account_id).await {
+        Ok(webhook) => {
+            let location = format!(
+                "{}/webhooks/{}/deliveries",
+                base_path, webhook.id.0
+            );
+            Ok(warp::reply::with_status(
+                warp::reply::with_header(
+                    warp::reply::json(&webhook),
+                    "location",
+                    location,
+                ),
+                StatusCode::CREATED,
+            ))
+        }
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/webhooks",
+    tag = "webhooks",
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Webhooks of the account", body = Vec<Webhook>),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn get_webhooks(
+    session: Session,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match store.get_webhooks(&session.account_id).await {
+        Ok(webhooks) => Ok(warp::reply::json(&webhooks)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/webhooks/{id}",
+    tag = "webhooks",
+    params(("id" = i32, Path, description = "Webhook id")),
+    security(("token" = [])),
+    responses(
+        (status = 204, description = "Webhook and its pending deliveries deleted"),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Webhook not found", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn delete_webhook(
+    webhook_id: i32,
+    session: Session,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    match store.delete_webhook(webhook_id, &session.account_id).await {
+        Ok(true) => Ok(warp::reply::with_status(
+            warp::reply(),
+            StatusCode::NO_CONTENT,
+        )),
+        Ok(false) => Err(warp::reject::custom(
+            handle_errors::Error::DatabaseQueryError(
+                sqlx::Error::RowNotFound,
+            ),
+        )),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/webhooks/{id}/deliveries",
+    tag = "webhooks",
+    params(
+        ("id" = i32, Path, description = "Webhook id"),
+        ("limit" = Option<i32>, Query, description = "Maximum number of deliveries, requires `offset`"),
+        ("offset" = Option<i32>, Query, description = "Number of deliveries to skip, requires `limit`"),
+    ),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Delivery log of the webhook, latest first", body = Vec<WebhookDelivery>),
+        (status = 400, description = "Invalid pagination", body = Problem, content_type = "application/problem+json"),
+        (status = 401, description = "Missing or invalid token", body = Problem, content_type = "application/problem+json"),
+        (status = 404, description = "Webhook not found", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+#[instrument]
+pub async fn get_webhook_deliveries(
+    webhook_id: i32,
+    params: HashMap<String, String>,
+    session: Session,
+    store: Store,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let mut pagination = Pagination::default();
+    if !params.is_empty() {
+        pagination = extract_pagination(params)?;
+    }
+    let webhook =
+        store.get_webhook(webhook_id, &session.account_id).await?;
+
+    match store
+        .get_webhook_deliveries(
+            webhook.id.0,
+            pagination.limit,
+            pagination.offset,
+        )
+        .await
+    {
+        Ok(deliveries) => Ok(warp::reply::json(&deliveries)),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
@@ -0,0 +1,160 @@
+use crate::markdown::fenced_code_blocks;
+use crate::types::snippet::NewSnippet;
+
+/// Other names used in the info string of fenced code blocks
+const ALIASES: [(&str, &str); 12] = [
+    ("rs", "rust"),
+    ("py", "python"),
+    ("python3", "python"),
+    ("js", "javascript"),
+    ("jsx", "javascript"),
+    ("ts", "typescript"),
+    ("golang", "go"),
+    ("c++", "cpp"),
+    ("sh", "shell"),
+    ("bash", "shell"),
+    ("console", "shell"),
+    ("postgresql", "sql"),
+];
+
+/// Fragments typical of each language, matched on the lowercased code
+const SIGNATURES: [(&str, &[&str]); 9] = [
+    (
+        "rust",
+        &[
+            "fn ",
+            "let mut ",
+            "impl ",
+            "println!",
+            "use std::",
+            "&mut ",
+            "pub fn ",
+        ],
+    ),
+    (
+        "python",
+        &["def ", "elif ", "self.", "__init__", "print(", "import "],
+    ),
+    (
+        "javascript",
+        &[
+            "const ",
+            "function ",
+            "=> ",
+            "console.log",
+            "require(",
+            "document.",
+        ],
+    ),
+    ("go", &["func ", "package ", ":= ", "fmt."]),
+    (
+        "java",
+        &[
+            "public class ",
+            "system.out",
+            "public static void",
+            "import java.",
+        ],
+    ),
+    (
+        "cpp",
+        &["#include", "std::cout", "std::vector", "int main("],
+    ),
+    (
+        "sql",
+        &[
+            "select ",
+            " from ",
+            "where ",
+            "insert into ",
+            "create table ",
+        ],
+    ),
+    (
+        "shell",
+        &["$ ", "sudo ", "apt ", "cargo ", "echo ", "export "],
+    ),
+    ("html", &["<div", "<html", "<body", "</p>", "<span"]),
+];
+
+/// Lowercased language of an info string, with the aliases resolved
+pub fn normalize_language(info: &str) -> Option<String> {
+    let language = info.split_whitespace().next()?.to_lowercase();
+    match ALIASES.iter().find(|(alias, _)| *alias == language) {
+        Some((_, language)) => Some(language.to_string()),
+        None => Some(language),
+    }
+}
+
+/// Language with the most typical fragments in the code, `None` on a
+/// tie or if nothing matches
+pub fn detect_language(code: &str) -> Option<&'static str> {
+    let code = code.to_lowercase();
+    let mut scores: Vec<(&str, usize)> = SIGNATURES
+        .iter()
+        .map(|(language, fragments)| {
+            let score = fragments
+                .iter()
+                .filter(|fragment| code.contains(*fragment))
+                .count();
+            (*language, score)
+        })
+        .collect();
+    scores.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+    match scores.as_slice() {
+        [(language, best), (_, second), ..] if *best > *second => {
+            Some(language)
+        }
+        _ => None,
+    }
+}
+
+/// Snippets of the fenced code blocks of a Markdown content
+pub fn extract(content: &str) -> Vec<NewSnippet> {
+    fenced_code_blocks(content)
+        .into_iter()
+        .filter(|(_, code)| !code.trim().is_empty())
+        .map(|(info, code)| match normalize_language(&info) {
+            Some(language) => NewSnippet {
+                language: Some(language),
+                detected: false,
+                code,
+            },
+            None => NewSnippet {
+                language: detect_language(&code).map(str::to_string),
+                detected: true,
+                code,
+            },
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod snippets_tests {
+    use super::*;
+
+    #[test]
+    fn untagged_snippets_are_detected() {
+        // Arrange
+        let content = "```RS\nlet a = 1;\n```\n\n\
+            ```\nfn main() {\n    let mut a = 1;\n}\n```\n\n\
+            ```\nSELECT id FROM questions WHERE id = 1;\n```\n\n\
+            ```\nhello\n```\n\n```js\n```\n";
+        // Act
+        let snippets = extract(content);
+        // Assert
+        let languages: Vec<(Option<&str>, bool)> = snippets
+            .iter()
+            .map(|s| (s.language.as_deref(), s.detected))
+            .collect();
+        assert_eq!(
+            languages,
+            vec![
+                (Some("rust"), false),
+                (Some("rust"), true),
+                (Some("sql"), true),
+                (None, true),
+            ]
+        );
+    }
+}
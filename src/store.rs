@@ -1,11 +1,71 @@
-use crate::types::account::{Account, AccountId};
+use crate::markdown::render;
+use crate::snippets::extract;
+use crate::types::account::{Account, AccountId, AccountInfo, Author};
 use crate::types::answer::{Answer, AnswerId, NewAnswer};
-use crate::types::question::{NewQuestion, Question, QuestionId};
+use crate::types::attachment::{Attachment, NewAttachment};
+use crate::types::badge::{
+    AccountBadge, Badge, BadgeDefinition, BadgeRule,
+};
+use crate::types::bounty::{Bounty, NewBounty};
+use crate::types::mention::Mention;
+use crate::types::notification::{
+    Notification, NotificationKind, NotificationPreferences,
+};
+use crate::types::profile::{
+    Activity, ActivityKind, Profile, ProfileUpdate,
+};
+use crate::types::question::{
+    CloseReason, NewQuestion, Question, QuestionId, QuestionPatch,
+    QuestionStatus, SimilarQuestion,
+};
+use crate::types::reputation::{
+    LeaderboardEntry, Privilege, ReputationKind,
+};
+use crate::types::snippet::{Snippet, SnippetSource};
+use crate::types::tag::Tag;
+use crate::types::transfer::{
+    ExportedAnswer, ExportedQuestion, ImportedQuestion,
+};
+use crate::types::vote::{Score, VoteTarget};
+use crate::types::watch::{WatchedQuestion, WatchedTag};
+use crate::types::webhook::{
+    NewWebhook, PendingDelivery, Webhook, WebhookDelivery, WebhookId,
+};
+use crate::validation::normalize_tag;
 use handle_errors::Error;
-use sqlx::postgres::{PgPool, PgPoolOptions, PgRow};
-use sqlx::Row;
+use sqlx::postgres::{PgConnection, PgPool, PgPoolOptions, PgRow};
+use sqlx::{Acquire, Row};
+use std::collections::HashMap;
 use tracing::{event, Level};
 
+/// Columns of `Question`, selected from the questions `q` with
+/// `QUESTION_JOINS`
+const QUESTION_COLUMNS: &str = "q.id, q.title, q.content, q.content_html,
+    q.tags, q.version, q.status, q.close_reason, q.created_on,
+    q.updated_on, b.amount as bounty, ac.answer_count, s.score,
+    a.id as author_id, a.username, a.display_name, a.avatar_url";
+
+/// Author, open bounty, number of answers and score of the questions `q`
+const QUESTION_JOINS: &str = "left join accounts a on a.id = q.account_id
+    left join bounties b on b.question_id = q.id and b.ended_on is null
+    cross join lateral (
+        select count(*) as answer_count from answers
+        where corresponding_question = q.id
+    ) ac
+    cross join lateral (
+        select coalesce(sum(value), 0)::int8 as score from question_votes
+        where question_id = q.id
+    ) s";
+
+/// Columns of `Answer`, selected from the answers `an` with
+/// `ANSWER_JOINS`
+const ANSWER_COLUMNS: &str = "an.id, an.content, an.content_html,
+    an.corresponding_question, an.version, an.accepted, an.created_on,
+    a.id as author_id, a.username, a.display_name, a.avatar_url";
+
+/// Author of the answers `an`
+const ANSWER_JOINS: &str = "left join accounts a on a.id = an.account_id";
+
 #[derive(Debug, Clone)]
 pub struct Store {
     pub connection: PgPool,
@@ -30,20 +90,25 @@ impl Store {
         }
     }
 
+    /// Featured questions are the ones with an open bounty, the largest
+    /// bounty first
     pub async fn get_questions(
         &self,
         limit: Option<i32>,
         offset: i32,
+        featured: bool,
     ) -> Result<Vec<Question>, Error> {
-        match sqlx::query("select * from questions limit $1 offset $2")
+        let query = format!(
+            "select {QUESTION_COLUMNS} from questions q {QUESTION_JOINS}
+            where not $3 or b.amount is not null
+            order by case when $3 then b.amount end desc nulls last, q.id
+            limit $1 offset $2"
+        );
+        match sqlx::query(&query)
             .bind(limit)
             .bind(offset)
-            .map(|row: PgRow| Question {
-                id: QuestionId(row.get("id")),
-                title: row.get("title"),
-                content: row.get("content"),
-                tags: row.get("tags"),
-            })
+            .bind(featured)
+            .map(question_from_row)
             .fetch_all(&self.connection)
             .await
         {
@@ -60,25 +125,167 @@ impl Store {
         new_question: NewQuestion,
         account_id: AccountId,
     ) -> Result<Question, Error> {
+        let query = format!(
+            "with q as (
+                insert into questions
+                (title, content, content_html, tags, account_id)
+                values ($1, $2, $5, $3, $4)
+                returning *
+            )
+            select {QUESTION_COLUMNS} from q {QUESTION_JOINS}"
+        );
+        let mut tx = self.connection.begin().await.map_err(db_error)?;
+        let question = sqlx::query(&query)
+            .bind(new_question.title)
+            .bind(&new_question.content)
+            .bind(new_question.tags)
+            .bind(account_id.0)
+            .bind(render(&new_question.content))
+            .map(question_from_row)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        write_snippets(
+            &mut tx,
+            &SnippetSource::Question(question.id.clone()),
+            &question.content,
+        )
+        .await
+        .map_err(db_error)?;
+        tx.commit().await.map_err(db_error)?;
+        Ok(question)
+    }
+
+    /// Only updates the question if it isn't locked and its version is
+    /// one of `expected_versions`, any version is accepted if `None`.
+    /// Returns `None` if no question was updated.
+    pub async fn update_question(
+        &self,
+        question: Question,
+        question_id: i32,
+        account_id: AccountId,
+        expected_versions: Option<Vec<i32>>,
+    ) -> Result<Option<Question>, Error> {
+        let query = format!(
+            "with q as (
+                update questions
+                set title = $1, content = $2, content_html = $7, tags = $3,
+                version = version + 1, updated_on = now()
+                where id = $4 and account_id = $5
+                and status <> 'locked'
+                and ($6::int4[] is null or version = any($6))
+                returning *
+            )
+            select {QUESTION_COLUMNS} from q {QUESTION_JOINS}"
+        );
+        let mut tx = self.connection.begin().await.map_err(db_error)?;
+        let question = sqlx::query(&query)
+            .bind(question.title)
+            .bind(&question.content)
+            .bind(question.tags)
+            .bind(question_id)
+            .bind(account_id.0)
+            .bind(expected_versions)
+            .bind(render(&question.content))
+            .map(question_from_row)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        if let Some(question) = &question {
+            write_snippets(
+                &mut tx,
+                &SnippetSource::Question(question.id.clone()),
+                &question.content,
+            )
+            .await
+            .map_err(db_error)?;
+        }
+        tx.commit().await.map_err(db_error)?;
+        Ok(question)
+    }
+
+    /// Only writes the members present in the patch, with the same
+    /// checks as `update_question`.
+    /// Returns `None` if no question was updated.
+    pub async fn patch_question(
+        &self,
+        patch: QuestionPatch,
+        question_id: i32,
+        account_id: AccountId,
+        expected_versions: Option<Vec<i32>>,
+    ) -> Result<Option<Question>, Error> {
+        let query = format!(
+            "with q as (
+                update questions
+                set title = coalesce($1, title),
+                content = coalesce($2, content),
+                content_html = coalesce($8, content_html),
+                tags = case when $3 then $4 else tags end,
+                version = version + 1, updated_on = now()
+                where id = $5 and account_id = $6
+                and status <> 'locked'
+                and ($7::int4[] is null or version = any($7))
+                returning *
+            )
+            select {QUESTION_COLUMNS} from q {QUESTION_JOINS}"
+        );
+        let content_changed = matches!(patch.content, Some(Some(_)));
+        let mut tx = self.connection.begin().await.map_err(db_error)?;
+        let question = sqlx::query(&query)
+            .bind(patch.title.flatten())
+            .bind(patch.content.clone().flatten())
+            .bind(patch.tags.is_some())
+            .bind(patch.tags.flatten())
+            .bind(question_id)
+            .bind(account_id.0)
+            .bind(expected_versions)
+            .bind(patch.content.flatten().map(|content| render(&content)))
+            .map(question_from_row)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        if let Some(question) =
+            question.as_ref().filter(|_| content_changed)
+        {
+            write_snippets(
+                &mut tx,
+                &SnippetSource::Question(question.id.clone()),
+                &question.content,
+            )
+            .await
+            .map_err(db_error)?;
+        }
+        tx.commit().await.map_err(db_error)?;
+        Ok(question)
+    }
+
+    /// Same checks as `update_question`, a moderator may delete any
+    /// question, locked or not.
+    /// Returns the tags of the deleted question, `None` if no question
+    /// was deleted.
+    pub async fn delete_question(
+        &self,
+        question_id: i32,
+        account_id: AccountId,
+        moderator: bool,
+        expected_versions: Option<Vec<i32>>,
+    ) -> Result<Option<Vec<String>>, Error> {
         match sqlx::query(
-            "insert into questions (title, content, tags, account_id)
-            values ($1, $2, $3, $4)
-            returning id, title, content, tags",
+            "delete from questions where id = $1
+            and (account_id = $2 or $4)
+            and (status <> 'locked' or $4)
+            and ($3::int4[] is null or version = any($3))
+            returning coalesce(tags, '{}') as tags",
         )
-        .bind(new_question.title)
-        .bind(new_question.content)
-        .bind(new_question.tags)
+        .bind(question_id)
         .bind(account_id.0)
-        .map(|row: PgRow| Question {
-            id: QuestionId(row.get("id")),
-            title: row.get("title"),
-            content: row.get("content"),
-            tags: row.get("tags"),
-        })
-        .fetch_one(&self.connection)
+        .bind(expected_versions)
+        .bind(moderator)
+        .map(|row: PgRow| row.get("tags"))
+        .fetch_optional(&self.connection)
         .await
         {
-            Ok(question) => Ok(question),
+            Ok(tags) => Ok(tags),
             Err(e) => {
                 event!(Level::ERROR, "{:?}", e);
                 Err(Error::DatabaseQueryError(e))
@@ -86,33 +293,196 @@ impl Store {
         }
     }
 
-    pub async fn update_question(
+    pub async fn question_exists(
         &self,
-        question: Question,
         question_id: i32,
+    ) -> Result<bool, Error> {
+        match sqlx::query("select id from questions where id = $1")
+            .bind(question_id)
+            .fetch_optional(&self.connection)
+            .await
+        {
+            Ok(question) => Ok(question.is_some()),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    pub async fn add_answer(
+        &self,
+        new_answer: NewAnswer,
         account_id: AccountId,
+    ) -> Result<Answer, Error> {
+        let query = format!(
+            "with an as (
+                insert into answers
+                (content, content_html, corresponding_question, account_id)
+                values ($1, $2, $3, $4)
+                returning *
+            )
+            select {ANSWER_COLUMNS} from an {ANSWER_JOINS}"
+        );
+        let mut tx = self.connection.begin().await.map_err(db_error)?;
+        let answer = sqlx::query(&query)
+            .bind(&new_answer.content)
+            .bind(render(&new_answer.content))
+            .bind(new_answer.question_id.0)
+            .bind(account_id.0)
+            .map(answer_from_row)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        write_snippets(
+            &mut tx,
+            &SnippetSource::Answer(
+                answer.question_id.clone(),
+                answer.id.clone(),
+            ),
+            &answer.content,
+        )
+        .await
+        .map_err(db_error)?;
+        tx.commit().await.map_err(db_error)?;
+        Ok(answer)
+    }
+
+    pub async fn get_question_by_id(
+        &self,
+        question_id: i32,
     ) -> Result<Question, Error> {
+        let query = format!(
+            "select {QUESTION_COLUMNS} from questions q {QUESTION_JOINS}
+            where q.id = $1"
+        );
+        match sqlx::query(&query)
+            .bind(question_id)
+            .map(question_from_row)
+            .fetch_one(&self.connection)
+            .await
+        {
+            Ok(question) => Ok(question),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    pub async fn get_answer_by_id(
+        &self,
+        answer_id: i32,
+    ) -> Result<Answer, Error> {
+        let query = format!(
+            "select {ANSWER_COLUMNS} from answers an {ANSWER_JOINS}
+            where an.id = $1"
+        );
+        match sqlx::query(&query)
+            .bind(answer_id)
+            .map(answer_from_row)
+            .fetch_one(&self.connection)
+            .await
+        {
+            Ok(answer) => Ok(answer),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Sends a `NOTIFY` to every connection listening on `channel`
+    pub async fn notify(
+        &self,
+        channel: &str,
+        payload: &str,
+    ) -> Result<(), Error> {
+        match sqlx::query("select pg_notify($1, $2)")
+            .bind(channel)
+            .bind(payload)
+            .execute(&self.connection)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    pub async fn get_answers_by_question_id(
+        &self,
+        question_id: i32,
+    ) -> Result<Vec<Answer>, Error> {
+        let query = format!(
+            "select {ANSWER_COLUMNS} from answers an {ANSWER_JOINS}
+            where an.corresponding_question = $1 order by an.id"
+        );
+        match sqlx::query(&query)
+            .bind(question_id)
+            .map(answer_from_row)
+            .fetch_all(&self.connection)
+            .await
+        {
+            Ok(answers) => Ok(answers),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    pub async fn get_answers_by_question_ids(
+        &self,
+        question_ids: &[i32],
+    ) -> Result<Vec<Answer>, Error> {
+        let query = format!(
+            "select {ANSWER_COLUMNS} from answers an {ANSWER_JOINS}
+            where an.corresponding_question = any($1) order by an.id"
+        );
+        match sqlx::query(&query)
+            .bind(question_ids)
+            .map(answer_from_row)
+            .fetch_all(&self.connection)
+            .await
+        {
+            Ok(answers) => Ok(answers),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    pub async fn get_question_authors(
+        &self,
+        question_ids: &[i32],
+    ) -> Result<Vec<(QuestionId, AccountInfo)>, Error> {
         match sqlx::query(
-            "update questions
-            set title = $1, content = $2, tags = $3
-            where id = $4 and account_id = $5
-            returning id, title, content, tags",
+            "select questions.id as question_id, accounts.id, accounts.email,
+            accounts.username, accounts.display_name, accounts.avatar_url
+            from questions join accounts on accounts.id = questions.account_id
+            where questions.id = any($1)",
         )
-        .bind(question.title)
-        .bind(question.content)
-        .bind(question.tags)
-        .bind(question_id)
-        .bind(account_id.0)
-        .map(|row: PgRow| Question {
-            id: QuestionId(row.get("id")),
-            title: row.get("title"),
-            content: row.get("content"),
-            tags: row.get("tags"),
+        .bind(question_ids)
+        .map(|row: PgRow| {
+            (
+                QuestionId(row.get("question_id")),
+                AccountInfo {
+                    id: AccountId(row.get("id")),
+                    email: row.get("email"),
+                    username: row.get("username"),
+                    display_name: row.get("display_name"),
+                    avatar_url: row.get("avatar_url"),
+                },
+            )
         })
-        .fetch_one(&self.connection)
+        .fetch_all(&self.connection)
         .await
         {
-            Ok(question) => Ok(question),
+            Ok(authors) => Ok(authors),
             Err(e) => {
                 event!(Level::ERROR, "{:?}", e);
                 Err(Error::DatabaseQueryError(e))
@@ -120,20 +490,60 @@ impl Store {
         }
     }
 
-    pub async fn delete_question(
+    pub async fn get_answer_authors(
         &self,
-        question_id: i32,
-        account_id: AccountId,
-    ) -> Result<bool, Error> {
+        answer_ids: &[i32],
+    ) -> Result<Vec<(AnswerId, AccountInfo)>, Error> {
         match sqlx::query(
-            "delete from questions where id = $1 and account_id = $2",
+            "select answers.id as answer_id, accounts.id, accounts.email,
+            accounts.username, accounts.display_name, accounts.avatar_url
+            from answers join accounts on accounts.id = answers.account_id
+            where answers.id = any($1)",
+        )
+        .bind(answer_ids)
+        .map(|row: PgRow| {
+            (
+                AnswerId(row.get("answer_id")),
+                AccountInfo {
+                    id: AccountId(row.get("id")),
+                    email: row.get("email"),
+                    username: row.get("username"),
+                    display_name: row.get("display_name"),
+                    avatar_url: row.get("avatar_url"),
+                },
+            )
+        })
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(authors) => Ok(authors),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    pub async fn get_account_by_id(
+        &self,
+        account_id: &AccountId,
+    ) -> Result<AccountInfo, Error> {
+        match sqlx::query(
+            "select id, email, username, display_name, avatar_url
+            from accounts where id = $1",
         )
-        .bind(question_id)
         .bind(account_id.0)
-        .execute(&self.connection)
+        .map(|row: PgRow| AccountInfo {
+            id: AccountId(row.get("id")),
+            email: row.get("email"),
+            username: row.get("username"),
+            display_name: row.get("display_name"),
+            avatar_url: row.get("avatar_url"),
+        })
+        .fetch_one(&self.connection)
         .await
         {
-            Ok(_) => Ok(true),
+            Ok(account) => Ok(account),
             Err(e) => {
                 event!(Level::ERROR, "{:?}", e);
                 Err(Error::DatabaseQueryError(e))
@@ -141,28 +551,35 @@ impl Store {
         }
     }
 
-    pub async fn add_answer(
+    pub async fn get_profile(
         &self,
-        new_answer: NewAnswer,
-        account_id: AccountId,
-    ) -> Result<Answer, Error> {
+        account_id: &AccountId,
+    ) -> Result<Profile, Error> {
         match sqlx::query(
-            "insert into answers (content, corresponding_question, account_id)
-            values ($1, $2, $3)
-            returning id, content, corresponding_question",
+            "select id, username, display_name, avatar_url, created_on,
+            (select coalesce(sum(points), 0)::int8 from reputation_events r
+            where r.account_id = accounts.id) as reputation,
+            (select count(*) from questions q
+            where q.account_id = accounts.id) as questions,
+            (select count(*) from answers an
+            where an.account_id = accounts.id) as answers
+            from accounts where id = $1",
         )
-        .bind(new_answer.content)
-        .bind(new_answer.question_id.0)
         .bind(account_id.0)
-        .map(|row: PgRow| Answer {
-            id: AnswerId(row.get("id")),
-            content: row.get("content"),
-            question_id: QuestionId(row.get("corresponding_question")),
+        .map(|row: PgRow| Profile {
+            id: AccountId(row.get("id")),
+            username: row.get("username"),
+            display_name: row.get("display_name"),
+            avatar_url: row.get("avatar_url"),
+            reputation: row.get("reputation"),
+            questions: row.get("questions"),
+            answers: row.get("answers"),
+            created_on: row.get("created_on"),
         })
         .fetch_one(&self.connection)
         .await
         {
-            Ok(answer) => Ok(answer),
+            Ok(profile) => Ok(profile),
             Err(e) => {
                 event!(Level::ERROR, "{:?}", e);
                 Err(Error::DatabaseQueryError(e))
@@ -170,22 +587,52 @@ impl Store {
         }
     }
 
-    pub async fn get_question_by_id(
+    pub async fn update_profile(
         &self,
-        question_id: i32,
-    ) -> Result<Question, Error> {
-        match sqlx::query("select * from questions where id = $1")
-            .bind(question_id)
-            .map(|row: PgRow| Question {
-                id: QuestionId(row.get("id")),
-                title: row.get("title"),
-                content: row.get("content"),
-                tags: row.get("tags"),
-            })
-            .fetch_one(&self.connection)
+        account_id: &AccountId,
+        update: &ProfileUpdate,
+    ) -> Result<Profile, Error> {
+        match sqlx::query(
+            "update accounts
+            set username = $2, display_name = $3, avatar_url = $4
+            where id = $1",
+        )
+        .bind(account_id.0)
+        .bind(&update.username)
+        .bind(update.display_name.as_deref().map(str::trim))
+        .bind(&update.avatar_url)
+        .execute(&self.connection)
+        .await
+        {
+            Ok(_) => self.get_profile(account_id).await,
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Questions of an account, latest first
+    pub async fn get_questions_by_account(
+        &self,
+        account_id: &AccountId,
+        limit: Option<i32>,
+        offset: i32,
+    ) -> Result<Vec<Question>, Error> {
+        let query = format!(
+            "select {QUESTION_COLUMNS} from questions q {QUESTION_JOINS}
+            where q.account_id = $1
+            order by q.id desc limit $2 offset $3"
+        );
+        match sqlx::query(&query)
+            .bind(account_id.0)
+            .bind(limit)
+            .bind(offset)
+            .map(question_from_row)
+            .fetch_all(&self.connection)
             .await
         {
-            Ok(question) => Ok(question),
+            Ok(questions) => Ok(questions),
             Err(e) => {
                 event!(Level::ERROR, "{:?}", e);
                 Err(Error::DatabaseQueryError(e))
@@ -193,23 +640,74 @@ impl Store {
         }
     }
 
-    pub async fn get_answers_by_question_id(
+    /// Answers of an account, latest first
+    pub async fn get_answers_by_account(
         &self,
-        question_id: i32,
+        account_id: &AccountId,
+        limit: Option<i32>,
+        offset: i32,
     ) -> Result<Vec<Answer>, Error> {
+        let query = format!(
+            "select {ANSWER_COLUMNS} from answers an {ANSWER_JOINS}
+            where an.account_id = $1
+            order by an.id desc limit $2 offset $3"
+        );
+        match sqlx::query(&query)
+            .bind(account_id.0)
+            .bind(limit)
+            .bind(offset)
+            .map(answer_from_row)
+            .fetch_all(&self.connection)
+            .await
+        {
+            Ok(answers) => Ok(answers),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Questions asked and answers given by an account, latest first
+    pub async fn get_activity(
+        &self,
+        account_id: &AccountId,
+        limit: Option<i32>,
+        offset: i32,
+    ) -> Result<Vec<Activity>, Error> {
         match sqlx::query(
-            "select * from answers where corresponding_question = $1",
+            "select * from (
+                select 'asked' as kind, id as question_id,
+                null::int4 as answer_id, title, created_on
+                from questions where account_id = $1
+                union all
+                select 'answered', q.id, an.id, q.title, an.created_on
+                from answers an
+                join questions q on q.id = an.corresponding_question
+                where an.account_id = $1
+            ) activity
+            order by created_on desc, answer_id desc nulls last
+            limit $2 offset $3",
         )
-        .bind(question_id)
-        .map(|row: PgRow| Answer {
-            id: AnswerId(row.get("id")),
-            content: row.get("content"),
-            question_id: QuestionId(row.get("corresponding_question")),
+        .bind(account_id.0)
+        .bind(limit)
+        .bind(offset)
+        .map(|row: PgRow| Activity {
+            kind: match row.get("kind") {
+                "asked" => ActivityKind::Asked,
+                _ => ActivityKind::Answered,
+            },
+            question_id: QuestionId(row.get("question_id")),
+            answer_id: row
+                .get::<Option<i32>, _>("answer_id")
+                .map(AnswerId),
+            title: row.get("title"),
+            created_on: row.get("created_on"),
         })
         .fetch_all(&self.connection)
         .await
         {
-            Ok(answers) => Ok(answers),
+            Ok(activity) => Ok(activity),
             Err(e) => {
                 event!(Level::ERROR, "{:?}", e);
                 Err(Error::DatabaseQueryError(e))
@@ -220,34 +718,35 @@ impl Store {
     pub async fn add_account(
         &self,
         account: Account,
-    ) -> Result<bool, Error> {
+    ) -> Result<Account, Error> {
         match sqlx::query(
-            "insert into accounts (email, password)
-            values ($1, $2)",
+            "insert into accounts (email, password, username)
+            values ($1, $2, $3)
+            returning id, email, password, username",
         )
         .bind(account.email)
         .bind(account.password)
-        .execute(&self.connection)
+        .bind(account.username)
+        .map(|row: PgRow| Account {
+            id: Some(AccountId(row.get("id"))),
+            email: row.get("email"),
+            password: row.get("password"),
+            username: row.get("username"),
+        })
+        .fetch_one(&self.connection)
         .await
         {
-            Ok(_) => Ok(true),
+            Ok(account) => Ok(account),
             Err(e) => {
-                event!(
-                    Level::ERROR,
-                    code = e
-                        .as_database_error()
-                        .unwrap()
-                        .code()
-                        .unwrap()
-                        .parse::<i32>()
-                        .unwrap(),
-                    db_message = e.as_database_error().unwrap().message(),
-                    constraint = e
-                        .as_database_error()
-                        .unwrap()
-                        .constraint()
-                        .unwrap()
-                );
+                match e.as_database_error() {
+                    Some(db_error) => event!(
+                        Level::ERROR,
+                        code = ?db_error.code(),
+                        db_message = db_error.message(),
+                        constraint = ?db_error.constraint()
+                    ),
+                    None => event!(Level::ERROR, "{:?}", e),
+                }
                 Err(Error::DatabaseQueryError(e))
             }
         }
@@ -257,15 +756,19 @@ impl Store {
         &self,
         email: String,
     ) -> Result<Account, Error> {
-        match sqlx::query("select * from accounts where email = $1")
-            .bind(email)
-            .map(|row: PgRow| Account {
-                id: Some(AccountId(row.get("id"))),
-                email: row.get("email"),
-                password: row.get("password"),
-            })
-            .fetch_one(&self.connection)
-            .await
+        match sqlx::query(
+            "select id, email, password, username from accounts
+            where email = $1",
+        )
+        .bind(email)
+        .map(|row: PgRow| Account {
+            id: Some(AccountId(row.get("id"))),
+            email: row.get("email"),
+            password: row.get("password"),
+            username: row.get("username"),
+        })
+        .fetch_one(&self.connection)
+        .await
         {
             Ok(account) => Ok(account),
             Err(e) => {
@@ -281,7 +784,7 @@ impl Store {
         account_id: &AccountId,
     ) -> Result<bool, Error> {
         match sqlx::query(
-            "select * from questions where id = $1 and account_id = $2",
+            "select id from questions where id = $1 and account_id = $2",
         )
         .bind(question_id)
         .bind(account_id.0)
@@ -295,4 +798,2410 @@ impl Store {
             }
         }
     }
+
+    pub async fn add_webhook(
+        &self,
+        webhook: NewWebhook,
+        account_id: &AccountId,
+    ) -> Result<Webhook, Error> {
+        // Compared with the normalized tags of the questions
+        let tags = self.normalize_tags(webhook.tags).await?;
+        match sqlx::query(
+            "insert into webhooks (account_id, url, event_types, tags, secret)
+            values ($1, $2, $3, $4, $5)
+            returning id, url, event_types, tags",
+        )
+        .bind(account_id.0)
+        .bind(webhook.url)
+        .bind(webhook.event_types)
+        .bind(tags)
+        .bind(webhook.secret)
+        .map(|row: PgRow| Webhook {
+            id: WebhookId(row.get("id")),
+            url: row.get("url"),
+            event_types: row.get("event_types"),
+            tags: row.get("tags"),
+        })
+        .fetch_one(&self.connection)
+        .await
+        {
+            Ok(webhook) => Ok(webhook),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    pub async fn get_webhooks(
+        &self,
+        account_id: &AccountId,
+    ) -> Result<Vec<Webhook>, Error> {
+        match sqlx::query(
+            "select id, url, event_types, tags from webhooks
+            where account_id = $1 order by id",
+        )
+        .bind(account_id.0)
+        .map(|row: PgRow| Webhook {
+            id: WebhookId(row.get("id")),
+            url: row.get("url"),
+            event_types: row.get("event_types"),
+            tags: row.get("tags"),
+        })
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(webhooks) => Ok(webhooks),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Fails with `RowNotFound` if the webhook belongs to another account
+    pub async fn get_webhook(
+        &self,
+        webhook_id: i32,
+        account_id: &AccountId,
+    ) -> Result<Webhook, Error> {
+        match sqlx::query(
+            "select id, url, event_types, tags from webhooks
+            where id = $1 and account_id = $2",
+        )
+        .bind(webhook_id)
+        .bind(account_id.0)
+        .map(|row: PgRow| Webhook {
+            id: WebhookId(row.get("id")),
+            url: row.get("url"),
+            event_types: row.get("event_types"),
+            tags: row.get("tags"),
+        })
+        .fetch_one(&self.connection)
+        .await
+        {
+            Ok(webhook) => Ok(webhook),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    pub async fn delete_webhook(
+        &self,
+        webhook_id: i32,
+        account_id: &AccountId,
+    ) -> Result<bool, Error> {
+        match sqlx::query(
+            "delete from webhooks where id = $1 and account_id = $2",
+        )
+        .bind(webhook_id)
+        .bind(account_id.0)
+        .execute(&self.connection)
+        .await
+        {
+            Ok(res) => Ok(res.rows_affected() > 0),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    pub async fn get_webhook_deliveries(
+        &self,
+        webhook_id: i32,
+        limit: Option<i32>,
+        offset: i32,
+    ) -> Result<Vec<WebhookDelivery>, Error> {
+        match sqlx::query(
+            "select id, event_type, status, attempts, last_status_code,
+            last_error, created_on, delivered_on,
+            case when status = 'pending' then next_attempt_on
+            end as next_attempt_on
+            from webhook_deliveries where webhook_id = $1
+            order by id desc limit $2 offset $3",
+        )
+        .bind(webhook_id)
+        .bind(limit)
+        .bind(offset)
+        .map(|row: PgRow| WebhookDelivery {
+            id: row.get("id"),
+            event_type: row.get("event_type"),
+            status: row.get("status"),
+            attempts: row.get("attempts"),
+            last_status_code: row.get("last_status_code"),
+            last_error: row.get("last_error"),
+            created_on: row.get("created_on"),
+            next_attempt_on: row.get("next_attempt_on"),
+            delivered_on: row.get("delivered_on"),
+        })
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(deliveries) => Ok(deliveries),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Queues a delivery for every webhook subscribed to the event type
+    /// whose tag filter matches the tags of the question
+    pub async fn enqueue_webhook_deliveries(
+        &self,
+        event_type: &str,
+        payload: &str,
+        question_id: i32,
+        tags: Option<Vec<String>>,
+    ) -> Result<u64, Error> {
+        match sqlx::query(
+            "insert into webhook_deliveries (webhook_id, event_type, payload)
+            select id, $1, $2 from webhooks
+            where $1 = any(event_types)
+            and (coalesce(cardinality(tags), 0) = 0 or tags && coalesce(
+                $4, (select q.tags from questions q where q.id = $3), '{}'
+            ))",
+        )
+        .bind(event_type)
+        .bind(payload)
+        .bind(question_id)
+        .bind(tags)
+        .execute(&self.connection)
+        .await
+        {
+            Ok(res) => Ok(res.rows_affected()),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Takes up to `limit` due deliveries and counts the attempt.
+    /// They are leased for `lease_secs`, so the deliveries of a worker
+    /// that stopped halfway are picked up again afterwards.
+    pub async fn claim_webhook_deliveries(
+        &self,
+        limit: i64,
+        lease_secs: f64,
+    ) -> Result<Vec<PendingDelivery>, Error> {
+        match sqlx::query(
+            "update webhook_deliveries d
+            set attempts = d.attempts + 1,
+            next_attempt_on = now() + make_interval(secs => $2)
+            from webhooks w
+            where w.id = d.webhook_id and d.id in (
+                select id from webhook_deliveries
+                where status = 'pending' and next_attempt_on <= now()
+                order by next_attempt_on limit $1
+                for update skip locked
+            )
+            returning d.id, w.url, w.secret, d.event_type, d.payload,
+            d.attempts",
+        )
+        .bind(limit)
+        .bind(lease_secs)
+        .map(|row: PgRow| PendingDelivery {
+            id: row.get("id"),
+            url: row.get("url"),
+            secret: row.get("secret"),
+            event_type: row.get("event_type"),
+            payload: row.get("payload"),
+            attempts: row.get("attempts"),
+        })
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(deliveries) => Ok(deliveries),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Records the outcome of an attempt. A failed delivery is retried
+    /// after `retry_in_secs`, or marked as `failed` if it is `None`.
+    pub async fn finish_webhook_delivery(
+        &self,
+        delivery_id: i32,
+        status_code: Option<i32>,
+        error: Option<String>,
+        retry_in_secs: Option<f64>,
+    ) -> Result<(), Error> {
+        match sqlx::query(
+            "update webhook_deliveries set
+            status = case when $3::text is null then 'delivered'
+                when $4::float8 is null then 'failed'
+                else 'pending' end,
+            delivered_on = case when $3::text is null then now() end,
+            next_attempt_on = now() + make_interval(secs => coalesce($4, 0)),
+            last_status_code = $2,
+            last_error = $3
+            where id = $1",
+        )
+        .bind(delivery_id)
+        .bind(status_code)
+        .bind(error)
+        .bind(retry_in_secs)
+        .execute(&self.connection)
+        .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    pub async fn get_question_owner(
+        &self,
+        question_id: i32,
+    ) -> Result<AccountId, Error> {
+        match sqlx::query("select account_id from questions where id = $1")
+            .bind(question_id)
+            .map(|row: PgRow| AccountId(row.get("account_id")))
+            .fetch_one(&self.connection)
+            .await
+        {
+            Ok(account_id) => Ok(account_id),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Notifies every recipient, except the actor itself and the
+    /// accounts which turned this kind of notification off
+    pub async fn add_notifications(
+        &self,
+        kind: NotificationKind,
+        recipients: &[i32],
+        question_id: Option<i32>,
+        answer_id: Option<i32>,
+        actor_id: &AccountId,
+    ) -> Result<u64, Error> {
+        match sqlx::query(
+            "insert into notifications
+            (account_id, kind, question_id, answer_id, actor_id)
+            select distinct r.id, $2, $3, $4, $5
+            from unnest($1::int4[]) as r(id)
+            left join notification_preferences p on p.account_id = r.id
+            where r.id <> $5 and coalesce(case $2
+                when 'answer' then p.answers
+                when 'accepted_answer' then p.accepted_answers
+                when 'mention' then p.mentions
+                when 'watched_question' then p.watches
+                when 'watched_tag' then p.watches
+            end, true)",
+        )
+        .bind(recipients)
+        .bind(kind)
+        .bind(question_id)
+        .bind(answer_id)
+        .bind(actor_id.0)
+        .execute(&self.connection)
+        .await
+        {
+            Ok(res) => Ok(res.rows_affected()),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    pub async fn get_notifications(
+        &self,
+        account_id: &AccountId,
+        unread_only: bool,
+        limit: Option<i32>,
+        offset: i32,
+    ) -> Result<Vec<Notification>, Error> {
+        match sqlx::query(
+            "select id, kind, question_id, answer_id, actor_id,
+            read_on is not null as read, created_on
+            from notifications
+            where account_id = $1 and (not $2 or read_on is null)
+            order by id desc limit $3 offset $4",
+        )
+        .bind(account_id.0)
+        .bind(unread_only)
+        .bind(limit)
+        .bind(offset)
+        .map(|row: PgRow| Notification {
+            id: row.get("id"),
+            kind: row.get("kind"),
+            question_id: row
+                .get::<Option<i32>, _>("question_id")
+                .map(QuestionId),
+            answer_id: row
+                .get::<Option<i32>, _>("answer_id")
+                .map(AnswerId),
+            actor_id: row.get::<Option<i32>, _>("actor_id").map(AccountId),
+            read: row.get("read"),
+            created_on: row.get("created_on"),
+        })
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(notifications) => Ok(notifications),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Records the mentions of the accounts with one of the usernames,
+    /// except the author. Returns the accounts which weren't already
+    /// mentioned in the post.
+    pub async fn add_mentions(
+        &self,
+        usernames: &[String],
+        question_id: i32,
+        answer_id: Option<i32>,
+        actor_id: &AccountId,
+    ) -> Result<Vec<i32>, Error> {
+        match sqlx::query(
+            "insert into mentions (account_id, actor_id, question_id,
+            answer_id)
+            select id, $2, $3, $4 from accounts
+            where lower(username) = any($1) and id <> $2
+            on conflict do nothing
+            returning account_id",
+        )
+        .bind(usernames)
+        .bind(actor_id.0)
+        .bind(question_id)
+        .bind(answer_id)
+        .map(|row: PgRow| row.get("account_id"))
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(mentioned) => Ok(mentioned),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    pub async fn get_mentions(
+        &self,
+        account_id: &AccountId,
+        limit: Option<i32>,
+        offset: i32,
+    ) -> Result<Vec<Mention>, Error> {
+        match sqlx::query(
+            "select m.id, m.question_id, m.answer_id, q.title,
+            m.actor_id, m.created_on
+            from mentions m
+            join questions q on q.id = m.question_id
+            where m.account_id = $1
+            order by m.id desc limit $2 offset $3",
+        )
+        .bind(account_id.0)
+        .bind(limit)
+        .bind(offset)
+        .map(|row: PgRow| Mention {
+            id: row.get("id"),
+            question_id: QuestionId(row.get("question_id")),
+            answer_id: row
+                .get::<Option<i32>, _>("answer_id")
+                .map(AnswerId),
+            title: row.get("title"),
+            actor_id: AccountId(row.get("actor_id")),
+            created_on: row.get("created_on"),
+        })
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(mentions) => Ok(mentions),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    pub async fn count_unread_notifications(
+        &self,
+        account_id: &AccountId,
+    ) -> Result<i64, Error> {
+        match sqlx::query(
+            "select count(*) from notifications
+            where account_id = $1 and read_on is null",
+        )
+        .bind(account_id.0)
+        .map(|row: PgRow| row.get(0))
+        .fetch_one(&self.connection)
+        .await
+        {
+            Ok(count) => Ok(count),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Returns `false` if the notification belongs to another account
+    pub async fn mark_notification_read(
+        &self,
+        notification_id: i32,
+        account_id: &AccountId,
+    ) -> Result<bool, Error> {
+        match sqlx::query(
+            "update notifications set read_on = coalesce(read_on, now())
+            where id = $1 and account_id = $2",
+        )
+        .bind(notification_id)
+        .bind(account_id.0)
+        .execute(&self.connection)
+        .await
+        {
+            Ok(res) => Ok(res.rows_affected() > 0),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    pub async fn mark_all_notifications_read(
+        &self,
+        account_id: &AccountId,
+    ) -> Result<u64, Error> {
+        match sqlx::query(
+            "update notifications set read_on = now()
+            where account_id = $1 and read_on is null",
+        )
+        .bind(account_id.0)
+        .execute(&self.connection)
+        .await
+        {
+            Ok(res) => Ok(res.rows_affected()),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    pub async fn get_notification_preferences(
+        &self,
+        account_id: &AccountId,
+    ) -> Result<NotificationPreferences, Error> {
+        match sqlx::query(
+            "select answers, accepted_answers, mentions, watches
+            from notification_preferences where account_id = $1",
+        )
+        .bind(account_id.0)
+        .map(|row: PgRow| NotificationPreferences {
+            answers: row.get("answers"),
+            accepted_answers: row.get("accepted_answers"),
+            mentions: row.get("mentions"),
+            watches: row.get("watches"),
+        })
+        .fetch_optional(&self.connection)
+        .await
+        {
+            Ok(preferences) => Ok(preferences.unwrap_or_default()),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    pub async fn set_notification_preferences(
+        &self,
+        account_id: &AccountId,
+        preferences: NotificationPreferences,
+    ) -> Result<NotificationPreferences, Error> {
+        match sqlx::query(
+            "insert into notification_preferences
+            (account_id, answers, accepted_answers, mentions, watches)
+            values ($1, $2, $3, $4, $5)
+            on conflict (account_id) do update set
+            answers = excluded.answers,
+            accepted_answers = excluded.accepted_answers,
+            mentions = excluded.mentions,
+            watches = excluded.watches
+            returning answers, accepted_answers, mentions, watches",
+        )
+        .bind(account_id.0)
+        .bind(preferences.answers)
+        .bind(preferences.accepted_answers)
+        .bind(preferences.mentions)
+        .bind(preferences.watches)
+        .map(|row: PgRow| NotificationPreferences {
+            answers: row.get("answers"),
+            accepted_answers: row.get("accepted_answers"),
+            mentions: row.get("mentions"),
+            watches: row.get("watches"),
+        })
+        .fetch_one(&self.connection)
+        .await
+        {
+            Ok(preferences) => Ok(preferences),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Watching twice is not an error
+    pub async fn watch_question(
+        &self,
+        question_id: i32,
+        account_id: &AccountId,
+    ) -> Result<(), Error> {
+        match sqlx::query(
+            "insert into question_watches (account_id, question_id)
+            values ($1, $2) on conflict do nothing",
+        )
+        .bind(account_id.0)
+        .bind(question_id)
+        .execute(&self.connection)
+        .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Returns `false` if the account wasn't watching the question
+    pub async fn unwatch_question(
+        &self,
+        question_id: i32,
+        account_id: &AccountId,
+    ) -> Result<bool, Error> {
+        match sqlx::query(
+            "delete from question_watches
+            where account_id = $1 and question_id = $2",
+        )
+        .bind(account_id.0)
+        .bind(question_id)
+        .execute(&self.connection)
+        .await
+        {
+            Ok(res) => Ok(res.rows_affected() > 0),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Watching twice is not an error
+    pub async fn watch_tag(
+        &self,
+        tag: &str,
+        account_id: &AccountId,
+    ) -> Result<(), Error> {
+        match sqlx::query(
+            "insert into tag_watches (account_id, tag)
+            values ($1, $2) on conflict do nothing",
+        )
+        .bind(account_id.0)
+        .bind(tag)
+        .execute(&self.connection)
+        .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Returns `false` if the account wasn't watching the tag
+    pub async fn unwatch_tag(
+        &self,
+        tag: &str,
+        account_id: &AccountId,
+    ) -> Result<bool, Error> {
+        match sqlx::query(
+            "delete from tag_watches where account_id = $1 and tag = $2",
+        )
+        .bind(account_id.0)
+        .bind(tag)
+        .execute(&self.connection)
+        .await
+        {
+            Ok(res) => Ok(res.rows_affected() > 0),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    pub async fn get_watched_questions(
+        &self,
+        account_id: &AccountId,
+    ) -> Result<Vec<WatchedQuestion>, Error> {
+        match sqlx::query(
+            "select w.question_id, q.title, w.created_on
+            from question_watches w
+            join questions q on q.id = w.question_id
+            where w.account_id = $1 order by w.created_on desc",
+        )
+        .bind(account_id.0)
+        .map(|row: PgRow| WatchedQuestion {
+            question_id: QuestionId(row.get("question_id")),
+            title: row.get("title"),
+            watched_on: row.get("created_on"),
+        })
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(questions) => Ok(questions),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    pub async fn get_watched_tags(
+        &self,
+        account_id: &AccountId,
+    ) -> Result<Vec<WatchedTag>, Error> {
+        match sqlx::query(
+            "select tag, created_on from tag_watches
+            where account_id = $1 order by tag",
+        )
+        .bind(account_id.0)
+        .map(|row: PgRow| WatchedTag {
+            tag: row.get("tag"),
+            watched_on: row.get("created_on"),
+        })
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(tags) => Ok(tags),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    pub async fn get_question_watchers(
+        &self,
+        question_id: i32,
+    ) -> Result<Vec<i32>, Error> {
+        match sqlx::query(
+            "select account_id from question_watches where question_id = $1",
+        )
+        .bind(question_id)
+        .map(|row: PgRow| row.get("account_id"))
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(watchers) => Ok(watchers),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Accounts watching at least one of the tags
+    pub async fn get_tag_watchers(
+        &self,
+        tags: &[String],
+    ) -> Result<Vec<i32>, Error> {
+        match sqlx::query(
+            "select distinct account_id from tag_watches
+            where tag = any($1)",
+        )
+        .bind(tags)
+        .map(|row: PgRow| row.get("account_id"))
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(watchers) => Ok(watchers),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Turns the tags of a question into the slugs of their tags,
+    /// replacing synonyms and creating the tags used for the first time.
+    /// The order is kept and duplicates are removed.
+    pub async fn normalize_tags(
+        &self,
+        tags: Option<Vec<String>>,
+    ) -> Result<Option<Vec<String>>, Error> {
+        let slugs: Vec<String> = match tags {
+            Some(tags) => {
+                tags.iter().map(|tag| normalize_tag(tag)).collect()
+            }
+            None => return Ok(None),
+        };
+        match sqlx::query(
+            "with resolved as (
+                select coalesce(t.slug, i.slug) as slug, min(i.ord) as ord
+                from unnest($1::text[]) with ordinality as i(slug, ord)
+                left join tag_synonyms s on s.synonym = i.slug
+                left join tags t on t.id = s.tag_id
+                where i.slug <> ''
+                group by 1
+            ), inserted as (
+                insert into tags (slug) select slug from resolved
+                on conflict (slug) do nothing
+            )
+            select slug from resolved order by ord",
+        )
+        .bind(slugs)
+        .map(|row: PgRow| row.get("slug"))
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(tags) => Ok(Some(tags)),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Slug of the tag a synonym stands for, or the slug itself
+    pub async fn canonical_tag(
+        &self,
+        slug: &str,
+    ) -> Result<String, Error> {
+        match sqlx::query(
+            "select coalesce((
+                select t.slug from tag_synonyms s
+                join tags t on t.id = s.tag_id where s.synonym = $1
+            ), $1) as slug",
+        )
+        .bind(slug)
+        .map(|row: PgRow| row.get("slug"))
+        .fetch_one(&self.connection)
+        .await
+        {
+            Ok(slug) => Ok(slug),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Most used tags first
+    pub async fn get_tags(
+        &self,
+        slug: Option<&str>,
+        limit: Option<i32>,
+        offset: i32,
+    ) -> Result<Vec<Tag>, Error> {
+        match sqlx::query(
+            "select t.slug, t.description,
+            array(
+                select s.synonym::text from tag_synonyms s
+                where s.tag_id = t.id order by s.synonym
+            ) as synonyms,
+            (
+                select count(*) from questions q
+                where q.tags @> array[t.slug::text]
+            ) as question_count
+            from tags t
+            where $1::text is null or t.slug = $1
+            order by question_count desc, t.slug
+            limit $2 offset $3",
+        )
+        .bind(slug)
+        .bind(limit)
+        .bind(offset)
+        .map(|row: PgRow| Tag {
+            slug: row.get("slug"),
+            description: row.get("description"),
+            synonyms: row.get("synonyms"),
+            question_count: row.get("question_count"),
+        })
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(tags) => Ok(tags),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    pub async fn get_questions_by_tag(
+        &self,
+        slug: &str,
+        limit: Option<i32>,
+        offset: i32,
+    ) -> Result<Vec<Question>, Error> {
+        let query = format!(
+            "select {QUESTION_COLUMNS} from questions q {QUESTION_JOINS}
+            where q.tags @> array[$1::text]
+            order by q.id desc limit $2 offset $3"
+        );
+        match sqlx::query(&query)
+            .bind(slug)
+            .bind(limit)
+            .bind(offset)
+            .map(question_from_row)
+            .fetch_all(&self.connection)
+            .await
+        {
+            Ok(questions) => Ok(questions),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Creates or updates a tag and replaces its synonyms. Tags listed
+    /// as synonyms are merged into it: their questions, watchers and
+    /// webhooks move over and they are deleted.
+    pub async fn update_tag(
+        &self,
+        slug: &str,
+        description: Option<String>,
+        synonyms: Vec<String>,
+    ) -> Result<(), Error> {
+        let mut tx = self.connection.begin().await.map_err(db_error)?;
+        let tag_id: i32 = sqlx::query(
+            "insert into tags (slug, description) values ($1, $2)
+            on conflict (slug) do update set description = $2
+            returning id",
+        )
+        .bind(slug)
+        .bind(description)
+        .map(|row: PgRow| row.get("id"))
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db_error)?;
+
+        let statements = [
+            "delete from tag_synonyms where tag_id = $1",
+            "insert into tag_synonyms (synonym, tag_id)
+            select unnest($2::text[]), $1
+            on conflict (synonym) do update set tag_id = $1",
+            "update tag_synonyms set tag_id = $1 where tag_id in (
+                select id from tags where slug = any($2)
+            )",
+            "update questions set tags = array(
+                select slug from (
+                    select case when t = any($2) then $3 else t end as slug,
+                    min(ord) as ord
+                    from unnest(tags) with ordinality as u(t, ord)
+                    group by 1
+                ) merged order by ord
+            ) where tags && $2",
+            "update webhooks set tags = array(
+                select slug from (
+                    select case when t = any($2) then $3 else t end as slug,
+                    min(ord) as ord
+                    from unnest(tags) with ordinality as u(t, ord)
+                    group by 1
+                ) merged order by ord
+            ) where tags && $2",
+            "insert into tag_watches (account_id, tag, created_on)
+            select account_id, $3, min(created_on) from tag_watches
+            where tag = any($2) group by account_id
+            on conflict do nothing",
+            "delete from tag_watches where tag = any($2)",
+            "delete from tags where slug = any($2)",
+        ];
+        for statement in statements {
+            sqlx::query(statement)
+                .bind(tag_id)
+                .bind(&synonyms)
+                .bind(slug)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?;
+        }
+
+        tx.commit().await.map_err(db_error)
+    }
+
+    pub async fn get_answer_owner(
+        &self,
+        answer_id: i32,
+    ) -> Result<(AccountId, QuestionId), Error> {
+        match sqlx::query(
+            "select account_id, corresponding_question from answers
+            where id = $1",
+        )
+        .bind(answer_id)
+        .map(|row: PgRow| {
+            (
+                AccountId(row.get("account_id")),
+                QuestionId(row.get("corresponding_question")),
+            )
+        })
+        .fetch_one(&self.connection)
+        .await
+        {
+            Ok(owner) => Ok(owner),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Casts, changes or withdraws (`None`) the vote of an account on a
+    /// post, together with the reputation it gives its owner
+    pub async fn set_vote(
+        &self,
+        target: VoteTarget,
+        post_id: i32,
+        owner: &AccountId,
+        voter: &AccountId,
+        value: Option<i16>,
+    ) -> Result<Score, Error> {
+        let (table, column) = (target.table(), target.column());
+        let mut tx = self.connection.begin().await.map_err(db_error)?;
+
+        match value {
+            Some(value) => {
+                sqlx::query(&format!(
+                    "insert into {table} (account_id, {column}, value)
+                    values ($1, $2, $3)
+                    on conflict (account_id, {column})
+                    do update set value = $3, created_on = now()"
+                ))
+                .bind(voter.0)
+                .bind(post_id)
+                .bind(value)
+                .execute(&mut *tx)
+                .await
+            }
+            None => {
+                sqlx::query(&format!(
+                    "delete from {table}
+                    where account_id = $1 and {column} = $2"
+                ))
+                .bind(voter.0)
+                .bind(post_id)
+                .execute(&mut *tx)
+                .await
+            }
+        }
+        .map_err(db_error)?;
+
+        sqlx::query(&format!(
+            "delete from reputation_events
+            where actor_id = $1 and {column} = $2
+            and kind::text in ($3, $4)"
+        ))
+        .bind(voter.0)
+        .bind(post_id)
+        .bind(target.reputation_kind(1).as_str())
+        .bind(target.reputation_kind(-1).as_str())
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+
+        if let Some(value) = value {
+            let kind = target.reputation_kind(value);
+            sqlx::query(&format!(
+                "insert into reputation_events
+                (account_id, kind, points, actor_id, {column}, post_id)
+                values ($1, $2, $3, $4, $5, $5)"
+            ))
+            .bind(owner.0)
+            .bind(kind)
+            .bind(kind.points())
+            .bind(voter.0)
+            .bind(post_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        }
+
+        let score = sqlx::query(&format!(
+            "select coalesce(sum(value), 0)::int8 as score
+            from {table} where {column} = $1"
+        ))
+        .bind(post_id)
+        .map(|row: PgRow| Score {
+            score: row.get("score"),
+            vote: value.unwrap_or(0),
+        })
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db_error)?;
+
+        tx.commit().await.map_err(db_error)?;
+        Ok(score)
+    }
+
+    /// Marks the answer as the accepted one of its question, replacing
+    /// the previously accepted answer and the reputation it gave
+    pub async fn accept_answer(
+        &self,
+        answer_id: i32,
+        question_id: i32,
+        author: &AccountId,
+        accepter: &AccountId,
+    ) -> Result<(), Error> {
+        let mut tx = self.connection.begin().await.map_err(db_error)?;
+        sqlx::query(
+            "update answers set accepted = false
+            where corresponding_question = $1 and accepted and id <> $2",
+        )
+        .bind(question_id)
+        .bind(answer_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+        sqlx::query("update answers set accepted = true where id = $1")
+            .bind(answer_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+
+        sqlx::query(
+            "delete from reputation_events
+            where kind = 'answer_accepted' and question_id = $1",
+        )
+        .bind(question_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+        // Accepting an own answer doesn't earn reputation
+        if author != accepter {
+            let kind = ReputationKind::AnswerAccepted;
+            sqlx::query(
+                "insert into reputation_events (account_id, kind, points,
+                actor_id, question_id, answer_id, post_id)
+                values ($1, $2, $3, $4, $5, $6, $6)",
+            )
+            .bind(author.0)
+            .bind(kind)
+            .bind(kind.points())
+            .bind(accepter.0)
+            .bind(question_id)
+            .bind(answer_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        }
+
+        tx.commit().await.map_err(db_error)
+    }
+
+    /// Returns `false` if the answer wasn't accepted
+    pub async fn unaccept_answer(
+        &self,
+        answer_id: i32,
+    ) -> Result<bool, Error> {
+        let mut tx = self.connection.begin().await.map_err(db_error)?;
+        let res = sqlx::query(
+            "update answers set accepted = false
+            where id = $1 and accepted",
+        )
+        .bind(answer_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+        sqlx::query(
+            "delete from reputation_events
+            where kind = 'answer_accepted' and answer_id = $1",
+        )
+        .bind(answer_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+
+        tx.commit().await.map_err(db_error)?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    pub async fn get_reputation(
+        &self,
+        account_id: &AccountId,
+    ) -> Result<i64, Error> {
+        match sqlx::query(
+            "select coalesce(sum(points), 0)::int8 as reputation
+            from reputation_events where account_id = $1",
+        )
+        .bind(account_id.0)
+        .map(|row: PgRow| row.get("reputation"))
+        .fetch_one(&self.connection)
+        .await
+        {
+            Ok(reputation) => Ok(reputation),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Accounts which earned the most reputation during the last
+    /// `window_secs` seconds, or ever if `None`
+    pub async fn get_leaderboard(
+        &self,
+        window_secs: Option<f64>,
+        limit: Option<i32>,
+        offset: i32,
+    ) -> Result<Vec<LeaderboardEntry>, Error> {
+        match sqlx::query(
+            "select account_id, sum(points)::int8 as reputation
+            from reputation_events
+            where $1::float8 is null
+            or created_on > now() - make_interval(secs => $1)
+            group by account_id
+            having sum(points) > 0
+            order by reputation desc, account_id
+            limit $2 offset $3",
+        )
+        .bind(window_secs)
+        .bind(limit)
+        .bind(offset)
+        .map(|row: PgRow| LeaderboardEntry {
+            account_id: AccountId(row.get("account_id")),
+            reputation: row.get("reputation"),
+        })
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(entries) => Ok(entries),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Renders the content of the questions and answers written before
+    /// their HTML was cached, returns the number of rendered rows
+    pub async fn render_missing_html(&self) -> Result<u64, Error> {
+        let mut rendered = 0;
+        for table in ["questions", "answers"] {
+            let rows: Vec<(i32, String)> = sqlx::query(&format!(
+                "select id, content from {table}
+                where content_html = '' and content <> ''"
+            ))
+            .map(|row: PgRow| (row.get("id"), row.get("content")))
+            .fetch_all(&self.connection)
+            .await
+            .map_err(db_error)?;
+            let (ids, html): (Vec<i32>, Vec<String>) = rows
+                .iter()
+                .map(|(id, content)| (*id, render(content)))
+                .unzip();
+
+            let res = sqlx::query(&format!(
+                "update {table} t set content_html = r.html
+                from unnest($1::int4[], $2::text[]) as r(id, html)
+                where t.id = r.id"
+            ))
+            .bind(ids)
+            .bind(html)
+            .execute(&self.connection)
+            .await
+            .map_err(db_error)?;
+            rendered += res.rows_affected();
+        }
+        Ok(rendered)
+    }
+
+    /// Extracts the snippets of the questions and answers written before
+    /// they were indexed, returns the number of indexed posts. Only the
+    /// posts with a code fence and no snippet are read.
+    pub async fn index_missing_snippets(&self) -> Result<u64, Error> {
+        let posts: Vec<(SnippetSource, String)> = sqlx::query(
+            "select q.id as question_id, null::int4 as answer_id, q.content
+            from questions q
+            where (q.content like '%```%' or q.content like '%~~~%')
+            and not exists (select 1 from snippets s
+                where s.question_id = q.id and s.answer_id is null)
+            union all
+            select a.corresponding_question, a.id, a.content
+            from answers a
+            where (a.content like '%```%' or a.content like '%~~~%')
+            and not exists (select 1 from snippets s
+                where s.answer_id = a.id)",
+        )
+        .map(|row: PgRow| {
+            let question_id = QuestionId(row.get("question_id"));
+            let source = match row.get::<Option<i32>, _>("answer_id") {
+                Some(answer_id) => {
+                    SnippetSource::Answer(question_id, AnswerId(answer_id))
+                }
+                None => SnippetSource::Question(question_id),
+            };
+            (source, row.get("content"))
+        })
+        .fetch_all(&self.connection)
+        .await
+        .map_err(db_error)?;
+
+        let mut tx = self.connection.begin().await.map_err(db_error)?;
+        for (source, content) in &posts {
+            write_snippets(&mut tx, source, content)
+                .await
+                .map_err(db_error)?;
+        }
+        tx.commit().await.map_err(db_error)?;
+        Ok(posts.len() as u64)
+    }
+
+    /// Applies the current points of every kind of event to the whole
+    /// ledger, except the bounties, returns the number of events which changed
+    pub async fn recompute_reputation(&self) -> Result<u64, Error> {
+        let (kinds, points): (Vec<&str>, Vec<i32>) = ReputationKind::ALL
+            .iter()
+            .filter_map(|kind| Some((kind.as_str(), kind.points()?)))
+            .unzip();
+        match sqlx::query(
+            "update reputation_events e set points = p.points
+            from unnest($1::text[], $2::int4[]) as p(kind, points)
+            where e.kind::text = p.kind and e.points <> p.points",
+        )
+        .bind(kinds)
+        .bind(points)
+        .execute(&self.connection)
+        .await
+        {
+            Ok(res) => Ok(res.rows_affected()),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    pub async fn get_open_bounty(
+        &self,
+        question_id: i32,
+    ) -> Result<Bounty, Error> {
+        match sqlx::query(
+            "select * from bounties
+            where question_id = $1 and ended_on is null",
+        )
+        .bind(question_id)
+        .map(|row: PgRow| Bounty {
+            id: row.get("id"),
+            question_id: QuestionId(row.get("question_id")),
+            account_id: AccountId(row.get("account_id")),
+            amount: row.get("amount"),
+            created_on: row.get("created_on"),
+            expires_on: row.get("expires_on"),
+            answer_id: row
+                .get::<Option<i32>, _>("answer_id")
+                .map(AnswerId),
+            ended_on: row.get("ended_on"),
+        })
+        .fetch_one(&self.connection)
+        .await
+        {
+            Ok(bounty) => Ok(bounty),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Opens a bounty and takes its amount from the reputation of the
+    /// owner, who needs the privilege and enough reputation left. A
+    /// question has at most one open bounty.
+    pub async fn offer_bounty(
+        &self,
+        question_id: i32,
+        owner: &AccountId,
+        new_bounty: &NewBounty,
+    ) -> Result<Bounty, Error> {
+        let mut tx = self.connection.begin().await.map_err(db_error)?;
+        // Locking the account serializes its offers, so the reputation
+        // is checked against every earlier debit
+        sqlx::query("select id from accounts where id = $1 for update")
+            .bind(owner.0)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        let reputation: i64 = sqlx::query(
+            "select coalesce(sum(points), 0)::int8 as reputation
+            from reputation_events where account_id = $1",
+        )
+        .bind(owner.0)
+        .map(|row: PgRow| row.get("reputation"))
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db_error)?;
+        Privilege::OfferBounty.check(reputation)?;
+        if reputation < i64::from(new_bounty.amount) {
+            return Err(Error::InsufficientReputation(new_bounty.amount));
+        }
+
+        let bounty = sqlx::query(
+            "insert into bounties
+            (question_id, account_id, amount, expires_on)
+            values ($1, $2, $3, now() + make_interval(days => $4))
+            returning *",
+        )
+        .bind(question_id)
+        .bind(owner.0)
+        .bind(new_bounty.amount)
+        .bind(new_bounty.days)
+        .map(|row: PgRow| Bounty {
+            id: row.get("id"),
+            question_id: QuestionId(row.get("question_id")),
+            account_id: AccountId(row.get("account_id")),
+            amount: row.get("amount"),
+            created_on: row.get("created_on"),
+            expires_on: row.get("expires_on"),
+            answer_id: None,
+            ended_on: None,
+        })
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(db_error)?;
+
+        sqlx::query(
+            "insert into reputation_events
+            (account_id, kind, points, actor_id, question_id, post_id)
+            values ($1, $2, $3, $1, $4, $4)",
+        )
+        .bind(owner.0)
+        .bind(ReputationKind::BountyOffered)
+        .bind(-bounty.amount)
+        .bind(question_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+        // The bounty is part of the question, so its ETag changes
+        sqlx::query(
+            "update questions set version = version + 1 where id = $1",
+        )
+        .bind(question_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+
+        tx.commit().await.map_err(db_error)?;
+        Ok(bounty)
+    }
+
+    /// Ends the bounty by giving its amount to the author of the answer.
+    /// Returns `None` if the bounty already ended.
+    pub async fn award_bounty(
+        &self,
+        bounty: &Bounty,
+        answer_id: i32,
+        author: &AccountId,
+    ) -> Result<Option<Bounty>, Error> {
+        let mut tx = self.connection.begin().await.map_err(db_error)?;
+        let awarded = sqlx::query(
+            "update bounties set answer_id = $2, ended_on = now()
+            where id = $1 and ended_on is null
+            returning ended_on",
+        )
+        .bind(bounty.id)
+        .bind(answer_id)
+        .map(|row: PgRow| Bounty {
+            answer_id: Some(AnswerId(answer_id)),
+            ended_on: row.get("ended_on"),
+            ..bounty.clone()
+        })
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_error)?;
+        let Some(awarded) = awarded else {
+            return Ok(None);
+        };
+
+        sqlx::query(
+            "insert into reputation_events (account_id, kind, points,
+            actor_id, question_id, answer_id, post_id)
+            values ($1, $2, $3, $4, $5, $6, $6)",
+        )
+        .bind(author.0)
+        .bind(ReputationKind::BountyAwarded)
+        .bind(bounty.amount)
+        .bind(bounty.account_id.0)
+        .bind(bounty.question_id.0)
+        .bind(answer_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+        sqlx::query(
+            "update questions set version = version + 1 where id = $1",
+        )
+        .bind(bounty.question_id.0)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+
+        tx.commit().await.map_err(db_error)?;
+        Ok(Some(awarded))
+    }
+
+    /// Ends the expired bounties, each one goes to the answer with the
+    /// highest positive score which isn't from the owner of the bounty.
+    /// Returns the number of bounties which ended.
+    pub async fn award_expired_bounties(&self) -> Result<u64, Error> {
+        match sqlx::query(
+            "with expired as (
+                select id, question_id, account_id, amount from bounties
+                where ended_on is null and expires_on <= now()
+                for update skip locked
+            ), top as (
+                select distinct on (e.id) e.id as bounty_id,
+                a.id as answer_id, a.account_id as author
+                from expired e
+                join answers a on a.corresponding_question = e.question_id
+                join answer_votes v on v.answer_id = a.id
+                where a.account_id <> e.account_id
+                group by e.id, a.id
+                having sum(v.value) > 0
+                order by e.id, sum(v.value) desc, a.id
+            ), ended as (
+                update bounties b
+                set ended_on = now(), answer_id = t.answer_id
+                from expired e left join top t on t.bounty_id = e.id
+                where b.id = e.id
+                returning b.id
+            ), awarded as (
+                insert into reputation_events (account_id, kind, points,
+                actor_id, question_id, answer_id, post_id)
+                select t.author, 'bounty_awarded', e.amount, e.account_id,
+                e.question_id, t.answer_id, t.answer_id
+                from top t join expired e on e.id = t.bounty_id
+            ), versions as (
+                update questions set version = version + 1
+                where id in (select question_id from expired)
+            )
+            select count(*) as ended from ended",
+        )
+        .map(|row: PgRow| row.get::<i64, _>("ended"))
+        .fetch_one(&self.connection)
+        .await
+        {
+            Ok(ended) => Ok(ended as u64),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Snippets of the language containing `query`, latest first
+    pub async fn search_snippets(
+        &self,
+        base_path: &str,
+        language: Option<&str>,
+        query: Option<&str>,
+        limit: Option<i32>,
+        offset: i32,
+    ) -> Result<Vec<Snippet>, Error> {
+        // Searched as a literal text, not as a `like` pattern
+        let pattern = query.map(|query| {
+            let escaped = query
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            format!("%{escaped}%")
+        });
+        match sqlx::query(
+            "select id, language, detected, code, question_id, answer_id
+            from snippets
+            where ($1::text is null or language = $1)
+            and ($2::text is null or code ilike $2)
+            order by id desc
+            limit $3 offset $4",
+        )
+        .bind(language)
+        .bind(pattern)
+        .bind(limit)
+        .bind(offset)
+        .map(|row: PgRow| {
+            let question_id: i32 = row.get("question_id");
+            let answer_id: Option<i32> = row.get("answer_id");
+            let url = match answer_id {
+                Some(_) => format!(
+                    "{}/questions/{}/answers",
+                    base_path, question_id
+                ),
+                None => format!("{}/questions/{}", base_path, question_id),
+            };
+            Snippet {
+                id: row.get("id"),
+                language: row.get("language"),
+                detected: row.get("detected"),
+                code: row.get("code"),
+                question_id: QuestionId(question_id),
+                answer_id: answer_id.map(AnswerId),
+                url,
+            }
+        })
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(snippets) => Ok(snippets),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Returns `None` if the question is missing or locked
+    pub async fn add_attachment(
+        &self,
+        attachment: &NewAttachment,
+        account_id: &AccountId,
+    ) -> Result<Option<Attachment>, Error> {
+        match sqlx::query(
+            "insert into attachments (question_id, answer_id, account_id,
+            key, file_name, content_type, size)
+            select $1, $2, $3, $4, $5, $6, $7 from questions
+            where id = $1 and status <> 'locked'
+            for share
+            returning id, question_id, answer_id, account_id, key,
+            file_name, content_type, size, created_on",
+        )
+        .bind(attachment.question_id.0)
+        .bind(attachment.answer_id.as_ref().map(|id| id.0))
+        .bind(account_id.0)
+        .bind(&attachment.key)
+        .bind(&attachment.file_name)
+        .bind(&attachment.content_type)
+        .bind(attachment.size)
+        .map(attachment_from_row)
+        .fetch_optional(&self.connection)
+        .await
+        {
+            Ok(attachment) => Ok(attachment),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Attachments of a question (`answer_id` is `None`) or of one of
+    /// its answers, oldest first
+    pub async fn get_attachments(
+        &self,
+        question_id: i32,
+        answer_id: Option<i32>,
+    ) -> Result<Vec<Attachment>, Error> {
+        match sqlx::query(
+            "select id, question_id, answer_id, account_id, key,
+            file_name, content_type, size, created_on
+            from attachments
+            where question_id = $1 and answer_id is not distinct from $2
+            order by id",
+        )
+        .bind(question_id)
+        .bind(answer_id)
+        .map(attachment_from_row)
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(attachments) => Ok(attachments),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    pub async fn get_attachment(
+        &self,
+        attachment_id: i32,
+    ) -> Result<Attachment, Error> {
+        match sqlx::query(
+            "select id, question_id, answer_id, account_id, key,
+            file_name, content_type, size, created_on
+            from attachments where id = $1",
+        )
+        .bind(attachment_id)
+        .map(attachment_from_row)
+        .fetch_one(&self.connection)
+        .await
+        {
+            Ok(attachment) => Ok(attachment),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Returns `false` if no attachment was deleted
+    pub async fn delete_attachment(
+        &self,
+        attachment_id: i32,
+        account_id: &AccountId,
+    ) -> Result<bool, Error> {
+        match sqlx::query(
+            "delete from attachments where id = $1 and account_id = $2",
+        )
+        .bind(attachment_id)
+        .bind(account_id.0)
+        .execute(&self.connection)
+        .await
+        {
+            Ok(res) => Ok(res.rows_affected() > 0),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Blob keys of the attachments of a question and of its answers
+    pub async fn get_attachment_keys(
+        &self,
+        question_id: i32,
+    ) -> Result<Vec<String>, Error> {
+        match sqlx::query(
+            "select key from attachments where question_id = $1",
+        )
+        .bind(question_id)
+        .map(|row: PgRow| row.get("key"))
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(keys) => Ok(keys),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Questions with a title similar to `title`, most similar first.
+    /// Duplicates are left out, their canonical question is listed.
+    pub async fn get_similar_questions(
+        &self,
+        title: &str,
+        limit: i32,
+    ) -> Result<Vec<SimilarQuestion>, Error> {
+        match sqlx::query(
+            "select id, title, similarity(title, $1) as similarity
+            from questions
+            where duplicate_of is null and title % $1
+            order by similarity desc, id
+            limit $2",
+        )
+        .bind(title)
+        .bind(limit)
+        .map(|row: PgRow| SimilarQuestion {
+            id: QuestionId(row.get("id")),
+            title: row.get("title"),
+            similarity: row.get("similarity"),
+        })
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(questions) => Ok(questions),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Canonical question of a duplicate, `None` if the question isn't
+    /// a duplicate
+    pub async fn get_duplicate_of(
+        &self,
+        question_id: i32,
+    ) -> Result<Option<i32>, Error> {
+        match sqlx::query(
+            "select duplicate_of from questions where id = $1",
+        )
+        .bind(question_id)
+        .map(|row: PgRow| row.get("duplicate_of"))
+        .fetch_one(&self.connection)
+        .await
+        {
+            Ok(duplicate_of) => Ok(duplicate_of),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Closes the question as a duplicate of a canonical question. The
+    /// duplicates of the question move over, so the canonical question
+    /// is always one hop away.
+    /// Returns `false` if the question is missing or locked.
+    pub async fn mark_duplicate(
+        &self,
+        question_id: i32,
+        canonical_id: i32,
+    ) -> Result<bool, Error> {
+        let mut tx = self.connection.begin().await.map_err(db_error)?;
+        let closed = sqlx::query(
+            "update questions set duplicate_of = $2, status = 'closed',
+            close_reason = 'duplicate', version = version + 1
+            where id = $1 and status <> 'locked'",
+        )
+        .bind(question_id)
+        .bind(canonical_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+        if closed.rows_affected() == 0 {
+            return Ok(false);
+        }
+        sqlx::query(
+            "update questions set duplicate_of = $2
+            where duplicate_of = $1",
+        )
+        .bind(question_id)
+        .bind(canonical_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+        tx.commit().await.map_err(db_error)?;
+        Ok(true)
+    }
+
+    pub async fn is_moderator(
+        &self,
+        account_id: &AccountId,
+    ) -> Result<bool, Error> {
+        match sqlx::query(
+            "select role in ('moderator', 'admin') as moderator
+            from accounts where id = $1",
+        )
+        .bind(account_id.0)
+        .map(|row: PgRow| row.get("moderator"))
+        .fetch_optional(&self.connection)
+        .await
+        {
+            Ok(moderator) => Ok(moderator.unwrap_or(false)),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    pub async fn get_question_status(
+        &self,
+        question_id: i32,
+    ) -> Result<QuestionStatus, Error> {
+        match sqlx::query("select status from questions where id = $1")
+            .bind(question_id)
+            .map(|row: PgRow| row.get("status"))
+            .fetch_one(&self.connection)
+            .await
+        {
+            Ok(status) => Ok(status),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Only changes the status if it is still `from`, and replaces the
+    /// duplicate closure. Returns `None` if no question was updated.
+    pub async fn set_question_status(
+        &self,
+        question_id: i32,
+        from: QuestionStatus,
+        to: QuestionStatus,
+        reason: Option<CloseReason>,
+    ) -> Result<Option<Question>, Error> {
+        let query = format!(
+            "with q as (
+                update questions
+                set status = $3, close_reason = $4, duplicate_of = null,
+                version = version + 1
+                where id = $1 and status = $2
+                returning *
+            )
+            select {QUESTION_COLUMNS} from q {QUESTION_JOINS}"
+        );
+        match sqlx::query(&query)
+            .bind(question_id)
+            .bind(from)
+            .bind(to)
+            .bind(reason)
+            .map(question_from_row)
+            .fetch_optional(&self.connection)
+            .await
+        {
+            Ok(question) => Ok(question),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Returns `false` if the question wasn't a duplicate
+    pub async fn unmark_duplicate(
+        &self,
+        question_id: i32,
+    ) -> Result<bool, Error> {
+        match sqlx::query(
+            "update questions set duplicate_of = null, status = 'open',
+            close_reason = null, version = version + 1
+            where id = $1 and duplicate_of is not null",
+        )
+        .bind(question_id)
+        .execute(&self.connection)
+        .await
+        {
+            Ok(res) => Ok(res.rows_affected() > 0),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Saves the badge definitions, returns the id and the rule of every
+    /// badge. Activity is evaluated again from the start if a rule is new
+    /// or changed.
+    pub async fn sync_badges(
+        &self,
+        definitions: &[BadgeDefinition],
+    ) -> Result<Vec<(i32, BadgeRule)>, Error> {
+        let mut tx = self.connection.begin().await.map_err(db_error)?;
+        let mut badges = Vec::new();
+        let mut changed = false;
+        for definition in definitions {
+            let rule = serde_json::to_string(&definition.rule)
+                .expect("Badge rules serialize to JSON");
+            let (id, rule_changed): (i32, bool) = sqlx::query(
+                "with previous as (
+                    select rule from badges where slug = $1
+                )
+                insert into badges (slug, name, description, rule)
+                values ($1, $2, $3, $4::jsonb)
+                on conflict (slug) do update
+                set name = $2, description = $3, rule = $4::jsonb
+                returning id, coalesce(
+                    (select rule from previous) <> $4::jsonb, true
+                ) as changed",
+            )
+            .bind(&definition.slug)
+            .bind(&definition.name)
+            .bind(&definition.description)
+            .bind(rule)
+            .map(|row: PgRow| (row.get("id"), row.get("changed")))
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(db_error)?;
+            changed |= rule_changed;
+            badges.push((id, definition.rule.clone()));
+        }
+
+        if changed {
+            sqlx::query(
+                "update badge_evaluations set evaluated_until = '-infinity'",
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        }
+        tx.commit().await.map_err(db_error)?;
+        Ok(badges)
+    }
+
+    /// Awards the badges the accounts with activity since the last
+    /// evaluation earned, returns the number of badges awarded. Does
+    /// nothing while another instance evaluates.
+    pub async fn evaluate_badges(
+        &self,
+        badges: &[(i32, BadgeRule)],
+    ) -> Result<u64, Error> {
+        let mut tx = self.connection.begin().await.map_err(db_error)?;
+        let locked = sqlx::query(
+            "select id from badge_evaluations for update skip locked",
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_error)?;
+        if locked.is_none() {
+            return Ok(0);
+        }
+
+        // The overlap catches activity committed after the previous
+        // evaluation started
+        let candidates: Vec<i32> = sqlx::query(
+            "with since as (
+                select evaluated_until - interval '5 minutes' as since
+                from badge_evaluations
+            )
+            select account_id from questions, since
+            where created_on > since
+            union select account_id from answers, since
+            where created_on > since
+            union select account_id from reputation_events, since
+            where created_on > since",
+        )
+        .map(|row: PgRow| row.get("account_id"))
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(db_error)?;
+
+        let mut awarded = 0;
+        if !candidates.is_empty() {
+            for (badge_id, rule) in badges {
+                let (qualified, threshold, tag) = badge_rule_query(rule);
+                let res = sqlx::query(&format!(
+                    "insert into account_badges (account_id, badge_id)
+                    select account_id, $4 from ({qualified}) as qualified
+                    on conflict do nothing"
+                ))
+                .bind(&candidates)
+                .bind(threshold)
+                .bind(tag)
+                .bind(badge_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?;
+                awarded += res.rows_affected();
+            }
+        }
+
+        sqlx::query(
+            "update badge_evaluations set evaluated_until = now()",
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+        tx.commit().await.map_err(db_error)?;
+        Ok(awarded)
+    }
+
+    pub async fn get_badges(&self) -> Result<Vec<Badge>, Error> {
+        match sqlx::query(
+            "select b.slug, b.name, b.description,
+            count(ab.account_id) as awarded_count
+            from badges b
+            left join account_badges ab on ab.badge_id = b.id
+            group by b.id
+            order by b.name",
+        )
+        .map(|row: PgRow| Badge {
+            slug: row.get("slug"),
+            name: row.get("name"),
+            description: row.get("description"),
+            awarded_count: row.get("awarded_count"),
+        })
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(badges) => Ok(badges),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    pub async fn get_account_badges(
+        &self,
+        account_id: &AccountId,
+    ) -> Result<Vec<AccountBadge>, Error> {
+        match sqlx::query(
+            "select b.slug, b.name, b.description, ab.awarded_on
+            from account_badges ab
+            join badges b on b.id = ab.badge_id
+            where ab.account_id = $1
+            order by ab.awarded_on desc",
+        )
+        .bind(account_id.0)
+        .map(|row: PgRow| AccountBadge {
+            slug: row.get("slug"),
+            name: row.get("name"),
+            description: row.get("description"),
+            awarded_on: row.get("awarded_on"),
+        })
+        .fetch_all(&self.connection)
+        .await
+        {
+            Ok(badges) => Ok(badges),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    pub async fn is_admin(
+        &self,
+        account_id: &AccountId,
+    ) -> Result<bool, Error> {
+        match sqlx::query(
+            "select role = 'admin' as admin from accounts where id = $1",
+        )
+        .bind(account_id.0)
+        .map(|row: PgRow| row.get("admin"))
+        .fetch_optional(&self.connection)
+        .await
+        {
+            Ok(admin) => Ok(admin.unwrap_or(false)),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// The accounts of `account_ids` which exist
+    pub async fn existing_accounts(
+        &self,
+        account_ids: &[i32],
+    ) -> Result<Vec<AccountId>, Error> {
+        match sqlx::query("select id from accounts where id = any($1)")
+            .bind(account_ids)
+            .map(|row: PgRow| AccountId(row.get("id")))
+            .fetch_all(&self.connection)
+            .await
+        {
+            Ok(accounts) => Ok(accounts),
+            Err(e) => {
+                event!(Level::ERROR, "{:?}", e);
+                Err(Error::DatabaseQueryError(e))
+            }
+        }
+    }
+
+    /// Inserts a batch of questions with their answers in a single
+    /// transaction. Every question is inserted in a savepoint, so a
+    /// failing one is reported without rolling back the others. The
+    /// tags must already be normalized.
+    pub async fn import_questions(
+        &self,
+        questions: &[ImportedQuestion],
+        account_id: &AccountId,
+    ) -> Result<Vec<Result<QuestionId, Error>>, Error> {
+        let mut tx = self.connection.begin().await.map_err(db_error)?;
+        let mut results = Vec::new();
+        for question in questions {
+            let mut savepoint = tx.begin().await.map_err(db_error)?;
+            match insert_imported(&mut savepoint, question, account_id)
+                .await
+            {
+                Ok(question_id) => {
+                    savepoint.commit().await.map_err(db_error)?;
+                    results.push(Ok(question_id));
+                }
+                Err(e) => {
+                    savepoint.rollback().await.map_err(db_error)?;
+                    results.push(Err(db_error(e)));
+                }
+            }
+        }
+        tx.commit().await.map_err(db_error)?;
+        Ok(results)
+    }
+
+    /// Up to `limit` questions with an id greater than `after`, with
+    /// their answers, read in a single transaction
+    pub async fn export_questions(
+        &self,
+        after: i32,
+        limit: i64,
+    ) -> Result<Vec<ExportedQuestion>, Error> {
+        let mut tx = self.connection.begin().await.map_err(db_error)?;
+        sqlx::query("set transaction isolation level repeatable read")
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+
+        let mut questions = sqlx::query(
+            "select id, title, content, tags, account_id, status,
+            close_reason, created_on, updated_on
+            from questions where id > $1 order by id limit $2",
+        )
+        .bind(after)
+        .bind(limit)
+        .map(|row: PgRow| ExportedQuestion {
+            id: QuestionId(row.get("id")),
+            title: row.get("title"),
+            content: row.get("content"),
+            tags: row.get("tags"),
+            account_id: AccountId(row.get("account_id")),
+            status: row.get("status"),
+            close_reason: row.get("close_reason"),
+            created_on: row.get("created_on"),
+            updated_on: row.get("updated_on"),
+            answers: Vec::new(),
+        })
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(db_error)?;
+
+        let question_ids: Vec<i32> =
+            questions.iter().map(|question| question.id.0).collect();
+        let rows = sqlx::query(
+            "select id, content, account_id, accepted, created_on,
+            corresponding_question
+            from answers where corresponding_question = any($1)
+            order by id",
+        )
+        .bind(&question_ids)
+        .map(|row: PgRow| {
+            let question_id: i32 = row.get("corresponding_question");
+            let answer = ExportedAnswer {
+                id: AnswerId(row.get("id")),
+                content: row.get("content"),
+                account_id: AccountId(row.get("account_id")),
+                accepted: row.get("accepted"),
+                created_on: row.get("created_on"),
+            };
+            (question_id, answer)
+        })
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(db_error)?;
+        tx.commit().await.map_err(db_error)?;
+
+        let mut answers: HashMap<i32, Vec<ExportedAnswer>> =
+            HashMap::new();
+        for (question_id, answer) in rows {
+            answers.entry(question_id).or_default().push(answer);
+        }
+        for question in questions.iter_mut() {
+            question.answers =
+                answers.remove(&question.id.0).unwrap_or_default();
+        }
+        Ok(questions)
+    }
+}
+
+/// Query selecting the `account_id` of the candidates (`$1`) which meet
+/// the rule, with the threshold (`$2`) and the tag (`$3`) it binds
+fn badge_rule_query(
+    rule: &BadgeRule,
+) -> (&'static str, i64, Option<&str>) {
+    match rule {
+        BadgeRule::QuestionsAsked { count } => (
+            "select account_id from questions
+            where account_id = any($1)
+            group by account_id having count(*) >= $2",
+            *count,
+            None,
+        ),
+        BadgeRule::AcceptedAnswers { count } => (
+            "select account_id from answers
+            where accepted and account_id = any($1)
+            group by account_id having count(*) >= $2",
+            *count,
+            None,
+        ),
+        BadgeRule::UpvotedAnswers { count } => (
+            "select account_id from (
+                select a.account_id from answers a
+                join answer_votes v on v.answer_id = a.id
+                where a.account_id = any($1)
+                group by a.account_id, a.id having sum(v.value) > 0
+            ) as upvoted
+            group by account_id having count(*) >= $2",
+            *count,
+            None,
+        ),
+        BadgeRule::TagAnswerScore { tag, score } => (
+            "select a.account_id from answers a
+            join questions q on q.id = a.corresponding_question
+            join answer_votes v on v.answer_id = a.id
+            where a.account_id = any($1) and q.tags @> array[$3::text]
+            group by a.account_id having sum(v.value) >= $2",
+            *score,
+            Some(tag),
+        ),
+    }
+}
+
+fn question_from_row(row: PgRow) -> Question {
+    Question {
+        id: QuestionId(row.get("id")),
+        title: row.get("title"),
+        content: row.get("content"),
+        content_html: row.get("content_html"),
+        tags: row.get("tags"),
+        version: row.get("version"),
+        status: row.get("status"),
+        close_reason: row.get("close_reason"),
+        bounty: row.get("bounty"),
+        author: author_from_row(&row),
+        created_on: row.get("created_on"),
+        updated_on: row.get("updated_on"),
+        answer_count: row.get("answer_count"),
+        score: row.get("score"),
+    }
+}
+
+fn answer_from_row(row: PgRow) -> Answer {
+    Answer {
+        id: AnswerId(row.get("id")),
+        content: row.get("content"),
+        content_html: row.get("content_html"),
+        question_id: QuestionId(row.get("corresponding_question")),
+        version: row.get("version"),
+        accepted: row.get("accepted"),
+        author: author_from_row(&row),
+        created_on: row.get("created_on"),
+    }
+}
+
+/// Author of a question or an answer, `None` if the account is gone
+fn author_from_row(row: &PgRow) -> Option<Author> {
+    row.get::<Option<i32>, _>("author_id").map(|id| Author {
+        id: AccountId(id),
+        username: row.get("username"),
+        display_name: row.get("display_name"),
+        avatar_url: row.get("avatar_url"),
+    })
+}
+
+fn attachment_from_row(row: PgRow) -> Attachment {
+    Attachment {
+        id: row.get("id"),
+        question_id: QuestionId(row.get("question_id")),
+        answer_id: row.get::<Option<i32>, _>("answer_id").map(AnswerId),
+        account_id: AccountId(row.get("account_id")),
+        file_name: row.get("file_name"),
+        content_type: row.get("content_type"),
+        size: row.get("size"),
+        created_on: row.get("created_on"),
+        key: row.get("key"),
+    }
+}
+
+/// Inserts an imported question and its answers with their snippets,
+/// keeping the dates, authors and status of the import
+async fn insert_imported(
+    connection: &mut PgConnection,
+    question: &ImportedQuestion,
+    account_id: &AccountId,
+) -> Result<QuestionId, sqlx::Error> {
+    let question_id: i32 = sqlx::query(
+        "insert into questions (title, content, content_html, tags,
+        account_id, status, close_reason, created_on, updated_on)
+        values ($1, $2, $3, $4, $5, $6, $7, coalesce($8, now()), $9)
+        returning id",
+    )
+    .bind(&question.title)
+    .bind(&question.content)
+    .bind(render(&question.content))
+    .bind(&question.tags)
+    .bind(question.account_id.as_ref().unwrap_or(account_id).0)
+    .bind(question.status)
+    .bind(question.close_reason)
+    .bind(question.created_on)
+    .bind(question.updated_on)
+    .map(|row: PgRow| row.get("id"))
+    .fetch_one(&mut *connection)
+    .await?;
+    let question_id = QuestionId(question_id);
+    write_snippets(
+        connection,
+        &SnippetSource::Question(question_id.clone()),
+        &question.content,
+    )
+    .await?;
+
+    for answer in &question.answers {
+        let answer_id = sqlx::query(
+            "insert into answers
+            (content, content_html, corresponding_question, account_id,
+            accepted, created_on)
+            values ($1, $2, $3, $4, $5, coalesce($6, now()))
+            returning id",
+        )
+        .bind(&answer.content)
+        .bind(render(&answer.content))
+        .bind(question_id.0)
+        .bind(answer.account_id.as_ref().unwrap_or(account_id).0)
+        .bind(answer.accepted)
+        .bind(answer.created_on)
+        .map(|row: PgRow| AnswerId(row.get("id")))
+        .fetch_one(&mut *connection)
+        .await?;
+        write_snippets(
+            connection,
+            &SnippetSource::Answer(question_id.clone(), answer_id.clone()),
+            &answer.content,
+        )
+        .await?;
+    }
+    Ok(question_id)
+}
+
+/// Replaces the snippets of a question or an answer with the code
+/// blocks of its content, in the transaction which writes the content
+async fn write_snippets(
+    connection: &mut PgConnection,
+    source: &SnippetSource,
+    content: &str,
+) -> Result<(), sqlx::Error> {
+    let (question_id, answer_id) = match source {
+        SnippetSource::Question(question_id) => (question_id.0, None),
+        SnippetSource::Answer(question_id, answer_id) => {
+            (question_id.0, Some(answer_id.0))
+        }
+    };
+    sqlx::query(
+        "delete from snippets where question_id = $1
+        and answer_id is not distinct from $2",
+    )
+    .bind(question_id)
+    .bind(answer_id)
+    .execute(&mut *connection)
+    .await?;
+
+    let mut languages = Vec::new();
+    let mut detected = Vec::new();
+    let mut codes = Vec::new();
+    for snippet in extract(content) {
+        languages.push(snippet.language);
+        detected.push(snippet.detected);
+        codes.push(snippet.code);
+    }
+    sqlx::query(
+        "insert into snippets
+        (question_id, answer_id, position, language, detected, code)
+        select $1, $2, s.position - 1, s.language, s.detected, s.code
+        from unnest($3::text[], $4::bool[], $5::text[])
+        with ordinality as s(language, detected, code, position)",
+    )
+    .bind(question_id)
+    .bind(answer_id)
+    .bind(languages)
+    .bind(detected)
+    .bind(codes)
+    .execute(&mut *connection)
+    .await?;
+    Ok(())
+}
+
+fn db_error(e: sqlx::Error) -> Error {
+    event!(Level::ERROR, "{:?}", e);
+    Error::DatabaseQueryError(e)
 }
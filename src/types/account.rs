@@ -1,18 +1,97 @@
 use chrono::prelude::*;
+use handle_errors::FieldError;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+use crate::mentions::is_valid_username;
+use crate::validation::{
+    check_email, field_error, Validate, MAX_PASSWORD_LENGTH,
+    MIN_PASSWORD_LENGTH,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 pub struct Account {
     pub id: Option<AccountId>,
     pub email: String,
     pub password: String,
+    /// Handle other accounts mention with `@username`
+    #[serde(default)]
+    pub username: Option<String>,
+}
+
+/// Account as it is sent back to its owner, without the password
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct AccountInfo {
+    pub id: AccountId,
+    pub email: String,
+    pub username: Option<String>,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+impl From<Account> for AccountInfo {
+    fn from(account: Account) -> Self {
+        AccountInfo {
+            id: account.id.expect("ID not found"),
+            email: account.email,
+            username: account.username,
+            display_name: None,
+            avatar_url: None,
+        }
+    }
+}
+
+#[derive(
+    Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, ToSchema,
+)]
 pub struct AccountId(pub i32);
 
+/// Public identity of an account, embedded in its questions and answers.
+/// The e-mail address is never part of it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct Author {
+    pub id: AccountId,
+    pub username: Option<String>,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Session {
     pub exp: DateTime<Utc>,
     pub account_id: AccountId,
 }
+
+impl Validate for Account {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        check_email(&mut errors, &self.email);
+        if let Some(username) = &self.username {
+            if !is_valid_username(username) {
+                errors.push(field_error(
+                    "username",
+                    "must be 3 to 30 letters, digits or underscores",
+                ));
+            }
+        }
+        let password_length = self.password.chars().count();
+        if password_length < MIN_PASSWORD_LENGTH {
+            errors.push(field_error(
+                "password",
+                &format!(
+                    "must be at least {} characters long",
+                    MIN_PASSWORD_LENGTH
+                ),
+            ));
+        } else if password_length > MAX_PASSWORD_LENGTH {
+            errors.push(field_error(
+                "password",
+                &format!(
+                    "must be at most {} characters long",
+                    MAX_PASSWORD_LENGTH
+                ),
+            ));
+        }
+        errors
+    }
+}
@@ -1,19 +1,63 @@
+use chrono::NaiveDateTime;
+use handle_errors::FieldError;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
+use crate::types::account::Author;
 use crate::types::question::QuestionId;
+use crate::validation::{
+    check_text, field_error, Validate, MAX_CONTENT_LENGTH,
+};
 
-#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(
+    Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Hash, ToSchema,
+)]
 pub struct AnswerId(pub i32);
 
-#[derive(Debug, Serialize, Clone, Deserialize)]
+#[derive(Debug, Serialize, Clone, Deserialize, ToSchema)]
 pub struct Answer {
     pub id: AnswerId,
+    /// Markdown source
     pub content: String,
+    /// Sanitised HTML rendering of `content`
+    #[serde(default)]
+    #[schema(read_only)]
+    pub content_html: String,
     pub question_id: QuestionId,
+    #[serde(default)]
+    #[schema(read_only)]
+    pub version: i32,
+    /// Whether the author of the question accepted the answer
+    #[serde(default)]
+    #[schema(read_only)]
+    pub accepted: bool,
+    /// Public profile of the author
+    #[serde(default)]
+    #[schema(read_only)]
+    pub author: Option<Author>,
+    #[serde(default)]
+    #[schema(read_only)]
+    pub created_on: NaiveDateTime,
 }
 
-#[derive(Debug, Serialize, Clone, Deserialize)]
+#[derive(Debug, Serialize, Clone, Deserialize, ToSchema)]
 pub struct NewAnswer {
     pub content: String,
     pub question_id: QuestionId,
 }
+
+impl Validate for NewAnswer {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        check_text(
+            &mut errors,
+            "content",
+            &self.content,
+            MAX_CONTENT_LENGTH,
+        );
+        if self.question_id.0 <= 0 {
+            errors.push(field_error("question_id", "must be a valid id"));
+        }
+        errors
+    }
+}
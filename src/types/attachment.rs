@@ -0,0 +1,175 @@
+use chrono::NaiveDateTime;
+use handle_errors::FieldError;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::types::account::AccountId;
+use crate::types::answer::AnswerId;
+use crate::types::question::QuestionId;
+use crate::validation::field_error;
+
+pub const MAX_ATTACHMENT_SIZE: u64 = 5 * 1024 * 1024;
+pub const MAX_FILE_NAME_LENGTH: usize = 255;
+
+/// File attached to a question or an answer, its content is served at
+/// `/attachments/{id}`
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct Attachment {
+    pub id: i32,
+    pub question_id: QuestionId,
+    /// `null` if the file is attached to the question
+    pub answer_id: Option<AnswerId>,
+    pub account_id: AccountId,
+    pub file_name: String,
+    pub content_type: String,
+    /// Size in bytes
+    pub size: i32,
+    pub created_on: NaiveDateTime,
+    /// Key of the content in the blob store
+    #[serde(skip)]
+    pub key: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewAttachment {
+    pub question_id: QuestionId,
+    pub answer_id: Option<AnswerId>,
+    pub key: String,
+    pub file_name: String,
+    pub content_type: String,
+    pub size: i32,
+}
+
+impl Attachment {
+    /// Images are shown in the browser, anything else is downloaded
+    pub fn is_image(&self) -> bool {
+        self.content_type.starts_with("image/")
+    }
+}
+
+/// Type of the content, from its first bytes. `None` if it isn't an
+/// accepted type.
+pub fn sniff_content_type(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if data.starts_with(b"\xff\xd8\xff") {
+        Some("image/jpeg")
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if data.len() >= 12
+        && data.starts_with(b"RIFF")
+        && &data[8..12] == b"WEBP"
+    {
+        Some("image/webp")
+    } else if data.starts_with(b"%PDF-") {
+        Some("application/pdf")
+    } else if std::str::from_utf8(data).is_ok_and(|text| {
+        text.chars()
+            .all(|c| !c.is_control() || "\t\n\r\x0c\x1b".contains(c))
+    }) {
+        Some("text/plain")
+    } else {
+        None
+    }
+}
+
+/// Checks the size of a file and that its content matches the declared
+/// type. `application/octet-stream` leaves it to the content.
+pub fn check_file(
+    declared: Option<&str>,
+    data: &[u8],
+) -> Result<&'static str, FieldError> {
+    if data.is_empty() {
+        return Err(field_error("file", "must not be empty"));
+    }
+    if data.len() as u64 > MAX_ATTACHMENT_SIZE {
+        return Err(field_error("file", "must not exceed 5 MiB"));
+    }
+    let Some(sniffed) = sniff_content_type(data) else {
+        return Err(field_error(
+            "file",
+            "must be a PNG, JPEG, GIF, WebP, PDF or text file",
+        ));
+    };
+
+    let declared = declared
+        .and_then(|declared| declared.split(';').next())
+        .map(|declared| declared.trim().to_ascii_lowercase());
+    match declared.as_deref() {
+        None | Some("application/octet-stream") => Ok(sniffed),
+        Some(declared) if declared == sniffed => Ok(sniffed),
+        Some(declared)
+            if sniffed == "text/plain"
+                && declared.starts_with("text/") =>
+        {
+            Ok(sniffed)
+        }
+        Some(_) => Err(field_error(
+            "file",
+            "content doesn't match its content type",
+        )),
+    }
+}
+
+/// Last component of the name given by the client, without the
+/// characters which could break the `Content-Disposition` header
+pub fn sanitize_file_name(file_name: Option<&str>) -> String {
+    let file_name: String = file_name
+        .unwrap_or_default()
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_graphic() && c != '"' || c == ' ' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(MAX_FILE_NAME_LENGTH)
+        .collect();
+    match file_name.trim() {
+        "" | "." | ".." => "attachment".to_string(),
+        file_name => file_name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod attachment_tests {
+    use super::*;
+
+    #[test]
+    fn content_must_match_declared_type() {
+        // Arrange
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+        let binary = b"\x7fELF\x02\x01\x01\0";
+        // Act
+        let declared_png = check_file(Some("image/png"), png);
+        let octet_stream =
+            check_file(Some("application/octet-stream"), png);
+        let text = check_file(Some("text/x-log; charset=utf-8"), b"ok\n");
+        let spoofed = check_file(Some("image/png"), b"<svg></svg>");
+        let unknown = check_file(None, binary);
+        let empty = check_file(Some("text/plain"), b"");
+        // Assert
+        assert_eq!(declared_png, Ok("image/png"));
+        assert_eq!(octet_stream, Ok("image/png"));
+        assert_eq!(text, Ok("text/plain"));
+        assert!(spoofed.is_err());
+        assert!(unknown.is_err());
+        assert!(empty.is_err());
+    }
+
+    #[test]
+    fn file_names_are_sanitized() {
+        // Arrange
+        // Act
+        let path =
+            sanitize_file_name(Some("C:\\logs\\../trace \"1\".txt"));
+        let missing = sanitize_file_name(None);
+        // Assert
+        assert_eq!(path, "trace _1_.txt");
+        assert_eq!(missing, "attachment");
+    }
+}
@@ -0,0 +1,50 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Condition an account has to meet to earn a badge
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BadgeRule {
+    QuestionsAsked {
+        count: i64,
+    },
+    AcceptedAnswers {
+        count: i64,
+    },
+    /// Answers with a positive score
+    UpvotedAnswers {
+        count: i64,
+    },
+    /// Total score of the answers to questions with the tag
+    TagAnswerScore {
+        tag: String,
+        score: i64,
+    },
+}
+
+/// Badge as it is defined in the rules file
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BadgeDefinition {
+    pub slug: String,
+    pub name: String,
+    pub description: String,
+    pub rule: BadgeRule,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct Badge {
+    pub slug: String,
+    pub name: String,
+    pub description: String,
+    /// Number of accounts which earned the badge
+    pub awarded_count: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct AccountBadge {
+    pub slug: String,
+    pub name: String,
+    pub description: String,
+    pub awarded_on: NaiveDateTime,
+}
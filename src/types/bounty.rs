@@ -0,0 +1,94 @@
+use chrono::NaiveDateTime;
+use handle_errors::FieldError;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::types::account::AccountId;
+use crate::types::answer::AnswerId;
+use crate::types::question::QuestionId;
+use crate::validation::{field_error, Validate};
+
+pub const MIN_BOUNTY: i32 = 50;
+pub const MAX_BOUNTY: i32 = 500;
+pub const MAX_BOUNTY_DAYS: i32 = 7;
+
+/// Reputation offered by the owner of a question for a good answer
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct Bounty {
+    pub id: i32,
+    pub question_id: QuestionId,
+    pub account_id: AccountId,
+    pub amount: i32,
+    pub created_on: NaiveDateTime,
+    pub expires_on: NaiveDateTime,
+    /// Answer which got the bounty, `null` while the bounty is open or
+    /// if it expired without a voted answer
+    pub answer_id: Option<AnswerId>,
+    pub ended_on: Option<NaiveDateTime>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct NewBounty {
+    /// Reputation taken from the owner right away
+    pub amount: i32,
+    /// Days until the bounty goes to the top voted answer
+    #[serde(default = "default_days")]
+    pub days: i32,
+}
+
+fn default_days() -> i32 {
+    MAX_BOUNTY_DAYS
+}
+
+impl Validate for NewBounty {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if !(MIN_BOUNTY..=MAX_BOUNTY).contains(&self.amount) {
+            errors.push(field_error(
+                "amount",
+                &format!("must be between {MIN_BOUNTY} and {MAX_BOUNTY}"),
+            ));
+        }
+        if !(1..=MAX_BOUNTY_DAYS).contains(&self.days) {
+            errors.push(field_error(
+                "days",
+                &format!("must be between 1 and {MAX_BOUNTY_DAYS}"),
+            ));
+        }
+        errors
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct BountyAward {
+    pub answer_id: AnswerId,
+}
+
+impl Validate for BountyAward {
+    fn validate(&self) -> Vec<FieldError> {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod bounty_tests {
+    use super::*;
+
+    #[test]
+    fn new_bounty_is_validated() {
+        // Arrange
+        let valid: NewBounty =
+            serde_json::from_str(r#"{"amount": 50}"#).unwrap();
+        let invalid = NewBounty {
+            amount: 1000,
+            days: 0,
+        };
+        // Act
+        let valid_errors = valid.validate();
+        let invalid_errors = invalid.validate();
+        // Assert
+        assert_eq!(valid.days, MAX_BOUNTY_DAYS);
+        assert!(valid_errors.is_empty());
+        assert_eq!(invalid_errors.len(), 2);
+    }
+}
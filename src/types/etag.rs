@@ -0,0 +1,91 @@
+use handle_errors::Error;
+
+/// Entity tag of a resource, derived from its `version` column
+pub fn etag(version: i32) -> String {
+    format!("\"{}\"", version)
+}
+
+/// Extract the versions a client expects from the `If-Match` header
+/// of a `PUT` or `DELETE` request.
+/// Returns `None` for `If-Match: *`, which matches any version.
+/// # Example
+/// `If-Match: "3"` gives `Some(vec![3])`
+pub fn extract_if_match(
+    header: Option<String>,
+) -> Result<Option<Vec<i32>>, Error> {
+    let header = match header {
+        Some(header) => header,
+        None => return Err(Error::PreconditionRequired),
+    };
+
+    if header.trim() == "*" {
+        return Ok(None);
+    }
+    // `If-Match` uses the strong comparison, so weak tags never match
+    Ok(Some(
+        header
+            .split(',')
+            .filter_map(|tag| parse_tag(tag.trim()))
+            .collect(),
+    ))
+}
+
+/// Check an `If-None-Match` header against the current version
+/// using the weak comparison
+pub fn matches_if_none_match(header: &str, version: i32) -> bool {
+    if header.trim() == "*" {
+        return true;
+    }
+    header.split(',').any(|tag| {
+        let tag = tag.trim();
+        let tag = tag.strip_prefix("W/").unwrap_or(tag);
+        parse_tag(tag) == Some(version)
+    })
+}
+
+fn parse_tag(tag: &str) -> Option<i32> {
+    tag.strip_prefix('"')?
+        .strip_suffix('"')?
+        .parse::<i32>()
+        .ok()
+}
+
+#[cfg(test)]
+mod etag_tests {
+    use super::*;
+
+    #[test]
+    fn missing_if_match() {
+        // Arrange
+        let expected = format!("{}", Error::PreconditionRequired);
+        // Act
+        let result = format!("{}", extract_if_match(None).unwrap_err());
+        // Assert
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn if_match_list_and_wildcard() {
+        // Arrange
+        let list = Some(String::from("\"1\", W/\"2\", \"3\""));
+        let wildcard = Some(String::from("*"));
+        // Act
+        let list = extract_if_match(list).unwrap();
+        let wildcard = extract_if_match(wildcard).unwrap();
+        // Assert
+        assert_eq!(list, Some(vec![1, 3]));
+        assert_eq!(wildcard, None);
+    }
+
+    #[test]
+    fn if_none_match_uses_weak_comparison() {
+        // Arrange
+        let header = "W/\"4\"";
+        // Act
+        let matching = matches_if_none_match(header, 4);
+        let not_matching = matches_if_none_match(header, 5);
+        // Assert
+        assert!(matching);
+        assert!(!not_matching);
+    }
+}
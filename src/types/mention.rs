@@ -0,0 +1,21 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::types::account::AccountId;
+use crate::types::answer::AnswerId;
+use crate::types::question::QuestionId;
+
+/// `@username` mention of an account in a question or an answer
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct Mention {
+    pub id: i32,
+    pub question_id: QuestionId,
+    /// `null` if the account was mentioned in the question
+    pub answer_id: Option<AnswerId>,
+    /// Title of the question
+    pub title: String,
+    /// Author of the post
+    pub actor_id: AccountId,
+    pub created_on: NaiveDateTime,
+}
@@ -1,4 +1,18 @@
 pub mod account;
 pub mod answer;
+pub mod attachment;
+pub mod badge;
+pub mod bounty;
+pub mod etag;
+pub mod mention;
+pub mod notification;
 pub mod pagination;
+pub mod profile;
 pub mod question;
+pub mod reputation;
+pub mod snippet;
+pub mod tag;
+pub mod transfer;
+pub mod vote;
+pub mod watch;
+pub mod webhook;
@@ -0,0 +1,79 @@
+use chrono::NaiveDateTime;
+use handle_errors::FieldError;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::types::account::AccountId;
+use crate::types::answer::AnswerId;
+use crate::types::question::QuestionId;
+use crate::validation::Validate;
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    ToSchema,
+    sqlx::Type,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "notification_kind", rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// Someone answered a question of the account
+    Answer,
+    AcceptedAnswer,
+    Mention,
+    /// Activity on a question the account watches
+    WatchedQuestion,
+    /// New question with a tag the account watches
+    WatchedTag,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct Notification {
+    pub id: i32,
+    pub kind: NotificationKind,
+    pub question_id: Option<QuestionId>,
+    pub answer_id: Option<AnswerId>,
+    /// Account which caused the notification
+    pub actor_id: Option<AccountId>,
+    pub read: bool,
+    pub created_on: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct NotificationList {
+    /// Unread notifications of the account, regardless of the pagination
+    pub unread_count: i64,
+    pub notifications: Vec<Notification>,
+}
+
+/// Kinds of notifications an account wants to receive
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct NotificationPreferences {
+    pub answers: bool,
+    pub accepted_answers: bool,
+    pub mentions: bool,
+    /// Activity on the watched questions and tags
+    pub watches: bool,
+}
+
+impl Default for NotificationPreferences {
+    fn default() -> Self {
+        NotificationPreferences {
+            answers: true,
+            accepted_answers: true,
+            mentions: true,
+            watches: true,
+        }
+    }
+}
+
+impl Validate for NotificationPreferences {
+    fn validate(&self) -> Vec<FieldError> {
+        Vec::new()
+    }
+}
@@ -11,6 +11,9 @@ pub struct Pagination {
     pub offset: i32,
 }
 
+const PAGINATION_ERROR: &str =
+    "Pagination requires 'limit' and 'offset' params!";
+
 /// Extract query parameters from the `/questions` route
 /// # Example query
 /// GET requests to this route can have a pagination attached so we just
@@ -25,10 +28,6 @@ pub struct Pagination {
 /// assert_eq!(p.limit, 1);
 /// assert_eq!(p.offset, 10);
 /// ```
-
-const PAGINATION_ERROR: &str =
-    "Pagination requires 'limit' and 'offset' params!";
-
 pub fn extract_pagination(
     params: HashMap<String, String>,
 ) -> Result<Pagination, Error> {
@@ -0,0 +1,136 @@
+use chrono::NaiveDateTime;
+use handle_errors::FieldError;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::mentions::is_valid_username;
+use crate::types::account::AccountId;
+use crate::types::answer::AnswerId;
+use crate::types::question::QuestionId;
+use crate::validation::{
+    check_text, field_error, Validate, MAX_URL_LENGTH,
+};
+
+pub const MAX_DISPLAY_NAME_LENGTH: usize = 50;
+
+/// Public profile of an account, without its e-mail address
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct Profile {
+    pub id: AccountId,
+    pub username: Option<String>,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+    pub reputation: i64,
+    /// Number of questions asked
+    pub questions: i64,
+    /// Number of answers given
+    pub answers: i64,
+    pub created_on: NaiveDateTime,
+}
+
+/// Replaces the public identity of the account, `null` clears a member
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct ProfileUpdate {
+    /// Handle of the account in `@username` mentions
+    pub username: Option<String>,
+    pub display_name: Option<String>,
+    /// HTTPS URL of the picture
+    pub avatar_url: Option<String>,
+}
+
+impl Validate for ProfileUpdate {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if let Some(username) = &self.username {
+            if !is_valid_username(username) {
+                errors.push(field_error(
+                    "username",
+                    "must be 3 to 30 letters, digits or underscores",
+                ));
+            }
+        }
+        if let Some(display_name) = &self.display_name {
+            check_text(
+                &mut errors,
+                "display_name",
+                display_name,
+                MAX_DISPLAY_NAME_LENGTH,
+            );
+        }
+        if let Some(avatar_url) = &self.avatar_url {
+            check_text(
+                &mut errors,
+                "avatar_url",
+                avatar_url,
+                MAX_URL_LENGTH,
+            );
+            if !avatar_url.starts_with("https://") {
+                errors.push(field_error(
+                    "avatar_url",
+                    "must be an https URL",
+                ));
+            }
+        }
+        errors
+    }
+}
+
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    Asked,
+    Answered,
+}
+
+/// Question asked or answer given by an account
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct Activity {
+    pub kind: ActivityKind,
+    pub question_id: QuestionId,
+    /// `null` for questions
+    pub answer_id: Option<AnswerId>,
+    /// Title of the question
+    pub title: String,
+    pub created_on: NaiveDateTime,
+}
+
+#[cfg(test)]
+mod profile_tests {
+    use super::*;
+
+    #[test]
+    fn avatar_must_be_https() {
+        // Arrange
+        let update = ProfileUpdate {
+            username: Some("ada".to_string()),
+            display_name: Some("Ada".to_string()),
+            avatar_url: Some("http://example.com/ada.png".to_string()),
+        };
+        let expected =
+            vec![field_error("avatar_url", "must be an https URL")];
+        // Act
+        let errors = update.validate();
+        // Assert
+        assert_eq!(errors, expected);
+    }
+
+    #[test]
+    fn username_must_be_valid() {
+        // Arrange
+        let update = ProfileUpdate {
+            username: Some("ada lovelace".to_string()),
+            display_name: None,
+            avatar_url: None,
+        };
+        let expected = vec![field_error(
+            "username",
+            "must be 3 to 30 letters, digits or underscores",
+        )];
+        // Act
+        let errors = update.validate();
+        // Assert
+        assert_eq!(errors, expected);
+    }
+}
@@ -1,19 +1,329 @@
-use serde::{Deserialize, Serialize};
+use chrono::NaiveDateTime;
+use handle_errors::FieldError;
+use serde::{Deserialize, Deserializer, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize, Clone, Deserialize)]
+use crate::types::account::Author;
+use crate::validation::{
+    check_tags, check_text, field_error, Validate, MAX_CONTENT_LENGTH,
+    MAX_TITLE_LENGTH,
+};
+
+#[derive(Debug, Serialize, Clone, Deserialize, ToSchema)]
 pub struct Question {
     pub id: QuestionId,
     pub title: String,
+    /// Markdown source
     pub content: String,
+    /// Sanitised HTML rendering of `content`
+    #[serde(default)]
+    #[schema(read_only)]
+    pub content_html: String,
     pub tags: Option<Vec<String>>,
+    /// Incremented on every update, sent to clients as the `ETag`
+    #[serde(default)]
+    #[schema(read_only)]
+    pub version: i32,
+    #[serde(default)]
+    #[schema(read_only)]
+    pub status: QuestionStatus,
+    /// Why the question was closed
+    #[serde(default)]
+    #[schema(read_only)]
+    pub close_reason: Option<CloseReason>,
+    /// Reputation offered by the open bounty of the question
+    #[serde(default)]
+    #[schema(read_only)]
+    pub bounty: Option<i32>,
+    /// Public profile of the author
+    #[serde(default)]
+    #[schema(read_only)]
+    pub author: Option<Author>,
+    #[serde(default)]
+    #[schema(read_only)]
+    pub created_on: NaiveDateTime,
+    /// Last edit of the title, content or tags by the author, `null` if
+    /// the question was never edited
+    #[serde(default)]
+    #[schema(read_only)]
+    pub updated_on: Option<NaiveDateTime>,
+    #[serde(default)]
+    #[schema(read_only)]
+    pub answer_count: i64,
+    /// Sum of the votes
+    #[serde(default)]
+    #[schema(read_only)]
+    pub score: i64,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[derive(
+    Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash, ToSchema,
+)]
 pub struct QuestionId(pub i32);
 
-#[derive(Debug, Serialize, Clone, Deserialize)]
+#[derive(
+    Debug,
+    Default,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    ToSchema,
+    sqlx::Type,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "question_status", rename_all = "snake_case")]
+pub enum QuestionStatus {
+    #[default]
+    Open,
+    /// No new answers
+    Closed,
+    /// No new answers and no edits
+    Locked,
+    /// Only accounts with some reputation may answer
+    Protected,
+}
+
+impl QuestionStatus {
+    /// Transitions moderators may make, a closed question is reopened by
+    /// going back to open
+    pub fn can_become(self, to: QuestionStatus) -> bool {
+        use QuestionStatus::*;
+        matches!(
+            (self, to),
+            (Open, Closed | Locked | Protected)
+                | (Closed, Open | Locked)
+                | (Locked, Open | Closed)
+                | (Protected, Open | Closed | Locked)
+        )
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            QuestionStatus::Open => "open",
+            QuestionStatus::Closed => "closed",
+            QuestionStatus::Locked => "locked",
+            QuestionStatus::Protected => "protected",
+        }
+    }
+}
+
+#[derive(
+    Debug,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    ToSchema,
+    sqlx::Type,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "close_reason", rename_all = "snake_case")]
+pub enum CloseReason {
+    /// Set by marking the question as a duplicate
+    Duplicate,
+    OffTopic,
+    Unclear,
+    TooBroad,
+    OpinionBased,
+}
+
+/// New status of a question, set by a moderator
+#[derive(Debug, Serialize, Clone, Deserialize, ToSchema)]
+pub struct StatusChange {
+    pub status: QuestionStatus,
+    /// Required to close the question
+    pub reason: Option<CloseReason>,
+}
+
+#[derive(Debug, Serialize, Clone, Deserialize, ToSchema)]
 pub struct NewQuestion {
     pub title: String,
     pub content: String,
     pub tags: Option<Vec<String>>,
 }
+
+/// Question whose title resembles the searched one
+#[derive(Debug, Serialize, Clone, Deserialize, ToSchema)]
+pub struct SimilarQuestion {
+    pub id: QuestionId,
+    pub title: String,
+    /// Trigram similarity of the titles, from 0 to 1
+    pub similarity: f32,
+}
+
+#[derive(Debug, Serialize, Clone, Deserialize, ToSchema)]
+pub struct Duplicate {
+    /// Question the duplicate points to
+    pub duplicate_of: QuestionId,
+}
+
+/// RFC 7396 merge patch of a question.
+/// The outer `Option` is `None` when the member is absent from the patch,
+/// the inner one is `None` when the member is set to `null`.
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct QuestionPatch {
+    #[serde(default, deserialize_with = "present")]
+    pub title: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub content: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub tags: Option<Option<Vec<String>>>,
+}
+
+/// Marks a member as present, even if its value is `null`
+fn present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+impl Validate for NewQuestion {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        check_text(&mut errors, "title", &self.title, MAX_TITLE_LENGTH);
+        check_text(
+            &mut errors,
+            "content",
+            &self.content,
+            MAX_CONTENT_LENGTH,
+        );
+        check_tags(&mut errors, &self.tags);
+        errors
+    }
+}
+
+impl Validate for Question {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        check_text(&mut errors, "title", &self.title, MAX_TITLE_LENGTH);
+        check_text(
+            &mut errors,
+            "content",
+            &self.content,
+            MAX_CONTENT_LENGTH,
+        );
+        check_tags(&mut errors, &self.tags);
+        errors
+    }
+}
+
+impl Validate for QuestionPatch {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        match &self.title {
+            Some(Some(title)) => {
+                check_text(&mut errors, "title", title, MAX_TITLE_LENGTH)
+            }
+            Some(None) => {
+                errors.push(field_error("title", "must not be null"))
+            }
+            None => (),
+        }
+        match &self.content {
+            Some(Some(content)) => check_text(
+                &mut errors,
+                "content",
+                content,
+                MAX_CONTENT_LENGTH,
+            ),
+            Some(None) => {
+                errors.push(field_error("content", "must not be null"))
+            }
+            None => (),
+        }
+        if let Some(tags) = &self.tags {
+            check_tags(&mut errors, tags);
+        }
+        errors
+    }
+}
+
+impl Validate for StatusChange {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        match (self.status, self.reason) {
+            (QuestionStatus::Closed, None) => {
+                errors.push(field_error("reason", "is required to close"))
+            }
+            (QuestionStatus::Closed, Some(CloseReason::Duplicate)) => {
+                errors.push(field_error(
+                    "reason",
+                    "must be set by marking the question as a duplicate",
+                ))
+            }
+            (QuestionStatus::Closed, _) | (_, None) => (),
+            (_, Some(_)) => {
+                errors.push(field_error("reason", "is only used to close"))
+            }
+        }
+        errors
+    }
+}
+
+impl Validate for Duplicate {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.duplicate_of.0 <= 0 {
+            errors.push(field_error("duplicate_of", "must be a valid id"));
+        }
+        errors
+    }
+}
+
+#[cfg(test)]
+mod question_tests {
+    use super::*;
+
+    #[test]
+    fn patch_distinguishes_absent_and_null_members() {
+        // Arrange
+        let body = r#"{ "title": "New title", "tags": null }"#;
+        // Act
+        let patch: QuestionPatch = serde_json::from_str(body).unwrap();
+        // Assert
+        assert_eq!(patch.title, Some(Some("New title".to_string())));
+        assert_eq!(patch.content, None);
+        assert_eq!(patch.tags, Some(None));
+    }
+
+    #[test]
+    fn patch_cannot_remove_required_members() {
+        // Arrange
+        let body = r#"{ "content": null }"#;
+        let expected = vec![field_error("content", "must not be null")];
+        // Act
+        let patch: QuestionPatch = serde_json::from_str(body).unwrap();
+        // Assert
+        assert_eq!(patch.validate(), expected);
+    }
+
+    #[test]
+    fn status_transitions_follow_the_state_machine() {
+        // Arrange
+        use QuestionStatus::*;
+        let closing = StatusChange {
+            status: Closed,
+            reason: None,
+        };
+        // Act
+        let reopen = Closed.can_become(Open);
+        let protect_closed = Closed.can_become(Protected);
+        let same = Locked.can_become(Locked);
+        let errors = closing.validate();
+        // Assert
+        assert!(reopen);
+        assert!(!protect_closed);
+        assert!(!same);
+        assert_eq!(
+            errors,
+            vec![field_error("reason", "is required to close")]
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use handle_errors::Error;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use utoipa::ToSchema;
+
+use crate::types::account::AccountId;
+use crate::validation::field_error;
+
+/// Events of the reputation ledger
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "reputation_kind", rename_all = "snake_case")]
+pub enum ReputationKind {
+    QuestionUpvoted,
+    QuestionDownvoted,
+    AnswerUpvoted,
+    AnswerDownvoted,
+    AnswerAccepted,
+    BountyOffered,
+    BountyAwarded,
+}
+
+impl ReputationKind {
+    pub const ALL: [ReputationKind; 7] = [
+        ReputationKind::QuestionUpvoted,
+        ReputationKind::QuestionDownvoted,
+        ReputationKind::AnswerUpvoted,
+        ReputationKind::AnswerDownvoted,
+        ReputationKind::AnswerAccepted,
+        ReputationKind::BountyOffered,
+        ReputationKind::BountyAwarded,
+    ];
+
+    /// Points the author of the post earns or loses, `None` if they
+    /// are the amount of the bounty
+    pub fn points(self) -> Option<i32> {
+        match self {
+            ReputationKind::QuestionUpvoted => Some(5),
+            ReputationKind::QuestionDownvoted => Some(-2),
+            ReputationKind::AnswerUpvoted => Some(10),
+            ReputationKind::AnswerDownvoted => Some(-2),
+            ReputationKind::AnswerAccepted => Some(15),
+            ReputationKind::BountyOffered
+            | ReputationKind::BountyAwarded => None,
+        }
+    }
+
+    /// Name of the Postgres enum value
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReputationKind::QuestionUpvoted => "question_upvoted",
+            ReputationKind::QuestionDownvoted => "question_downvoted",
+            ReputationKind::AnswerUpvoted => "answer_upvoted",
+            ReputationKind::AnswerDownvoted => "answer_downvoted",
+            ReputationKind::AnswerAccepted => "answer_accepted",
+            ReputationKind::BountyOffered => "bounty_offered",
+            ReputationKind::BountyAwarded => "bounty_awarded",
+        }
+    }
+}
+
+/// Actions which require a minimum reputation
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Privilege {
+    /// Answer questions which are protected by a moderator
+    AnswerProtected,
+    /// Offer bounties on own questions
+    OfferBounty,
+    VoteDown,
+    /// Edit the description and the synonyms of tags
+    EditTags,
+    /// Mark questions of other accounts as duplicates
+    CloseQuestions,
+}
+
+impl Privilege {
+    pub const ALL: [Privilege; 5] = [
+        Privilege::AnswerProtected,
+        Privilege::OfferBounty,
+        Privilege::VoteDown,
+        Privilege::EditTags,
+        Privilege::CloseQuestions,
+    ];
+
+    pub fn required_reputation(self) -> i32 {
+        match self {
+            Privilege::AnswerProtected => 10,
+            Privilege::OfferBounty => 75,
+            Privilege::VoteDown => 125,
+            Privilege::EditTags => 1500,
+            Privilege::CloseQuestions => 3000,
+        }
+    }
+
+    pub fn check(self, reputation: i64) -> Result<(), Error> {
+        let required = self.required_reputation();
+        if reputation >= i64::from(required) {
+            Ok(())
+        } else {
+            Err(Error::InsufficientReputation(required))
+        }
+    }
+
+    /// Privileges granted with the given reputation
+    pub fn granted(reputation: i64) -> Vec<Privilege> {
+        Privilege::ALL
+            .into_iter()
+            .filter(|privilege| privilege.check(reputation).is_ok())
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct Reputation {
+    pub account_id: AccountId,
+    pub reputation: i64,
+    pub privileges: Vec<Privilege>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct LeaderboardEntry {
+    pub account_id: AccountId,
+    /// Reputation earned during the window
+    pub reputation: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeaderboardWindow {
+    Week,
+    Month,
+    All,
+}
+
+impl LeaderboardWindow {
+    /// Length of the window, `None` for all-time
+    pub fn seconds(self) -> Option<f64> {
+        match self {
+            LeaderboardWindow::Week => Some(7.0 * 24.0 * 60.0 * 60.0),
+            LeaderboardWindow::Month => Some(30.0 * 24.0 * 60.0 * 60.0),
+            LeaderboardWindow::All => None,
+        }
+    }
+}
+
+impl FromStr for LeaderboardWindow {
+    type Err = Error;
+
+    fn from_str(window: &str) -> Result<Self, Self::Err> {
+        match window {
+            "week" => Ok(LeaderboardWindow::Week),
+            "month" => Ok(LeaderboardWindow::Month),
+            "all" => Ok(LeaderboardWindow::All),
+            _ => Err(Error::ValidationError(vec![field_error(
+                "window",
+                "must be one of week, month or all",
+            )])),
+        }
+    }
+}
+
+#[cfg(test)]
+mod reputation_tests {
+    use super::*;
+
+    #[test]
+    fn privileges_follow_thresholds() {
+        // Arrange
+        let reputations = [0, 125, 3000];
+        // Act
+        let granted = reputations.map(Privilege::granted);
+        let denied = Privilege::EditTags.check(1499);
+        // Assert
+        assert_eq!(granted[0], vec![]);
+        assert_eq!(
+            granted[1],
+            vec![
+                Privilege::AnswerProtected,
+                Privilege::OfferBounty,
+                Privilege::VoteDown
+            ]
+        );
+        assert_eq!(granted[2], Privilege::ALL.to_vec());
+        assert!(matches!(
+            denied,
+            Err(Error::InsufficientReputation(1500))
+        ));
+    }
+}
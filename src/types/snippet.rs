@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::types::answer::AnswerId;
+use crate::types::question::QuestionId;
+
+/// Fenced code block of a question or an answer
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct Snippet {
+    pub id: i32,
+    /// Language of the info string, or detected if `detected` is true.
+    /// `null` if it couldn't be detected.
+    pub language: Option<String>,
+    pub detected: bool,
+    pub code: String,
+    pub question_id: QuestionId,
+    /// `null` if the snippet is part of the question
+    pub answer_id: Option<AnswerId>,
+    /// URL of the question, or of the answers of the question
+    pub url: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewSnippet {
+    pub language: Option<String>,
+    pub detected: bool,
+    pub code: String,
+}
+
+/// Post the snippets were extracted from
+#[derive(Debug, Clone)]
+pub enum SnippetSource {
+    Question(QuestionId),
+    Answer(QuestionId, AnswerId),
+}
@@ -0,0 +1,64 @@
+use handle_errors::FieldError;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::validation::{
+    check_tags, field_error, normalize_tag, Validate,
+    MAX_TAG_DESCRIPTION_LENGTH,
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct Tag {
+    pub slug: String,
+    pub description: Option<String>,
+    /// Spellings replaced by the slug when a question is tagged
+    pub synonyms: Vec<String>,
+    pub question_count: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct TagUpdate {
+    pub description: Option<String>,
+    /// Replaces the synonyms of the tag. Existing tags listed here are
+    /// merged into this one.
+    #[serde(default)]
+    pub synonyms: Vec<String>,
+}
+
+impl Validate for TagUpdate {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if let Some(description) = &self.description {
+            if description.chars().count() > MAX_TAG_DESCRIPTION_LENGTH {
+                errors.push(field_error(
+                    "description",
+                    &format!(
+                        "must be at most {} characters long",
+                        MAX_TAG_DESCRIPTION_LENGTH
+                    ),
+                ));
+            }
+        }
+        // Synonyms follow the rules of the tags of a question, except
+        // for their number
+        for (index, synonym) in self.synonyms.iter().enumerate() {
+            let mut synonym_errors = Vec::new();
+            check_tags(&mut synonym_errors, &Some(vec![synonym.clone()]));
+            errors.extend(synonym_errors.into_iter().map(|e| {
+                field_error(&format!("synonyms[{}]", index), &e.message)
+            }));
+        }
+        errors
+    }
+}
+
+impl TagUpdate {
+    /// Slugs of the synonyms, without duplicates
+    pub fn synonym_slugs(&self) -> Vec<String> {
+        let mut slugs: Vec<String> =
+            self.synonyms.iter().map(|s| normalize_tag(s)).collect();
+        slugs.sort();
+        slugs.dedup();
+        slugs
+    }
+}
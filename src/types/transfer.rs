@@ -0,0 +1,261 @@
+use chrono::NaiveDateTime;
+use handle_errors::FieldError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+use crate::types::account::AccountId;
+use crate::types::answer::AnswerId;
+use crate::types::question::{CloseReason, QuestionId, QuestionStatus};
+use crate::validation::{
+    check_tags, check_text, field_error, Validate, MAX_CONTENT_LENGTH,
+    MAX_TITLE_LENGTH,
+};
+
+/// Maximum size in bytes of an import
+pub const MAX_IMPORT_SIZE: u64 = 32 * 1024 * 1024;
+/// Rows imported in a single transaction
+pub const IMPORT_BATCH_SIZE: usize = 100;
+/// Questions exported in a single transaction
+pub const EXPORT_BATCH_SIZE: i64 = 100;
+
+/// Question of an import, with its answers. Ids and unknown members
+/// are ignored, so an export can be imported again. Questions closed as
+/// duplicates are rejected, as the links between questions aren't kept.
+#[derive(Deserialize, Debug, Clone, ToSchema)]
+pub struct ImportedQuestion {
+    pub title: String,
+    pub content: String,
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+    /// Author, the importing account if missing
+    #[serde(default)]
+    pub account_id: Option<AccountId>,
+    /// Open if missing
+    #[serde(default)]
+    pub status: QuestionStatus,
+    /// Required to import a closed question
+    #[serde(default)]
+    pub close_reason: Option<CloseReason>,
+    /// Now if missing
+    #[serde(default)]
+    pub created_on: Option<NaiveDateTime>,
+    #[serde(default)]
+    pub updated_on: Option<NaiveDateTime>,
+    #[serde(default)]
+    pub answers: Vec<ImportedAnswer>,
+}
+
+#[derive(Deserialize, Debug, Clone, ToSchema)]
+pub struct ImportedAnswer {
+    pub content: String,
+    /// Author, the importing account if missing
+    #[serde(default)]
+    pub account_id: Option<AccountId>,
+    /// Now if missing
+    #[serde(default)]
+    pub created_on: Option<NaiveDateTime>,
+    #[serde(default)]
+    pub accepted: bool,
+}
+
+impl Validate for ImportedQuestion {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        check_text(&mut errors, "title", &self.title, MAX_TITLE_LENGTH);
+        check_text(
+            &mut errors,
+            "content",
+            &self.content,
+            MAX_CONTENT_LENGTH,
+        );
+        check_tags(&mut errors, &self.tags);
+        for (index, answer) in self.answers.iter().enumerate() {
+            check_text(
+                &mut errors,
+                &format!("answers[{}].content", index),
+                &answer.content,
+                MAX_CONTENT_LENGTH,
+            );
+        }
+        match (self.status, self.close_reason) {
+            (QuestionStatus::Closed, None) => errors
+                .push(field_error("close_reason", "is required to close")),
+            (QuestionStatus::Closed, Some(CloseReason::Duplicate)) => {
+                errors.push(field_error(
+                    "close_reason",
+                    "must not be duplicate, the links aren't imported",
+                ))
+            }
+            (QuestionStatus::Closed, _) | (_, None) => (),
+            (_, Some(_)) => errors.push(field_error(
+                "close_reason",
+                "is only used to close",
+            )),
+        }
+        if self.answers.iter().filter(|a| a.accepted).count() > 1 {
+            errors.push(field_error(
+                "answers",
+                "must contain at most one accepted answer",
+            ));
+        }
+        errors
+    }
+}
+
+/// Line of an export. Votes, bounties, attachments and the links
+/// between duplicates aren't exported.
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct ExportedQuestion {
+    pub id: QuestionId,
+    pub title: String,
+    /// Markdown source
+    pub content: String,
+    pub tags: Option<Vec<String>>,
+    pub account_id: AccountId,
+    pub status: QuestionStatus,
+    pub close_reason: Option<CloseReason>,
+    pub created_on: NaiveDateTime,
+    pub updated_on: Option<NaiveDateTime>,
+    /// Oldest first
+    pub answers: Vec<ExportedAnswer>,
+}
+
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct ExportedAnswer {
+    pub id: AnswerId,
+    /// Markdown source
+    pub content: String,
+    pub account_id: AccountId,
+    pub accepted: bool,
+    pub created_on: NaiveDateTime,
+}
+
+/// Outcome of an import, the rows are imported independently
+#[derive(Serialize, Debug, Default, ToSchema)]
+pub struct ImportReport {
+    pub imported: Vec<ImportedRow>,
+    pub failed: Vec<FailedRow>,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct ImportedRow {
+    /// Line number of an NDJSON row, key of a map-style row
+    pub row: String,
+    pub question_id: QuestionId,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct FailedRow {
+    /// Line number of an NDJSON row, key of a map-style row
+    pub row: String,
+    pub errors: Vec<FieldError>,
+}
+
+/// Row of an import, either parsed or with the reason it couldn't be
+pub type ParsedRow = (String, Result<ImportedQuestion, FieldError>);
+
+fn parse_row(row: String, value: Result<Value, String>) -> ParsedRow {
+    let question = value
+        .and_then(|value| {
+            serde_json::from_value(value).map_err(|e| e.to_string())
+        })
+        .map_err(|e| field_error("row", &e));
+    (row, question)
+}
+
+/// One question per line, blank lines are skipped
+pub fn parse_ndjson(body: &str) -> Vec<ParsedRow> {
+    body.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            let value =
+                serde_json::from_str(line).map_err(|e| e.to_string());
+            parse_row((index + 1).to_string(), value)
+        })
+        .collect()
+}
+
+/// Object of questions keyed by their old ids, like `question.json`.
+/// Numeric keys come first, in numeric order.
+pub fn parse_map(body: &str) -> Result<Vec<ParsedRow>, serde_json::Error> {
+    let map: HashMap<String, Value> = serde_json::from_str(body)?;
+    let mut rows: Vec<(String, Value)> = map.into_iter().collect();
+    rows.sort_by_cached_key(|(key, _)| {
+        let number = key.parse::<i64>().ok();
+        (number.is_none(), number, key.clone())
+    });
+    Ok(rows
+        .into_iter()
+        .map(|(key, value)| parse_row(key, Ok(value)))
+        .collect())
+}
+
+#[cfg(test)]
+mod transfer_tests {
+    use super::*;
+
+    #[test]
+    fn ndjson_rows_are_numbered_by_line() {
+        // Arrange
+        let body =
+            "{\"title\": \"How?\", \"content\": \"Please help!\"}\n\
+            \n\
+            not json\n\
+            {\"title\": \"Why?\"}\n";
+        // Act
+        let rows = parse_ndjson(body);
+        // Assert
+        let rows: Vec<(&str, bool)> = rows
+            .iter()
+            .map(|(row, question)| (row.as_str(), question.is_ok()))
+            .collect();
+        assert_eq!(rows, [("1", true), ("3", false), ("4", false)]);
+    }
+
+    #[test]
+    fn map_rows_are_sorted_by_key() {
+        // Arrange
+        let body = r#"{
+            "10": {"id": "10", "title": "B", "content": "b"},
+            "2": {"id": "2", "title": "A", "content": "a", "tags": []},
+            "x": {"id": "x", "title": "C", "content": "c"}
+        }"#;
+        // Act
+        let rows = parse_map(body).unwrap();
+        // Assert
+        let titles: Vec<(&str, &str)> = rows
+            .iter()
+            .map(|(row, question)| {
+                (row.as_str(), question.as_ref().unwrap().title.as_str())
+            })
+            .collect();
+        assert_eq!(titles, [("2", "A"), ("10", "B"), ("x", "C")]);
+    }
+
+    #[test]
+    fn closed_rows_need_a_reason_other_than_duplicate() {
+        // Arrange
+        let rows = [
+            r#"{"title": "A", "content": "a", "status": "closed"}"#,
+            r#"{"title": "B", "content": "b", "status": "closed",
+                "close_reason": "duplicate"}"#,
+            r#"{"title": "C", "content": "c", "close_reason": "unclear"}"#,
+            r#"{"title": "D", "content": "d", "status": "closed",
+                "close_reason": "unclear"}"#,
+        ];
+        // Act
+        let errors: Vec<usize> = rows
+            .iter()
+            .map(|row| {
+                let question: ImportedQuestion =
+                    serde_json::from_str(row).unwrap();
+                question.validate().len()
+            })
+            .collect();
+        // Assert
+        assert_eq!(errors, [1, 1, 1, 0]);
+    }
+}
@@ -0,0 +1,67 @@
+use handle_errors::FieldError;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::types::reputation::ReputationKind;
+use crate::validation::{field_error, Validate};
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct Vote {
+    /// `1` for an upvote, `-1` for a downvote
+    pub value: i16,
+}
+
+impl Validate for Vote {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.value != 1 && self.value != -1 {
+            errors.push(field_error("value", "must be 1 or -1"));
+        }
+        errors
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct Score {
+    /// Sum of the votes of the post
+    pub score: i64,
+    /// Vote of the account, `0` if it didn't vote
+    pub vote: i16,
+}
+
+/// Kind of post a vote is cast on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VoteTarget {
+    Question,
+    Answer,
+}
+
+impl VoteTarget {
+    pub fn table(self) -> &'static str {
+        match self {
+            VoteTarget::Question => "question_votes",
+            VoteTarget::Answer => "answer_votes",
+        }
+    }
+
+    /// Column of the post in the vote table and the reputation ledger
+    pub fn column(self) -> &'static str {
+        match self {
+            VoteTarget::Question => "question_id",
+            VoteTarget::Answer => "answer_id",
+        }
+    }
+
+    pub fn reputation_kind(self, value: i16) -> ReputationKind {
+        match (self, value > 0) {
+            (VoteTarget::Question, true) => {
+                ReputationKind::QuestionUpvoted
+            }
+            (VoteTarget::Question, false) => {
+                ReputationKind::QuestionDownvoted
+            }
+            (VoteTarget::Answer, true) => ReputationKind::AnswerUpvoted,
+            (VoteTarget::Answer, false) => ReputationKind::AnswerDownvoted,
+        }
+    }
+}
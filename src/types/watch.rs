@@ -0,0 +1,25 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::types::question::QuestionId;
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct WatchedQuestion {
+    pub question_id: QuestionId,
+    pub title: String,
+    pub watched_on: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct WatchedTag {
+    pub tag: String,
+    pub watched_on: NaiveDateTime,
+}
+
+/// Questions and tags an account follows
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct WatchList {
+    pub questions: Vec<WatchedQuestion>,
+    pub tags: Vec<WatchedTag>,
+}
@@ -0,0 +1,104 @@
+use chrono::NaiveDateTime;
+use handle_errors::FieldError;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::events::EVENT_TYPES;
+use crate::validation::{
+    check_tags, check_text, field_error, Validate, MAX_URL_LENGTH,
+};
+use crate::webhooks::check_url;
+
+pub const MIN_SECRET_LENGTH: usize = 16;
+pub const MAX_SECRET_LENGTH: usize = 255;
+
+#[derive(
+    Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Hash, ToSchema,
+)]
+pub struct WebhookId(pub i32);
+
+/// Subscription as it is sent back to its owner, without the secret
+#[derive(Debug, Serialize, Clone, Deserialize, ToSchema)]
+pub struct Webhook {
+    pub id: WebhookId,
+    pub url: String,
+    pub event_types: Vec<String>,
+    /// Only questions with at least one of these tags trigger the webhook
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Clone, Deserialize, ToSchema)]
+pub struct NewWebhook {
+    pub url: String,
+    /// Any of `question_added`, `question_updated`, `question_deleted`
+    /// and `answer_added`
+    pub event_types: Vec<String>,
+    pub tags: Option<Vec<String>>,
+    /// Key of the HMAC-SHA256 signature of `{timestamp}.{body}` sent in
+    /// `X-Webhook-Signature`, `timestamp` being the Unix time sent in
+    /// `X-Webhook-Timestamp`. Deliveries more than 5 minutes off should
+    /// be rejected.
+    #[schema(write_only)]
+    pub secret: String,
+}
+
+impl Validate for NewWebhook {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        check_text(&mut errors, "url", &self.url, MAX_URL_LENGTH);
+        if let Err(reason) = check_url(&self.url) {
+            errors.push(field_error("url", reason));
+        }
+        if self.event_types.is_empty() {
+            errors.push(field_error("event_types", "must not be empty"));
+        }
+        for (index, event_type) in self.event_types.iter().enumerate() {
+            if !EVENT_TYPES.contains(&event_type.as_str()) {
+                errors.push(field_error(
+                    &format!("event_types[{}]", index),
+                    &format!("must be one of {}", EVENT_TYPES.join(", ")),
+                ));
+            }
+        }
+        check_tags(&mut errors, &self.tags);
+        let secret_length = self.secret.chars().count();
+        if !(MIN_SECRET_LENGTH..=MAX_SECRET_LENGTH)
+            .contains(&secret_length)
+        {
+            errors.push(field_error(
+                "secret",
+                &format!(
+                    "must be between {} and {} characters long",
+                    MIN_SECRET_LENGTH, MAX_SECRET_LENGTH
+                ),
+            ));
+        }
+        errors
+    }
+}
+
+/// Entry of the delivery log of a webhook
+#[derive(Debug, Serialize, Clone, Deserialize, ToSchema)]
+pub struct WebhookDelivery {
+    pub id: i32,
+    pub event_type: String,
+    /// `pending`, `delivered` or `failed` once every attempt failed
+    pub status: String,
+    pub attempts: i32,
+    pub last_status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_on: NaiveDateTime,
+    pub next_attempt_on: Option<NaiveDateTime>,
+    pub delivered_on: Option<NaiveDateTime>,
+}
+
+/// Delivery claimed by the worker, with what it needs to send it
+#[derive(Debug, Clone)]
+pub struct PendingDelivery {
+    pub id: i32,
+    pub url: String,
+    pub secret: String,
+    pub event_type: String,
+    pub payload: String,
+    pub attempts: i32,
+}
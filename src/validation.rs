@@ -0,0 +1,302 @@
+use handle_errors::{Error, FieldError};
+use serde::de::DeserializeOwned;
+use warp::hyper::body::Bytes;
+use warp::Filter;
+
+/// Maximum size in bytes accepted for the body of a write request
+pub const MAX_BODY_SIZE: u64 = 1024 * 128;
+/// Matches the `varchar(255)` columns of the database
+pub const MAX_TITLE_LENGTH: usize = 255;
+pub const MAX_EMAIL_LENGTH: usize = 255;
+pub const MAX_URL_LENGTH: usize = 2048;
+pub const MAX_CONTENT_LENGTH: usize = 30_000;
+pub const MAX_TAGS: usize = 5;
+pub const MAX_TAG_LENGTH: usize = 35;
+pub const MAX_TAG_DESCRIPTION_LENGTH: usize = 1000;
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+pub const MAX_PASSWORD_LENGTH: usize = 128;
+
+const MERGE_PATCH_CONTENT_TYPE: &str = "application/merge-patch+json";
+
+/// Checks the constraints of an incoming payload before it reaches
+/// a route handler
+pub trait Validate {
+    /// Returns one entry for every field that is not valid,
+    /// or an empty list if the payload can be processed
+    fn validate(&self) -> Vec<FieldError>;
+}
+
+pub fn field_error(field: &str, message: &str) -> FieldError {
+    FieldError {
+        field: field.to_string(),
+        message: message.to_string(),
+    }
+}
+
+/// Checks that a text field is not blank and doesn't exceed
+/// `max` characters
+pub fn check_text(
+    errors: &mut Vec<FieldError>,
+    field: &str,
+    value: &str,
+    max: usize,
+) {
+    if value.trim().is_empty() {
+        errors.push(field_error(field, "must not be empty"));
+    } else if value.chars().count() > max {
+        errors.push(field_error(
+            field,
+            &format!("must be at most {} characters long", max),
+        ));
+    }
+}
+
+pub fn check_tags(
+    errors: &mut Vec<FieldError>,
+    tags: &Option<Vec<String>>,
+) {
+    let tags = match tags {
+        Some(tags) => tags,
+        None => return,
+    };
+
+    if tags.len() > MAX_TAGS {
+        errors.push(field_error(
+            "tags",
+            &format!("must contain at most {} tags", MAX_TAGS),
+        ));
+    }
+    for (index, tag) in tags.iter().enumerate() {
+        let field = format!("tags[{}]", index);
+        let length = errors.len();
+        check_text(errors, &field, tag, MAX_TAG_LENGTH);
+        if errors.len() == length && normalize_tag(tag).is_empty() {
+            errors.push(field_error(
+                &field,
+                "must contain a letter or a digit",
+            ));
+        }
+    }
+}
+
+/// Slug of a tag: lowercase letters, digits and `+#.`, with
+/// whitespace, `_` and `-` runs turned into a single `-`.
+/// The backfill of the `tags` table applies the same rules.
+/// # Example
+/// `" Rust_Lang "` gives `"rust-lang"`
+pub fn normalize_tag(tag: &str) -> String {
+    let mut slug = String::new();
+    for c in tag.trim().to_lowercase().chars() {
+        if c.is_whitespace() || c == '_' || c == '-' {
+            if !slug.is_empty() && !slug.ends_with('-') {
+                slug.push('-');
+            }
+        } else if c.is_alphanumeric() || "+#.".contains(c) {
+            slug.push(c);
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+pub fn check_email(errors: &mut Vec<FieldError>, email: &str) {
+    if email.chars().count() > MAX_EMAIL_LENGTH {
+        errors.push(field_error(
+            "email",
+            &format!(
+                "must be at most {} characters long",
+                MAX_EMAIL_LENGTH
+            ),
+        ));
+    } else if !is_valid_email(email) {
+        errors
+            .push(field_error("email", "must be a valid e-mail address"));
+    }
+}
+
+/// Only catches obviously malformed addresses, the real check
+/// is being able to deliver to it
+fn is_valid_email(email: &str) -> bool {
+    if email.chars().any(char::is_whitespace) {
+        return false;
+    }
+    match email.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !domain.contains('@')
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+        }
+        None => false,
+    }
+}
+
+fn validated<T: Validate>(body: T) -> Result<T, warp::Rejection> {
+    let errors = body.validate();
+    if errors.is_empty() {
+        Ok(body)
+    } else {
+        Err(warp::reject::custom(Error::ValidationError(errors)))
+    }
+}
+
+/// Extracts a size limited JSON body and validates it
+pub fn json_body<T>(
+) -> impl Filter<Extract = (T,), Error = warp::Rejection> + Clone
+where
+    T: DeserializeOwned + Validate + Send,
+{
+    warp::body::content_length_limit(MAX_BODY_SIZE)
+        .and(warp::body::json())
+        .and_then(|body: T| async move { validated(body) })
+}
+
+/// Extracts a size limited form body and validates it
+pub fn form_body<T>(
+) -> impl Filter<Extract = (T,), Error = warp::Rejection> + Clone
+where
+    T: DeserializeOwned + Validate + Send,
+{
+    warp::body::content_length_limit(MAX_BODY_SIZE)
+        .and(warp::body::form())
+        .and_then(|body: T| async move { validated(body) })
+}
+
+/// Extracts a size limited RFC 7396 merge patch body and validates it.
+/// Plain `application/json` is accepted as well.
+pub fn merge_patch_body<T>(
+) -> impl Filter<Extract = (T,), Error = warp::Rejection> + Clone
+where
+    T: DeserializeOwned + Validate + Send,
+{
+    warp::header::optional::<String>("content-type")
+        .and(warp::body::content_length_limit(MAX_BODY_SIZE))
+        .and(warp::body::bytes())
+        .and_then(|content_type: Option<String>, body: Bytes| async move {
+            let essence = content_type
+                .as_deref()
+                .and_then(|ct| ct.split(';').next())
+                .map(|ct| ct.trim().to_ascii_lowercase());
+            match essence.as_deref() {
+                Some(MERGE_PATCH_CONTENT_TYPE)
+                | Some("application/json") => {}
+                _ => {
+                    return Err(warp::reject::custom(
+                        Error::UnsupportedMediaType(
+                            MERGE_PATCH_CONTENT_TYPE.to_string(),
+                        ),
+                    ))
+                }
+            }
+            let body =
+                serde_json::from_slice::<T>(&body).map_err(|e| {
+                    warp::reject::custom(Error::InvalidBody(e.to_string()))
+                })?;
+            validated(body)
+        })
+}
+
+#[cfg(test)]
+mod validation_tests {
+    use super::*;
+    use crate::types::account::Account;
+    use crate::types::question::NewQuestion;
+
+    #[test]
+    fn valid_question() {
+        // Arrange
+        let question = NewQuestion {
+            title: "How?".to_string(),
+            content: "Please help!".to_string(),
+            tags: Some(vec!["general".to_string()]),
+        };
+        // Act
+        let errors = question.validate();
+        // Assert
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn empty_and_oversized_question_fields() {
+        // Arrange
+        let question = NewQuestion {
+            title: " ".to_string(),
+            content: "a".repeat(MAX_CONTENT_LENGTH + 1),
+            tags: None,
+        };
+        let expected = vec![
+            field_error("title", "must not be empty"),
+            field_error(
+                "content",
+                "must be at most 30000 characters long",
+            ),
+        ];
+        // Act
+        let errors = question.validate();
+        // Assert
+        assert_eq!(errors, expected);
+    }
+
+    #[test]
+    fn tags_are_normalized_to_slugs() {
+        // Arrange
+        let tags = [" Rust_Lang ", "C++", "a - ! - b", "!!!"];
+        // Act
+        let slugs = tags.map(normalize_tag);
+        // Assert
+        assert_eq!(slugs, ["rust-lang", "c++", "a-b", ""]);
+    }
+
+    #[test]
+    fn malformed_email() {
+        // Arrange
+        let account = Account {
+            id: None,
+            email: "not an email".to_string(),
+            password: "long enough password".to_string(),
+            username: None,
+        };
+        let expected =
+            vec![field_error("email", "must be a valid e-mail address")];
+        // Act
+        let errors = account.validate();
+        // Assert
+        assert_eq!(errors, expected);
+    }
+
+    #[tokio::test]
+    async fn json_body_rejects_invalid_payload() {
+        // Arrange
+        let filter = json_body::<NewQuestion>();
+        // Act
+        let res = warp::test::request()
+            .method("POST")
+            .json(&serde_json::json!({ "title": "", "content": "text" }))
+            .filter(&filter)
+            .await;
+        // Assert
+        let rejection = res.unwrap_err();
+        assert!(matches!(
+            rejection.find::<Error>(),
+            Some(Error::ValidationError(errors)) if errors.len() == 1
+        ));
+    }
+
+    #[tokio::test]
+    async fn json_body_rejects_oversized_payload() {
+        // Arrange
+        let filter = json_body::<NewQuestion>();
+        let content = "a".repeat(MAX_BODY_SIZE as usize);
+        // Act
+        let res = warp::test::request()
+            .method("POST")
+            .json(&serde_json::json!({ "title": "t", "content": content }))
+            .filter(&filter)
+            .await;
+        // Assert
+        assert!(res
+            .unwrap_err()
+            .find::<warp::reject::PayloadTooLarge>()
+            .is_some());
+    }
+}
@@ -0,0 +1,376 @@
+use futures_util::future::join_all;
+use hmac::{Hmac, Mac};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::Url;
+use sha2::Sha256;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{event, Level};
+
+use crate::events::Event;
+use crate::store::Store;
+use crate::types::webhook::PendingDelivery;
+
+/// Deliveries sent at once by the worker
+const BATCH_SIZE: i64 = 20;
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// A claimed delivery is attempted again after this delay if the
+/// worker stops before recording the outcome
+const LEASE: Duration = Duration::from_secs(60);
+pub const MAX_ATTEMPTS: i32 = 8;
+const BASE_RETRY_DELAY: Duration = Duration::from_secs(30);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Queues the deliveries of an event, called by the route handlers
+/// along with `EventBus::publish`
+pub async fn enqueue(store: &Store, event: &Event) {
+    let payload = match serde_json::to_string(event) {
+        Ok(payload) => payload,
+        Err(e) => {
+            event!(Level::ERROR, "{:?}", e);
+            return;
+        }
+    };
+    // Looked up for answers, a deleted question is gone already
+    let tags = match event {
+        Event::QuestionAdded { question }
+        | Event::QuestionUpdated { question } => {
+            Some(question.tags.clone().unwrap_or_default())
+        }
+        Event::QuestionDeleted { tags, .. } => Some(tags.clone()),
+        Event::AnswerAdded { .. } => None,
+    };
+    // The store already logs the error
+    let _ = store
+        .enqueue_webhook_deliveries(
+            event.name(),
+            &payload,
+            event.question_id(),
+            tags,
+        )
+        .await;
+}
+
+/// Value of the `X-Webhook-Signature` header, the HMAC-SHA256 of
+/// `{timestamp}.{body}` with the secret of the webhook, `timestamp`
+/// being the value of the `X-Webhook-Timestamp` header. Receivers
+/// should reject deliveries more than 5 minutes off their clock, so
+/// that a captured delivery can't be replayed later.
+pub fn sign(secret: &str, timestamp: i64, payload: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC takes keys of any size");
+    mac.update(format!("{}.{}", timestamp, payload).as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Whether deliveries may be sent to the address. Loopback, private,
+/// link-local, unspecified and other non-public addresses would let a
+/// webhook reach into the internal network.
+pub fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_v4(ip),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    // Unique local fc00::/7 and link-local fe80::/10
+                    || first & 0xfe00 == 0xfc00
+                    || first & 0xffc0 == 0xfe80)
+            }
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, _, _] = ip.octets();
+    !(ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        // "This network" 0.0.0.0/8 and shared address space 100.64/10
+        || a == 0
+        || (a == 100 && b & 0xc0 == 64))
+}
+
+/// Checks the URL of a webhook, its host can't be `localhost` or a
+/// non-public address. Host names are checked again once resolved.
+pub fn check_url(url: &str) -> Result<(), &'static str> {
+    let url = Url::parse(url).map_err(|_| "must be an http(s) URL")?;
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err("must be an http(s) URL");
+    }
+    let host = url.host_str().ok_or("must be an http(s) URL")?;
+    // IPv6 addresses are kept in brackets
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let public = match host.parse::<IpAddr>() {
+        Ok(ip) => is_public_address(ip),
+        Err(_) => {
+            let domain = host.trim_end_matches('.').to_lowercase();
+            domain != "localhost" && !domain.ends_with(".localhost")
+        }
+    };
+    if public {
+        Ok(())
+    } else {
+        Err("must not point to a private or local address")
+    }
+}
+
+/// Resolves the hosts of the deliveries without their non-public
+/// addresses, so that a DNS record can't point a webhook at the
+/// internal network. The connection uses the checked addresses.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((name.as_str(), 0))
+                    .await?
+                    .filter(|addr| is_public_address(addr.ip()))
+                    .collect();
+            if addrs.is_empty() {
+                return Err(format!(
+                    "{} has no public address",
+                    name.as_str()
+                )
+                .into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Exponential backoff after the given number of attempts,
+/// `None` once the last attempt failed
+pub fn retry_delay(attempts: i32) -> Option<Duration> {
+    if attempts >= MAX_ATTEMPTS {
+        return None;
+    }
+    let factor = 2u32.saturating_pow(attempts.max(1) as u32 - 1);
+    Some(BASE_RETRY_DELAY.saturating_mul(factor).min(MAX_RETRY_DELAY))
+}
+
+/// Posts the payload of a delivery, returning the status code of a
+/// successful response, or the status code and the reason of a failure
+pub async fn send(
+    client: &reqwest::Client,
+    delivery: &PendingDelivery,
+) -> Result<u16, (Option<u16>, String)> {
+    let timestamp = chrono::Utc::now().timestamp();
+    let res = client
+        .post(&delivery.url)
+        .header("content-type", "application/json")
+        .header("x-webhook-event", &delivery.event_type)
+        .header("x-webhook-delivery", delivery.id.to_string())
+        .header("x-webhook-timestamp", timestamp.to_string())
+        .header(
+            "x-webhook-signature",
+            sign(&delivery.secret, timestamp, &delivery.payload),
+        )
+        .body(delivery.payload.clone())
+        .send()
+        .await
+        .map_err(|e| (None, e.to_string()))?;
+
+    let status = res.status();
+    if status.is_success() {
+        Ok(status.as_u16())
+    } else {
+        Err((
+            Some(status.as_u16()),
+            format!("Unexpected status {}", status),
+        ))
+    }
+}
+
+async fn attempt(
+    store: &Store,
+    client: &reqwest::Client,
+    delivery: PendingDelivery,
+) {
+    // Addresses in the URL skip the resolver, they are checked here.
+    // Checked on registration too, for webhooks from before the check.
+    if let Err(reason) = check_url(&delivery.url) {
+        let _ = store
+            .finish_webhook_delivery(
+                delivery.id,
+                None,
+                Some(format!("The URL {}", reason)),
+                None,
+            )
+            .await;
+        return;
+    }
+    let (status_code, error, retry_in) =
+        match send(client, &delivery).await {
+            Ok(status_code) => (Some(status_code), None, None),
+            Err((status_code, error)) => {
+                event!(
+                    Level::WARN,
+                    delivery = delivery.id,
+                    attempts = delivery.attempts,
+                    error
+                );
+                let retry_in = retry_delay(delivery.attempts)
+                    .map(|delay| delay.as_secs_f64());
+                (status_code, Some(error), retry_in)
+            }
+        };
+    // The store already logs the error, the lease retries it later
+    let _ = store
+        .finish_webhook_delivery(
+            delivery.id,
+            status_code.map(i32::from),
+            error,
+            retry_in,
+        )
+        .await;
+}
+
+/// Sends the queued deliveries, runs until the server stops.
+/// Every instance can run one, a delivery is only claimed by one of them.
+pub async fn run_worker(store: Store) {
+    let client = match reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        // A redirect could lead to an address the resolver didn't check
+        .redirect(reqwest::redirect::Policy::none())
+        .no_proxy()
+        .dns_resolver(Arc::new(PublicResolver))
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            event!(Level::ERROR, "{:?}", e);
+            return;
+        }
+    };
+
+    loop {
+        let deliveries = store
+            .claim_webhook_deliveries(BATCH_SIZE, LEASE.as_secs_f64())
+            .await
+            .unwrap_or_default();
+        let claimed = deliveries.len() as i64;
+        join_all(
+            deliveries
+                .into_iter()
+                .map(|delivery| attempt(&store, &client, delivery)),
+        )
+        .await;
+
+        // Keep going while there is a backlog
+        if claimed < BATCH_SIZE {
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod webhooks_tests {
+    use super::*;
+    use mock_server::MockServer;
+
+    fn delivery(url: String) -> PendingDelivery {
+        PendingDelivery {
+            id: 7,
+            url,
+            secret: "0123456789abcdef".to_string(),
+            event_type: "question_added".to_string(),
+            payload: r#"{"type":"question_added"}"#.to_string(),
+            attempts: 1,
+        }
+    }
+
+    #[test]
+    fn retry_delay_backs_off_exponentially() {
+        // Arrange
+        let attempts = [1, 2, 3, MAX_ATTEMPTS];
+        // Act
+        let delays = attempts.map(retry_delay);
+        // Assert
+        assert_eq!(delays[0], Some(Duration::from_secs(30)));
+        assert_eq!(delays[1], Some(Duration::from_secs(60)));
+        assert_eq!(delays[2], Some(Duration::from_secs(120)));
+        assert_eq!(delays[3], None);
+    }
+
+    #[tokio::test]
+    async fn signed_delivery_reaches_receiver() {
+        // Arrange
+        let socket =
+            "127.0.0.1:3031".parse().expect("Not a valid address");
+        let mut handler = MockServer::new(socket).webhook_receiver();
+        let client = reqwest::Client::new();
+        let ok =
+            delivery("http://127.0.0.1:3031/webhooks/200".to_string());
+        let failing =
+            delivery("http://127.0.0.1:3031/webhooks/503".to_string());
+        // Act
+        let ok_res = send(&client, &ok).await;
+        let failing_res = send(&client, &failing).await;
+        let received = handler.received.recv().await.unwrap();
+        let _ = handler.sender.send(1);
+        // Assert
+        assert_eq!(ok_res, Ok(200));
+        assert_eq!(failing_res.unwrap_err().0, Some(503));
+        assert_eq!(received.body, ok.payload);
+        assert_eq!(received.headers["x-webhook-event"], "question_added");
+        assert_eq!(received.headers["x-webhook-delivery"], "7");
+        let timestamp: i64 =
+            received.headers["x-webhook-timestamp"].parse().unwrap();
+        assert!((chrono::Utc::now().timestamp() - timestamp).abs() < 60);
+        assert_eq!(
+            received.headers["x-webhook-signature"],
+            sign(&ok.secret, timestamp, &ok.payload)
+        );
+    }
+
+    #[tokio::test]
+    async fn resolver_drops_internal_addresses() {
+        // Arrange
+        let name: Name = "localhost".parse().unwrap();
+        // Act
+        let addrs = PublicResolver.resolve(name).await;
+        // Assert
+        assert!(addrs.is_err());
+    }
+
+    #[test]
+    fn internal_urls_are_rejected() {
+        // Arrange
+        let urls = [
+            "https://example.com/hook",
+            "http://93.184.215.14:8080/hook",
+            "http://localhost:3030/hook",
+            "http://api.localhost/hook",
+            "http://127.0.0.1/hook",
+            "http://10.1.2.3/hook",
+            "http://172.16.0.1/hook",
+            "http://192.168.1.1/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://0.0.0.0/hook",
+            "http://[::1]/hook",
+            "http://[fd00::1]/hook",
+            "http://[::ffff:127.0.0.1]/hook",
+            "ftp://example.com/hook",
+        ];
+        // Act
+        let allowed = urls.map(|url| check_url(url).is_ok());
+        // Assert
+        assert_eq!(
+            allowed,
+            [
+                true, true, false, false, false, false, false, false,
+                false, false, false, false, false, false
+            ]
+        );
+    }
+}